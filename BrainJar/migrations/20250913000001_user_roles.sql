@@ -0,0 +1,14 @@
+-- Role-based access control and account suspension
+-- Promote the first admin by hand: UPDATE users SET role = 'admin' WHERE email = '...';
+
+DO $$ BEGIN
+    CREATE TYPE user_role AS ENUM ('user', 'moderator', 'admin');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role user_role NOT NULL DEFAULT 'user';
+ALTER TABLE users ADD COLUMN IF NOT EXISTS suspended_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS suspension_reason TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_users_role ON users(role) WHERE role <> 'user';
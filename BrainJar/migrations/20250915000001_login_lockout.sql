@@ -0,0 +1,4 @@
+-- Progressive lockout after repeated failed logins (wrong password or second factor)
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS failed_login_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS locked_until TIMESTAMPTZ;
@@ -0,0 +1,17 @@
+-- Up/down votes on solutions and the one solution a problem's author accepted
+
+CREATE TABLE IF NOT EXISTS solution_votes (
+    solution_id UUID NOT NULL REFERENCES problem_solutions(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    value SMALLINT NOT NULL CHECK (value IN (-1, 1)),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (solution_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_solution_votes_user ON solution_votes(user_id);
+
+ALTER TABLE problem_solutions ADD COLUMN IF NOT EXISTS is_accepted BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- At most one accepted solution per problem
+CREATE UNIQUE INDEX IF NOT EXISTS idx_problem_solutions_accepted
+    ON problem_solutions(problem_id) WHERE is_accepted;
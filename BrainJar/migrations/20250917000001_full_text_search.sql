@@ -0,0 +1,45 @@
+-- Full-text search over problems, solutions and resources (see repository/search.rs).
+-- Titles and tags weigh more than body text.
+
+-- array_to_string isn't immutable, so problems keep their vector up to date with a trigger
+ALTER TABLE problems ADD COLUMN IF NOT EXISTS tags TEXT[];
+ALTER TABLE problems ADD COLUMN IF NOT EXISTS search_vector tsvector;
+
+CREATE OR REPLACE FUNCTION problems_search_vector_update()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_vector :=
+        setweight(to_tsvector('english', COALESCE(NEW.title, '')), 'A') ||
+        setweight(to_tsvector('english', COALESCE(array_to_string(NEW.tags, ' '), '')), 'A') ||
+        setweight(to_tsvector('english', COALESCE(NEW.description, '')), 'B');
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_problems_search_vector ON problems;
+CREATE TRIGGER trigger_problems_search_vector
+    BEFORE INSERT OR UPDATE OF title, description, tags ON problems
+    FOR EACH ROW
+    EXECUTE FUNCTION problems_search_vector_update();
+
+-- Backfill existing rows through the trigger. trigger_update_user_stats (20250825) reads
+-- problems.status, which databases created after that migration don't have, so it's
+-- switched off for the backfill.
+ALTER TABLE problems DISABLE TRIGGER trigger_update_user_stats;
+UPDATE problems SET title = title;
+ALTER TABLE problems ENABLE TRIGGER trigger_update_user_stats;
+
+CREATE INDEX IF NOT EXISTS idx_problems_search ON problems USING GIN (search_vector);
+
+ALTER TABLE problem_solutions ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (to_tsvector('english', solution_text)) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_problem_solutions_search ON problem_solutions USING GIN (search_vector);
+
+ALTER TABLE problem_resources ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', title), 'A') ||
+        setweight(to_tsvector('english', COALESCE(description, '')), 'B')
+    ) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_problem_resources_search ON problem_resources USING GIN (search_vector);
@@ -0,0 +1,102 @@
+-- One problem model for every module. The initial schema only had the core columns;
+-- the rest were added by hand on some databases, and the enhanced schema assumed
+-- `difficulty`/`status` columns that never existed. Problems carry difficulty_level and
+-- solved, plus updated_at.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS avatar_url TEXT;
+
+ALTER TABLE problems ADD COLUMN IF NOT EXISTS documentation_links TEXT[];
+ALTER TABLE problems ADD COLUMN IF NOT EXISTS video_references TEXT[];
+ALTER TABLE problems ADD COLUMN IF NOT EXISTS difficulty_level VARCHAR(20);
+ALTER TABLE problems ADD COLUMN IF NOT EXISTS tags TEXT[];
+ALTER TABLE problems ADD COLUMN IF NOT EXISTS solved BOOLEAN DEFAULT FALSE;
+ALTER TABLE problems ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ;
+
+-- The stats trigger read NEW.status, which breaks every write on
+-- databases without it, including the updates below
+CREATE OR REPLACE FUNCTION update_user_stats()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.solved AND (TG_OP = 'INSERT' OR OLD.solved IS DISTINCT FROM TRUE) THEN
+        UPDATE users
+        SET total_problems_solved = total_problems_solved + 1,
+            last_activity = NOW()
+        WHERE id = NEW.user_id;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Fold the legacy columns in where a database has them
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM information_schema.columns
+               WHERE table_name = 'problems' AND column_name = 'difficulty') THEN
+        UPDATE problems SET difficulty_level = COALESCE(difficulty_level, difficulty);
+        ALTER TABLE problems DROP COLUMN difficulty;
+    END IF;
+
+    IF EXISTS (SELECT 1 FROM information_schema.columns
+               WHERE table_name = 'problems' AND column_name = 'status') THEN
+        -- Already counted in users.total_problems_solved when they were completed
+        ALTER TABLE problems DISABLE TRIGGER trigger_update_user_stats;
+        UPDATE problems SET solved = TRUE WHERE status = 'completed';
+        ALTER TABLE problems ENABLE TRIGGER trigger_update_user_stats;
+        ALTER TABLE problems DROP COLUMN status;
+    END IF;
+END $$;
+
+UPDATE problems SET solved = FALSE WHERE solved IS NULL;
+ALTER TABLE problems ALTER COLUMN solved SET NOT NULL;
+
+UPDATE problems SET updated_at = created_at WHERE updated_at IS NULL;
+ALTER TABLE problems ALTER COLUMN updated_at SET DEFAULT NOW();
+ALTER TABLE problems ALTER COLUMN updated_at SET NOT NULL;
+
+CREATE OR REPLACE FUNCTION problems_touch_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at := NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_problems_updated_at ON problems;
+CREATE TRIGGER trigger_problems_updated_at
+    BEFORE UPDATE ON problems
+    FOR EACH ROW
+    EXECUTE FUNCTION problems_touch_updated_at();
+
+-- Problems name their category in problems.category; link it to the catalog so
+-- category lookups only need the mappings table
+INSERT INTO problem_category_mappings (problem_id, category_id)
+SELECT p.id, c.id
+FROM problems p
+JOIN problem_categories c ON LOWER(c.name) = LOWER(p.category)
+ON CONFLICT DO NOTHING;
+
+CREATE OR REPLACE FUNCTION problems_map_category()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' AND OLD.category IS DISTINCT FROM NEW.category THEN
+        DELETE FROM problem_category_mappings m
+        USING problem_categories c
+        WHERE m.problem_id = NEW.id AND m.category_id = c.id AND LOWER(c.name) = LOWER(OLD.category);
+    END IF;
+
+    INSERT INTO problem_category_mappings (problem_id, category_id)
+    SELECT NEW.id, c.id FROM problem_categories c WHERE LOWER(c.name) = LOWER(NEW.category)
+    ON CONFLICT DO NOTHING;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_problems_map_category ON problems;
+CREATE TRIGGER trigger_problems_map_category
+    AFTER INSERT OR UPDATE OF category ON problems
+    FOR EACH ROW
+    EXECUTE FUNCTION problems_map_category();
+
+UPDATE problem_resources SET is_recommended = FALSE WHERE is_recommended IS NULL;
+ALTER TABLE problem_resources ALTER COLUMN is_recommended SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_problem_category_mappings_category ON problem_category_mappings(category_id);
@@ -0,0 +1,23 @@
+-- Edit history of problems. Every revision is a full snapshot of the editable fields, so
+-- any two can be diffed and any one restored. Revision 1 is the problem as created.
+CREATE TABLE IF NOT EXISTS problem_revisions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    problem_id UUID NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id),
+    title VARCHAR(255) NOT NULL,
+    description TEXT NOT NULL,
+    category VARCHAR(50) NOT NULL,
+    -- Which of title, description and category differ from the previous revision
+    changed_fields TEXT[] NOT NULL,
+    -- Set when the owner restored an earlier revision
+    restored_from INTEGER,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (problem_id, revision)
+);
+
+-- Existing problems start their history with their current content
+INSERT INTO problem_revisions (problem_id, revision, user_id, title, description, category, changed_fields, created_at)
+SELECT id, 1, user_id, title, description, category, ARRAY['title', 'description', 'category'], created_at
+FROM problems
+ON CONFLICT (problem_id, revision) DO NOTHING;
@@ -0,0 +1,5 @@
+-- Who can see a problem. Owners always see their own; `friends` also shows it to
+-- anyone in the owner's friends table. Existing problems stay public.
+CREATE TYPE problem_visibility AS ENUM ('private', 'friends', 'public');
+
+ALTER TABLE problems ADD COLUMN IF NOT EXISTS visibility problem_visibility NOT NULL DEFAULT 'public';
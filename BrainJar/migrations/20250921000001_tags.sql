@@ -0,0 +1,53 @@
+-- Normalized tags. problem_tags is the source of truth; problems.tags keeps a copy of the
+-- names for the search vector and listing filters, written alongside it.
+CREATE TABLE IF NOT EXISTS tags (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- Lowercase, words joined with '-' (see models/tag.rs)
+    name VARCHAR(50) NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Other spellings of a tag, e.g. `js` for `javascript`. Also left behind by merges.
+CREATE TABLE IF NOT EXISTS tag_aliases (
+    alias VARCHAR(50) PRIMARY KEY,
+    tag_id UUID NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS problem_tags (
+    problem_id UUID NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    tag_id UUID NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (problem_id, tag_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_problem_tags_tag ON problem_tags(tag_id);
+CREATE INDEX IF NOT EXISTS idx_tags_name_prefix ON tags(name text_pattern_ops);
+
+-- Bring existing free-form tags into the table, normalized the same way as the API does
+CREATE TEMPORARY TABLE normalized_tags AS
+SELECT DISTINCT p.id AS problem_id,
+       TRIM(BOTH '-' FROM LEFT(REGEXP_REPLACE(LOWER(TRIM(tag)), '[^a-z0-9+#.]+', '-', 'g'), 50)) AS name
+FROM problems p, UNNEST(p.tags) AS tag;
+
+INSERT INTO tags (name)
+SELECT DISTINCT name FROM normalized_tags WHERE name <> ''
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO problem_tags (problem_id, tag_id)
+SELECT n.problem_id, t.id
+FROM normalized_tags n
+JOIN tags t ON t.name = n.name
+ON CONFLICT DO NOTHING;
+
+DROP TABLE normalized_tags;
+
+-- Rewriting the copies isn't an edit, so leave updated_at alone
+ALTER TABLE problems DISABLE TRIGGER trigger_problems_updated_at;
+UPDATE problems p
+SET tags = (
+    SELECT COALESCE(ARRAY_AGG(t.name ORDER BY t.name), '{}')
+    FROM problem_tags pt JOIN tags t ON t.id = pt.tag_id
+    WHERE pt.problem_id = p.id
+)
+WHERE p.tags IS NOT NULL;
+ALTER TABLE problems ENABLE TRIGGER trigger_problems_updated_at;
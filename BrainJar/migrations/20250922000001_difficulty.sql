@@ -0,0 +1,32 @@
+-- One difficulty scale for problems and resources. Problems used Easy/Medium/Hard and
+-- resources beginner/intermediate/advanced; both map onto easy/medium/hard, any other
+-- value is dropped to NULL.
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'difficulty') THEN
+        CREATE TYPE difficulty AS ENUM ('easy', 'medium', 'hard');
+    END IF;
+END $$;
+
+CREATE OR REPLACE FUNCTION to_difficulty(level TEXT)
+RETURNS difficulty AS $$
+    SELECT CASE LOWER(TRIM(level))
+        WHEN 'easy' THEN 'easy'
+        WHEN 'beginner' THEN 'easy'
+        WHEN 'medium' THEN 'medium'
+        WHEN 'intermediate' THEN 'medium'
+        WHEN 'hard' THEN 'hard'
+        WHEN 'advanced' THEN 'hard'
+    END::difficulty;
+$$ LANGUAGE sql IMMUTABLE;
+
+ALTER TABLE problems
+    ALTER COLUMN difficulty_level TYPE difficulty USING to_difficulty(difficulty_level::text);
+
+ALTER TABLE problem_resources DROP CONSTRAINT IF EXISTS problem_resources_difficulty_level_check;
+ALTER TABLE problem_resources
+    ALTER COLUMN difficulty_level TYPE difficulty USING to_difficulty(difficulty_level::text);
+
+DROP FUNCTION to_difficulty(TEXT);
+
+CREATE INDEX IF NOT EXISTS idx_problems_user_difficulty ON problems(user_id, difficulty_level);
@@ -0,0 +1,9 @@
+-- IANA time zone each user's streak days are counted in. Existing users keep the UTC
+-- days they've been counted in so far.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS timezone VARCHAR(64);
+
+UPDATE users SET timezone = 'UTC' WHERE timezone IS NULL;
+
+ALTER TABLE users
+    ALTER COLUMN timezone SET DEFAULT 'UTC',
+    ALTER COLUMN timezone SET NOT NULL;
@@ -0,0 +1,18 @@
+-- Streak freezes cover a missed day so the streak carries on. They're earned at streak
+-- milestones or credited after a purchase, and used up automatically by the next solve
+-- after a gap.
+CREATE TYPE streak_freeze_kind AS ENUM ('earned', 'purchased', 'used');
+
+ALTER TABLE streaks ADD COLUMN IF NOT EXISTS freezes_available INTEGER NOT NULL DEFAULT 0
+    CHECK (freezes_available >= 0);
+
+CREATE TABLE IF NOT EXISTS streak_freeze_events (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind streak_freeze_kind NOT NULL,
+    -- For `used`, the missed day (in the user's timezone) the freeze covered
+    day DATE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_streak_freeze_events_user ON streak_freeze_events (user_id, created_at DESC);
@@ -0,0 +1,50 @@
+-- One row per user per day (in the user's timezone) counting what they did. Streaks and
+-- the contribution heatmap are read from here, so they can be recomputed at any time.
+CREATE TABLE IF NOT EXISTS daily_activity (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    problems_created INTEGER NOT NULL DEFAULT 0,
+    problems_solved INTEGER NOT NULL DEFAULT 0,
+    solutions_posted INTEGER NOT NULL DEFAULT 0,
+    feedback_given INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (user_id, day)
+);
+
+-- Backfill from what's already recorded, bucketed like streak_day(): the local date two
+-- hours (the default streaks.grace_hours) before the event. Run the recompute afterwards
+-- if grace_hours is configured differently.
+--
+-- A solve is an accepted solution to someone else's problem. Acceptance isn't timestamped,
+-- so it counts on the day the solution was posted. Feedback times were stored without a
+-- zone and are taken as UTC.
+INSERT INTO daily_activity (user_id, day, problems_created, problems_solved, solutions_posted, feedback_given)
+SELECT e.user_id, DATE((e.at - INTERVAL '2 hours') AT TIME ZONE u.timezone) AS day,
+       COUNT(*) FILTER (WHERE e.kind = 'created'),
+       COUNT(DISTINCT e.problem_id) FILTER (WHERE e.kind = 'solved'),
+       COUNT(*) FILTER (WHERE e.kind = 'posted'),
+       COUNT(*) FILTER (WHERE e.kind = 'feedback')
+FROM (
+    SELECT s.user_id, s.problem_id, s.created_at AS at, 'solved' AS kind
+    FROM problem_solutions s
+    JOIN problems p ON p.id = s.problem_id
+    WHERE s.is_accepted AND p.user_id <> s.user_id AND s.created_at IS NOT NULL
+
+    UNION ALL
+
+    SELECT user_id, id, created_at, 'created' FROM problems
+
+    UNION ALL
+
+    SELECT user_id, problem_id, created_at, 'posted'
+    FROM problem_solutions
+    WHERE created_at IS NOT NULL
+
+    UNION ALL
+
+    SELECT user_id, problem_id, created_at AT TIME ZONE 'UTC', 'feedback'
+    FROM problem_feedback
+    WHERE created_at IS NOT NULL
+) e
+JOIN users u ON u.id = e.user_id
+GROUP BY e.user_id, day
+ON CONFLICT (user_id, day) DO NOTHING;
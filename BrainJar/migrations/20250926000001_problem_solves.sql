@@ -0,0 +1,18 @@
+-- The first time each user had a solution to each problem accepted. Only a new row here
+-- advances a streak, so re-accepting a solution (or accepting another of theirs) can't
+-- inflate it.
+CREATE TABLE IF NOT EXISTS problem_solves (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    problem_id UUID NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    solved_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, problem_id)
+);
+
+-- Accepted solutions to other users' problems, dated by when they were posted as in the
+-- daily_activity backfill
+INSERT INTO problem_solves (user_id, problem_id, solved_at)
+SELECT s.user_id, s.problem_id, s.created_at
+FROM problem_solutions s
+JOIN problems p ON p.id = s.problem_id
+WHERE s.is_accepted AND p.user_id <> s.user_id AND s.created_at IS NOT NULL
+ON CONFLICT (user_id, problem_id) DO NOTHING;
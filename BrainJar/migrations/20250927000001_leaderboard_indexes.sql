@@ -0,0 +1,5 @@
+-- Leaderboards aggregate every user's activity over a window, so index by time rather
+-- than by user for the rows each metric counts
+CREATE INDEX IF NOT EXISTS idx_daily_activity_solve_day ON daily_activity(day) WHERE problems_solved > 0;
+CREATE INDEX IF NOT EXISTS idx_problem_solutions_accepted_at ON problem_solutions(created_at) WHERE is_accepted;
+CREATE INDEX IF NOT EXISTS idx_problem_feedback_helpful ON problem_feedback(created_at) WHERE is_helpful;
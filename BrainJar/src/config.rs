@@ -0,0 +1,343 @@
+use chrono::Duration;
+use config::{Config, Environment as EnvSource, File, FileFormat};
+use serde::Deserialize;
+
+use crate::rate_limit::RateLimitConfig;
+
+// Used when no config file is given explicitly with BRAINJAR_CONFIG; missing is fine
+const DEFAULT_CONFIG_FILE: &str = "config/brainjar.toml";
+// Development-only fallback. Production refuses to start with it, with the literal that
+// older builds fell back to, or with anything short.
+const DEV_JWT_SECRET: &str = "brainjar_dev_secret_do_not_use_in_production";
+const KNOWN_INSECURE_SECRETS: &[&str] = &[DEV_JWT_SECRET, "brainjar_secret_key_2025"];
+const MIN_PRODUCTION_SECRET_LENGTH: usize = 32;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("failed to load configuration: {0}")]
+    Load(#[from] config::ConfigError),
+    #[error("invalid configuration:\n  - {}", .0.join("\n  - "))]
+    Invalid(Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    Development,
+    Production,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub environment: Environment,
+    // Frontend origin used to build links in emails
+    pub app_base_url: String,
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub jwt: JwtConfig,
+    pub cors: CorsConfig,
+    pub tokens: TokenConfig,
+    pub mail: MailConfig,
+    pub rate_limit: RateLimitConfig,
+    pub streaks: StreakConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    // Defaults to the number of CPU cores when unset
+    pub workers: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseBackend {
+    Postgres,
+    // Everything in process, lost on restart (see repository/mod.rs)
+    Memory,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    pub backend: DatabaseBackend,
+    pub url: Option<String>,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout_seconds: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct JwtConfig {
+    // HS256 shared secret, used only when keys_dir is unset
+    pub secret: String,
+    // Directory of <kid>.pub.pem / <kid>.key.pem pairs for RS256/EdDSA signing (see jwt.rs)
+    pub keys_dir: Option<String>,
+    pub signing_kid: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CorsConfig {
+    // Exact origins, or ["*"] to allow any origin (development only)
+    pub allowed_origins: Vec<String>,
+    pub max_age_seconds: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TokenConfig {
+    pub access_token_ttl_minutes: i64,
+    pub mfa_token_ttl_minutes: i64,
+    pub refresh_token_ttl_days: i64,
+    pub password_reset_ttl_minutes: i64,
+    pub email_verification_ttl_hours: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MailConfig {
+    // smtp, file or memory
+    pub transport: String,
+    pub from: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub outbox_dir: String,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct StreakConfig {
+    // Streak days end this many hours after local midnight, so a late-night solve still
+    // counts for the day before
+    pub grace_hours: i64,
+    // A freeze is earned each time the streak reaches a multiple of this many days
+    pub freeze_every_days: i32,
+    // No freeze is earned while this many are available. Purchased ones can go past it.
+    pub max_earned_freezes: i32,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            environment: Environment::Development,
+            app_base_url: "http://localhost:3000".to_string(),
+            server: ServerConfig::default(),
+            database: DatabaseConfig::default(),
+            jwt: JwtConfig::default(),
+            cors: CorsConfig::default(),
+            tokens: TokenConfig::default(),
+            mail: MailConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            streaks: StreakConfig::default(),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            workers: None,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            backend: DatabaseBackend::Postgres,
+            url: None,
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout_seconds: 30,
+        }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        CorsConfig {
+            allowed_origins: vec!["*".to_string()],
+            max_age_seconds: 3600,
+        }
+    }
+}
+
+impl Default for TokenConfig {
+    fn default() -> Self {
+        TokenConfig {
+            access_token_ttl_minutes: 15,
+            mfa_token_ttl_minutes: 5,
+            refresh_token_ttl_days: 30,
+            password_reset_ttl_minutes: 60,
+            email_verification_ttl_hours: 72,
+        }
+    }
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        MailConfig {
+            transport: "memory".to_string(),
+            from: "BrainJar <no-reply@brainjar.local>".to_string(),
+            smtp_host: "localhost".to_string(),
+            smtp_port: 587,
+            smtp_username: None,
+            smtp_password: None,
+            outbox_dir: "mail_outbox".to_string(),
+        }
+    }
+}
+
+impl Default for StreakConfig {
+    fn default() -> Self {
+        StreakConfig {
+            grace_hours: 2,
+            freeze_every_days: 7,
+            max_earned_freezes: 2,
+        }
+    }
+}
+
+impl CorsConfig {
+    pub fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|origin| origin == "*")
+    }
+}
+
+impl TokenConfig {
+    pub fn access_token_ttl(&self) -> Duration {
+        Duration::minutes(self.access_token_ttl_minutes)
+    }
+
+    pub fn mfa_token_ttl(&self) -> Duration {
+        Duration::minutes(self.mfa_token_ttl_minutes)
+    }
+
+    pub fn refresh_token_ttl(&self) -> Duration {
+        Duration::days(self.refresh_token_ttl_days)
+    }
+}
+
+impl AppConfig {
+    // Layers, later ones winning: built-in defaults, the TOML file, BRAINJAR_* environment
+    // variables (nested keys separated by `__`, e.g. BRAINJAR_SERVER__PORT=9000), and
+    // finally the long-standing DATABASE_URL and JWT_SECRET variables.
+    pub fn load() -> Result<AppConfig, ConfigError> {
+        let explicit_file = std::env::var("BRAINJAR_CONFIG").ok();
+        let file = explicit_file.clone().unwrap_or_else(|| DEFAULT_CONFIG_FILE.to_string());
+
+        let config: AppConfig = Config::builder()
+            .add_source(File::new(&file, FileFormat::Toml).required(explicit_file.is_some()))
+            .add_source(
+                EnvSource::with_prefix("BRAINJAR")
+                    .prefix_separator("_")
+                    .separator("__")
+                    .list_separator(",")
+                    .with_list_parse_key("cors.allowed_origins")
+                    .try_parsing(true),
+            )
+            .set_override_option("database.url", std::env::var("DATABASE_URL").ok())?
+            .set_override_option("jwt.secret", std::env::var("JWT_SECRET").ok())?
+            .build()?
+            .try_deserialize()?;
+
+        config.validate()
+    }
+
+    pub fn is_production(&self) -> bool {
+        self.environment == Environment::Production
+    }
+
+    // Collect every problem at once so a bad deploy reports everything in one go
+    fn validate(mut self) -> Result<AppConfig, ConfigError> {
+        let mut errors = Vec::new();
+        let production = self.is_production();
+
+        if self.jwt.keys_dir.is_some() {
+            if self.jwt.signing_kid.is_none() {
+                errors.push("jwt.signing_kid must be set when jwt.keys_dir is".to_string());
+            }
+        } else if self.jwt.secret.is_empty() {
+            if production {
+                errors.push("jwt.keys_dir or jwt.secret (JWT_SECRET) must be set in production".to_string());
+            } else {
+                tracing::warn!("No JWT secret configured, using the development default");
+                self.jwt.secret = DEV_JWT_SECRET.to_string();
+            }
+        } else if production && KNOWN_INSECURE_SECRETS.contains(&self.jwt.secret.as_str()) {
+            errors.push("jwt.secret must not be a built-in default in production".to_string());
+        } else if production && self.jwt.secret.len() < MIN_PRODUCTION_SECRET_LENGTH {
+            errors.push(format!(
+                "jwt.secret must be at least {} characters in production",
+                MIN_PRODUCTION_SECRET_LENGTH
+            ));
+        }
+
+        if self.server.host.trim().is_empty() {
+            errors.push("server.host must not be empty".to_string());
+        }
+        if self.server.workers == Some(0) {
+            errors.push("server.workers must be at least 1".to_string());
+        }
+
+        if production && self.database.backend == DatabaseBackend::Memory {
+            errors.push("database.backend must be postgres in production".to_string());
+        }
+        if self.database.max_connections == 0 {
+            errors.push("database.max_connections must be at least 1".to_string());
+        }
+        if self.database.min_connections > self.database.max_connections {
+            errors.push("database.min_connections must not exceed database.max_connections".to_string());
+        }
+
+        if self.cors.allowed_origins.is_empty() {
+            errors.push("cors.allowed_origins must list at least one origin".to_string());
+        } else if production && self.cors.allows_any_origin() {
+            errors.push("cors.allowed_origins must list explicit origins in production".to_string());
+        }
+
+        let ttls = [
+            ("tokens.access_token_ttl_minutes", self.tokens.access_token_ttl_minutes),
+            ("tokens.mfa_token_ttl_minutes", self.tokens.mfa_token_ttl_minutes),
+            ("tokens.refresh_token_ttl_days", self.tokens.refresh_token_ttl_days),
+            ("tokens.password_reset_ttl_minutes", self.tokens.password_reset_ttl_minutes),
+            ("tokens.email_verification_ttl_hours", self.tokens.email_verification_ttl_hours),
+        ];
+        for (name, value) in ttls {
+            if value <= 0 {
+                errors.push(format!("{} must be positive", name));
+            }
+        }
+
+        if !(0..24).contains(&self.streaks.grace_hours) {
+            errors.push("streaks.grace_hours must be between 0 and 23".to_string());
+        }
+        if self.streaks.freeze_every_days < 1 {
+            errors.push("streaks.freeze_every_days must be at least 1".to_string());
+        }
+        if self.streaks.max_earned_freezes < 0 {
+            errors.push("streaks.max_earned_freezes must not be negative".to_string());
+        }
+
+        if !matches!(self.mail.transport.as_str(), "smtp" | "file" | "memory") {
+            errors.push(format!("mail.transport must be smtp, file or memory (got {})", self.mail.transport));
+        } else if production && self.mail.transport == "memory" {
+            tracing::warn!("Using the in-memory mail transport in production, emails will not be delivered");
+        }
+
+        if errors.is_empty() {
+            Ok(self)
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+}
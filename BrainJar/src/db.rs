@@ -1,12 +1,20 @@
+use std::time::Duration;
+
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 
-pub async fn create_db_pool() -> Result<PgPool, sqlx::Error> {
-    let database_url = std::env::var("DATABASE_URL")
-        .expect("DATABASE_URL must be set");
+use crate::config::DatabaseConfig;
+
+pub async fn create_db_pool(config: &DatabaseConfig) -> Result<PgPool, sqlx::Error> {
+    let database_url = config
+        .url
+        .as_deref()
+        .ok_or_else(|| sqlx::Error::Configuration("database.url (or DATABASE_URL) must be set".into()))?;
 
     PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_seconds))
+        .connect(database_url)
         .await
 }
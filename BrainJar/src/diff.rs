@@ -0,0 +1,112 @@
+use serde::Serialize;
+
+// Above this many line pairs the LCS table gets too big, and the diff degrades to
+// deleting every old line and inserting every new one
+const MAX_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+fn line(op: DiffOp, text: &str) -> DiffLine {
+    DiffLine { op, text: text.to_string() }
+}
+
+// Line-based diff from the longest common subsequence, deletions before insertions
+pub fn diff_lines(before: &str, after: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+
+    if old.len().saturating_mul(new.len()) > MAX_CELLS {
+        return old
+            .iter()
+            .map(|l| line(DiffOp::Delete, l))
+            .chain(new.iter().map(|l| line(DiffOp::Insert, l)))
+            .collect();
+    }
+
+    // lcs[i][j] is the LCS length of old[i..] and new[j..]
+    let mut lcs = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(line(DiffOp::Equal, old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(line(DiffOp::Delete, old[i]));
+            i += 1;
+        } else {
+            lines.push(line(DiffOp::Insert, new[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|l| line(DiffOp::Delete, l)));
+    lines.extend(new[j..].iter().map(|l| line(DiffOp::Insert, l)));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ops(lines: &[DiffLine]) -> Vec<(DiffOp, &str)> {
+        lines.iter().map(|l| (l.op, l.text.as_str())).collect()
+    }
+
+    #[test]
+    fn identical_text_is_all_equal() {
+        let lines = diff_lines("a\nb", "a\nb");
+        assert_eq!(ops(&lines), [(DiffOp::Equal, "a"), (DiffOp::Equal, "b")]);
+    }
+
+    #[test]
+    fn changed_line_is_deleted_then_inserted() {
+        let lines = diff_lines("a\nb\nc", "a\nx\nc\nd");
+        assert_eq!(
+            ops(&lines),
+            [
+                (DiffOp::Equal, "a"),
+                (DiffOp::Delete, "b"),
+                (DiffOp::Insert, "x"),
+                (DiffOp::Equal, "c"),
+                (DiffOp::Insert, "d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_sides() {
+        assert!(diff_lines("", "").is_empty());
+        assert_eq!(ops(&diff_lines("", "a")), [(DiffOp::Insert, "a")]);
+        assert_eq!(ops(&diff_lines("a", "")), [(DiffOp::Delete, "a")]);
+    }
+
+    #[test]
+    fn keeps_the_longest_common_subsequence() {
+        let lines = diff_lines("a\nb\nc\nd", "b\nd\na");
+        let kept: Vec<&str> = lines.iter().filter(|l| l.op == DiffOp::Equal).map(|l| l.text.as_str()).collect();
+        assert_eq!(kept, ["b", "d"]);
+    }
+}
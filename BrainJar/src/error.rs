@@ -0,0 +1,176 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::{header, StatusCode},
+    middleware::Next,
+    HttpResponse, ResponseError,
+};
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+tokio::task_local! {
+    // Id of the request being handled, so errors can echo it without access to the request
+    static REQUEST_ID: String;
+}
+
+// The single error type returned by handlers and extractors. Every variant renders as
+// `{code, message, details, request_id}` so clients only deal with one error shape.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{message}")]
+    Validation { message: String, details: Value },
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{message}")]
+    RateLimited { message: String, retry_after: u64 },
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error("{0}")]
+    Internal(String),
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    code: &'a str,
+    message: String,
+    details: Option<Value>,
+    request_id: Option<String>,
+}
+
+impl AppError {
+    pub fn internal(context: &str, error: impl std::fmt::Display) -> AppError {
+        AppError::Internal(format!("{}: {}", context, error))
+    }
+
+    pub fn validation(message: impl Into<String>, details: Value) -> AppError {
+        AppError::Validation { message: message.into(), details }
+    }
+
+    // Stable machine-readable code, status and client-safe message. Database and internal
+    // failures never expose their underlying text.
+    fn parts(&self) -> (StatusCode, &'static str, String, Option<Value>) {
+        match self {
+            AppError::BadRequest(message) => (StatusCode::BAD_REQUEST, "bad_request", message.clone(), None),
+            AppError::Validation { message, details } => {
+                (StatusCode::BAD_REQUEST, "validation_failed", message.clone(), Some(details.clone()))
+            }
+            AppError::Unauthorized(message) => (StatusCode::UNAUTHORIZED, "unauthorized", message.clone(), None),
+            AppError::Forbidden(message) => (StatusCode::FORBIDDEN, "forbidden", message.clone(), None),
+            AppError::NotFound(message) => (StatusCode::NOT_FOUND, "not_found", message.clone(), None),
+            AppError::Conflict(message) => (StatusCode::CONFLICT, "conflict", message.clone(), None),
+            AppError::RateLimited { message, retry_after } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limited",
+                message.clone(),
+                Some(serde_json::json!({ "retry_after": retry_after })),
+            ),
+            AppError::Database(e) => database_parts(e),
+            AppError::Internal(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+                "Internal server error".to_string(),
+                None,
+            ),
+        }
+    }
+}
+
+fn database_parts(error: &sqlx::Error) -> (StatusCode, &'static str, String, Option<Value>) {
+    match error {
+        sqlx::Error::RowNotFound => (StatusCode::NOT_FOUND, "not_found", "Resource not found".to_string(), None),
+        sqlx::Error::PoolTimedOut => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "service_unavailable",
+            "Database is busy, try again shortly".to_string(),
+            None,
+        ),
+        sqlx::Error::Database(db) => {
+            let details = db.constraint().map(|c| serde_json::json!({ "constraint": c }));
+            // https://www.postgresql.org/docs/current/errcodes-appendix.html
+            match db.code().as_deref() {
+                Some("23505") => (StatusCode::CONFLICT, "conflict", "Resource already exists".to_string(), details),
+                Some("23503") => (
+                    StatusCode::BAD_REQUEST,
+                    "invalid_reference",
+                    "Referenced resource does not exist".to_string(),
+                    details,
+                ),
+                Some("23502") | Some("23514") | Some("22P02") => {
+                    (StatusCode::BAD_REQUEST, "bad_request", "Invalid value".to_string(), details)
+                }
+                _ => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "Internal server error".to_string(), None),
+            }
+        }
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "Internal server error".to_string(), None),
+    }
+}
+
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        self.parts().0
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let (status, code, message, details) = self.parts();
+        let request_id = current_request_id();
+
+        if status.is_server_error() {
+            tracing::error!("[{}] {}", request_id.as_deref().unwrap_or("-"), self);
+        }
+
+        let mut response = HttpResponse::build(status);
+        if let AppError::RateLimited { retry_after, .. } = self {
+            response.insert_header((header::RETRY_AFTER, retry_after.to_string()));
+        }
+
+        response.json(ErrorBody { code, message, details, request_id })
+    }
+}
+
+// Extractor failures (malformed JSON, bad path or query parameters) in the same shape
+pub fn extractor_error(error: impl std::fmt::Display) -> actix_web::Error {
+    AppError::BadRequest(error.to_string()).into()
+}
+
+pub async fn not_found() -> Result<HttpResponse, AppError> {
+    Err(AppError::NotFound("Route not found".to_string()))
+}
+
+// Tags every request with an id (the caller's X-Request-Id if it sent a sane one),
+// echoes it in the response and makes it available to error bodies.
+pub async fn request_id(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= 64 && value.bytes().all(|b| b.is_ascii_graphic()))
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let header_value = header::HeaderValue::from_str(&id).ok();
+    let mut response = REQUEST_ID.scope(id, next.call(req)).await?;
+
+    if let Some(value) = header_value {
+        response.headers_mut().insert(header::HeaderName::from_static("x-request-id"), value);
+    }
+
+    Ok(response)
+}
@@ -0,0 +1,159 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Serialize;
+
+use crate::config::MailConfig;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Email {
+    pub fn new(to: &str, subject: &str, body: String) -> Self {
+        Email {
+            to: to.to_string(),
+            subject: subject.to_string(),
+            body,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MailError {
+    #[error("invalid email address: {0}")]
+    Address(String),
+    #[error("mail transport error: {0}")]
+    Transport(String),
+    #[error("mail io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+// Outgoing mail transport. Handlers take `web::Data<dyn Mailer>` so the transport
+// can be chosen at startup without touching the routes.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> Result<(), MailError>;
+
+    // Messages captured so far, for transports that keep them around (local dev only)
+    fn outbox(&self) -> Option<Vec<Email>> {
+        None
+    }
+}
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(host: &str, port: u16, username: Option<String>, password: Option<String>, from: &str) -> Result<Self, MailError> {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .map_err(|e| MailError::Transport(e.to_string()))?
+            .port(port);
+
+        if let (Some(username), Some(password)) = (username, password) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        let from = from
+            .parse::<Mailbox>()
+            .map_err(|e| MailError::Address(e.to_string()))?;
+
+        Ok(SmtpMailer { transport: builder.build(), from })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: Email) -> Result<(), MailError> {
+        let to = email
+            .to
+            .parse::<Mailbox>()
+            .map_err(|e| MailError::Address(e.to_string()))?;
+
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(email.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(email.body)
+            .map_err(|e| MailError::Transport(e.to_string()))?;
+
+        self.transport
+            .send(message)
+            .await
+            .map_err(|e| MailError::Transport(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+// Writes every message to its own file in a directory, handy for local development
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, MailError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(FileMailer { dir })
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: Email) -> Result<(), MailError> {
+        let file_name = format!("{}-{}.txt", email.created_at.format("%Y%m%dT%H%M%S%.3f"), uuid::Uuid::new_v4());
+        let contents = format!("To: {}\nSubject: {}\nDate: {}\n\n{}\n", email.to, email.subject, email.created_at.to_rfc2822(), email.body);
+        tokio::fs::write(self.dir.join(file_name), contents).await?;
+        Ok(())
+    }
+}
+
+// Keeps messages in memory so tests and the dev outbox endpoint can read them back
+#[derive(Default)]
+pub struct InMemoryMailer {
+    sent: Mutex<Vec<Email>>,
+}
+
+#[async_trait]
+impl Mailer for InMemoryMailer {
+    async fn send(&self, email: Email) -> Result<(), MailError> {
+        tracing::debug!("Captured email to {}: {}", email.to, email.subject);
+        self.sent.lock().unwrap().push(email);
+        Ok(())
+    }
+
+    fn outbox(&self) -> Option<Vec<Email>> {
+        Some(self.sent.lock().unwrap().clone())
+    }
+}
+
+// Build the transport selected by `mail.transport` (smtp, file or memory)
+pub fn from_config(config: &MailConfig) -> Result<Arc<dyn Mailer>, MailError> {
+    let mailer: Arc<dyn Mailer> = match config.transport.as_str() {
+        "smtp" => Arc::new(SmtpMailer::new(
+            &config.smtp_host,
+            config.smtp_port,
+            config.smtp_username.clone(),
+            config.smtp_password.clone(),
+            &config.from,
+        )?),
+        "file" => Arc::new(FileMailer::new(&config.outbox_dir)?),
+        _ => Arc::new(InMemoryMailer::default()),
+    };
+
+    Ok(mailer)
+}
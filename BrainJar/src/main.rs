@@ -1,9 +1,24 @@
+mod config;
 mod db;
+mod error;
 mod routes;
 mod models;
 mod middleware;
+mod crypto;
+mod mailer;
+mod totp;
+mod diff;
+mod transfer;
+mod streak_audit;
+mod jwt;
+mod rate_limit;
+mod repository;
+#[cfg(test)]
+mod test_app;
 
-use actix_web::{web, App, HttpServer, middleware::Logger};
+use std::sync::Arc;
+
+use actix_web::{web, App, HttpServer, middleware::{from_fn, Logger}};
 use actix_cors::Cors;
 use dotenv::dotenv;
 
@@ -12,49 +27,113 @@ async fn main() -> std::io::Result<()> {
     // Load environment variables
     dotenv().ok();
 
-    // Set default JWT_SECRET if not provided
-    if std::env::var("JWT_SECRET").is_err() {
-        unsafe {
-            std::env::set_var("JWT_SECRET", "brainjar_secret_key_2025");
-        }
-        println!("Using default JWT_SECRET");
-    }
-
     // Initialize logging
     tracing_subscriber::fmt::init();
 
-    // Try to create database connection pool (optional for now)
-    let pool = match db::create_db_pool().await {
-        Ok(pool) => {
-            println!("Database connected successfully");
-            Some(web::Data::new(pool))
+    // Refuse to start on invalid configuration rather than limping along on defaults
+    let config = config::AppConfig::load()
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+
+    // `backend recompute-streaks [--dry-run]` audits the streaks stored in Postgres and exits.
+    // No offline fallback here, auditing an empty in-memory store would look like success.
+    if std::env::args().nth(1).as_deref() == Some("recompute-streaks") {
+        if !matches!(config.database.backend, config::DatabaseBackend::Postgres) {
+            return Err(std::io::Error::other("recompute-streaks needs the postgres database backend"));
         }
-        Err(e) => {
-            println!("Warning: Failed to connect to database: {}. Running in offline mode.", e);
-            None
+        let pool = db::create_db_pool(&config.database)
+            .await
+            .map_err(|e| std::io::Error::other(format!("Failed to connect to database: {}", e)))?;
+
+        let dry_run = std::env::args().any(|arg| arg == "--dry-run");
+        let audit = streak_audit::recompute_streaks(&pool, dry_run)
+            .await
+            .map_err(|e| std::io::Error::other(format!("Streak recompute failed: {}", e)))?;
+
+        println!("{}", serde_json::to_string_pretty(&audit)?);
+        return Ok(());
+    }
+
+    let repositories = match config.database.backend {
+        config::DatabaseBackend::Memory => {
+            tracing::warn!("Using the in-memory database backend, data will be lost on restart");
+            repository::Repositories::in_memory()
         }
+        config::DatabaseBackend::Postgres => match db::create_db_pool(&config.database).await {
+            Ok(pool) => {
+                println!("Database connected successfully");
+                repository::Repositories::postgres(pool)
+            }
+            Err(e) if config.is_production() => {
+                return Err(std::io::Error::other(format!("Failed to connect to database: {}", e)));
+            }
+            Err(e) => {
+                // Development keeps the old offline mode, now backed by the in-memory store
+                println!("Warning: Failed to connect to database: {}. Running in offline mode.", e);
+                repository::Repositories::in_memory()
+            }
+        },
     };
 
-    println!("Server running on http://localhost:8080");
+    let jwt_keys = web::Data::new(
+        jwt::JwtKeys::load(&config.jwt).map_err(|e| std::io::Error::other(format!("Failed to load JWT keys: {}", e)))?,
+    );
 
-    HttpServer::new(move || {
-        let cors = Cors::default()
-            .allow_any_origin()
+    let mailer = mailer::from_config(&config.mail)
+        .map_err(|e| std::io::Error::other(format!("Failed to configure mail transport: {}", e)))?;
+
+    // Shared by every worker so limits apply to the whole process
+    let rate_limiter = web::Data::new(rate_limit::RateLimiter::new(
+        config.rate_limit.clone(),
+        Arc::new(rate_limit::InMemoryRateLimitStore::default()),
+    ));
+
+    let bind_address = (config.server.host.clone(), config.server.port);
+    let workers = config.server.workers;
+    let config = web::Data::new(config);
+
+    println!("Server running on http://{}:{}", bind_address.0, bind_address.1);
+
+    let server = HttpServer::new(move || {
+        let mut cors = Cors::default()
             .allow_any_method()
             .allow_any_header()
-            .max_age(3600);
+            .expose_headers([error::REQUEST_ID_HEADER])
+            .max_age(config.cors.max_age_seconds);
+
+        cors = if config.cors.allows_any_origin() {
+            cors.allow_any_origin()
+        } else {
+            config
+                .cors
+                .allowed_origins
+                .iter()
+                .fold(cors, |cors, origin| cors.allowed_origin(origin))
+        };
 
         App::new()
+            .wrap(from_fn(rate_limit::rate_limit))
             .wrap(cors)
+            .wrap(from_fn(error::request_id))
             .wrap(Logger::default())
+            .app_data(web::Data::from(mailer.clone()))
+            .app_data(config.clone())
+            .app_data(jwt_keys.clone())
+            .app_data(rate_limiter.clone())
+            // Malformed bodies, queries and paths get the same error shape as handler errors
+            .app_data(web::JsonConfig::default().error_handler(|e, _| error::extractor_error(e)))
+            .app_data(web::QueryConfig::default().error_handler(|e, _| error::extractor_error(e)))
+            .app_data(web::PathConfig::default().error_handler(|e, _| error::extractor_error(e)))
             .configure(|cfg| {
-                if let Some(pool) = pool.clone() {
-                    cfg.app_data(pool);
-                }
+                repositories.configure(cfg);
                 routes::configure(cfg);
             })
-    })
-    .bind(("127.0.0.1", 8080))?
-    .run()
-    .await
+            .default_service(web::to(error::not_found))
+    });
+
+    let server = match workers {
+        Some(workers) => server.workers(workers),
+        None => server,
+    };
+
+    server.bind(bind_address)?.run().await
 }
@@ -1,67 +1,106 @@
-use actix_web::{
-    dev::Payload, Error as ActixError, FromRequest, HttpRequest,
-    error::ErrorUnauthorized,
-};
-use futures::future::{ready, Ready};
-use jsonwebtoken::{decode, DecodingKey, Validation};
+use std::marker::PhantomData;
+
+use actix_web::{dev::Payload, http::Method, web, FromRequest, HttpRequest};
+use chrono::Utc;
+use futures::future::{ready, LocalBoxFuture};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::jwt::JwtKeys;
+use crate::models::access_token::{scopes_allow, TOKEN_PREFIX};
+use crate::models::user::Role;
+use crate::repository::{AccessTokenRepository, SessionRepository};
+
+const MFA_PENDING_PURPOSE: &str = "mfa_pending";
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
+    pub sid: String,
+    pub role: Role,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+// Issued after a correct password when two-factor is enabled. It has no session id,
+// so it can never pass as an access token.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaPendingClaims {
+    pub sub: String,
+    pub purpose: String,
     pub exp: i64,
 }
 
+// How the caller authenticated: an interactive login session or a personal access token
+#[derive(Debug, Clone)]
+pub enum Credential {
+    Session(Uuid),
+    AccessToken { scopes: Vec<String> },
+}
+
 #[derive(Debug, Clone)]
 pub struct AuthenticatedUser {
     pub id: Uuid,
+    pub role: Role,
+    pub credential: Credential,
 }
 
-impl FromRequest for AuthenticatedUser {
-    type Error = ActixError;
-    type Future = Ready<Result<Self, Self::Error>>;
-
-    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        let auth_header = req.headers().get("Authorization");
-        let auth_str = match auth_header {
-            Some(h) => match h.to_str() {
-                Ok(s) => s,
-                Err(_) => return ready(Err(ErrorUnauthorized("Invalid authorization header"))),
-            },
-            None => return ready(Err(ErrorUnauthorized("No authorization header"))),
-        };
+impl AuthenticatedUser {
+    pub fn session_id(&self) -> Option<Uuid> {
+        match self.credential {
+            Credential::Session(id) => Some(id),
+            Credential::AccessToken { .. } => None,
+        }
+    }
 
-        if !auth_str.starts_with("Bearer ") {
-            return ready(Err(ErrorUnauthorized("Invalid authorization header format")));
+    // Login sessions carry every scope; access tokens only the ones they were granted
+    pub fn has_scope(&self, scope: &str) -> bool {
+        match &self.credential {
+            Credential::Session(_) => true,
+            Credential::AccessToken { scopes } => scopes_allow(scopes, scope),
         }
+    }
+}
 
-        let token = &auth_str["Bearer ".len()..];
-        let jwt_secret = match std::env::var("JWT_SECRET") {
-            Ok(s) => s,
-            Err(_) => "brainjar_secret_key_2025".to_string(),
-        };
+// Issue a short-lived access token bound to a server-side session
+pub fn create_access_token(config: &AppConfig, keys: &JwtKeys, user_id: Uuid, session_id: Uuid, role: Role) -> Result<String, jsonwebtoken::errors::Error> {
+    let now = Utc::now();
+    let claims = Claims {
+        sub: user_id.to_string(),
+        sid: session_id.to_string(),
+        role,
+        iat: now.timestamp(),
+        exp: (now + config.tokens.access_token_ttl()).timestamp(),
+    };
 
-        let token_data = match decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(jwt_secret.as_bytes()),
-            &Validation::default(),
-        ) {
-            Ok(data) => data,
-            Err(_) => return ready(Err(ErrorUnauthorized("Invalid token"))),
-        };
+    keys.encode(&claims)
+}
 
-        let user_id = match Uuid::parse_str(&token_data.claims.sub) {
-            Ok(id) => id,
-            Err(_) => return ready(Err(ErrorUnauthorized("Invalid user ID in token"))),
-        };
+pub fn create_mfa_token(config: &AppConfig, keys: &JwtKeys, user_id: Uuid) -> Result<String, jsonwebtoken::errors::Error> {
+    let claims = MfaPendingClaims {
+        sub: user_id.to_string(),
+        purpose: MFA_PENDING_PURPOSE.to_string(),
+        exp: (Utc::now() + config.tokens.mfa_token_ttl()).timestamp(),
+    };
+
+    keys.encode(&claims)
+}
+
+pub fn verify_mfa_token(keys: &JwtKeys, token: &str) -> Result<Uuid, &'static str> {
+    let claims = keys
+        .decode::<MfaPendingClaims>(token)
+        .map_err(|_| "Invalid or expired MFA token")?;
 
-        ready(Ok(AuthenticatedUser { id: user_id }))
+    if claims.purpose != MFA_PENDING_PURPOSE {
+        return Err("Invalid MFA token");
     }
+
+    Uuid::parse_str(&claims.sub).map_err(|_| "Invalid user ID in token")
 }
 
-// Helper function to verify token and return user ID
-pub fn verify_token(req: &HttpRequest) -> Result<Uuid, &'static str> {
+fn bearer_token(req: &HttpRequest) -> Result<&str, &'static str> {
     let auth_header = req.headers().get("Authorization");
     let auth_str = match auth_header {
         Some(h) => match h.to_str() {
@@ -71,29 +110,180 @@ pub fn verify_token(req: &HttpRequest) -> Result<Uuid, &'static str> {
         None => return Err("No authorization header"),
     };
 
-    if !auth_str.starts_with("Bearer ") {
-        return Err("Invalid authorization header format");
-    }
-
-    let token = &auth_str["Bearer ".len()..];
-    let jwt_secret = match std::env::var("JWT_SECRET") {
-        Ok(s) => s,
-        Err(_) => "brainjar_secret_key_2025".to_string(),
-    };
+    auth_str
+        .strip_prefix("Bearer ")
+        .ok_or("Invalid authorization header format")
+}
 
-    let token_data = match decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(jwt_secret.as_bytes()),
-        &Validation::default(),
-    ) {
-        Ok(data) => data,
+// Validate an access token JWT, returning the user and session ids it carries
+fn decode_jwt(keys: &JwtKeys, token: &str) -> Result<(Uuid, Uuid), &'static str> {
+    let claims = match keys.decode::<Claims>(token) {
+        Ok(claims) => claims,
         Err(_) => return Err("Invalid token"),
     };
 
-    let user_id = match Uuid::parse_str(&token_data.claims.sub) {
+    let user_id = match Uuid::parse_str(&claims.sub) {
         Ok(id) => id,
         Err(_) => return Err("Invalid user ID in token"),
     };
 
-    Ok(user_id)
+    let session_id = match Uuid::parse_str(&claims.sid) {
+        Ok(id) => id,
+        Err(_) => return Err("Invalid session ID in token"),
+    };
+
+    Ok((user_id, session_id))
+}
+
+// Scope a personal access token needs for this request, derived from the API area and
+// method. None means the endpoint is only reachable from a login session (account,
+// token and admin management).
+fn required_scope(req: &HttpRequest) -> Option<String> {
+    let path = req.path().strip_prefix("/api/")?;
+    let mut segments = path.split('/');
+    let resource = match (segments.next()?, segments.next()) {
+        // Search, tags and the caller's solved list only read problem data
+        ("problems" | "resources" | "me" | "search" | "tags", _) => "problems",
+        ("streaks" | "leaderboards", _) => "streaks",
+        ("characters", _) => "characters",
+        // The caller's own account settings, other /api/users routes look up people to befriend
+        ("users", Some("me")) => "profile",
+        ("friends" | "friend-request" | "users", _) => "friends",
+        ("messages" | "chat", _) => "messages",
+        _ => return None,
+    };
+
+    let access = match *req.method() {
+        Method::GET | Method::HEAD => "read",
+        _ => "write",
+    };
+
+    Some(format!("{}:{}", resource, access))
+}
+
+async fn authenticate_access_token(tokens: &dyn AccessTokenRepository, token: &str, required: Option<String>) -> Result<AuthenticatedUser, AppError> {
+    let required = required
+        .ok_or_else(|| AppError::Forbidden("Personal access tokens cannot be used for this endpoint".into()))?;
+
+    let resolved = tokens
+        .resolve(token)
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("Invalid token".into()))?;
+
+    let user = AuthenticatedUser {
+        id: resolved.user_id,
+        role: resolved.role,
+        credential: Credential::AccessToken { scopes: resolved.scopes },
+    };
+
+    if !user.has_scope(&required) {
+        return Err(AppError::Forbidden(format!("Token is missing the {} scope", required)));
+    }
+
+    Ok(user)
+}
+
+async fn authenticate_session(sessions: &dyn SessionRepository, user_id: Uuid, session_id: Uuid) -> Result<AuthenticatedUser, AppError> {
+    // Reject tokens whose session was logged out or revoked. The role comes from the
+    // database rather than the token so demotions take effect immediately.
+    let role = sessions
+        .active_role(session_id, user_id)
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("Session has been revoked".into()))?;
+
+    Ok(AuthenticatedUser { id: user_id, role, credential: Credential::Session(session_id) })
+}
+
+// Accepts either a JWT from /api/auth/login or a personal access token (bjp_...)
+impl FromRequest for AuthenticatedUser {
+    type Error = AppError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let token = match bearer_token(req) {
+            Ok(token) => token.to_string(),
+            Err(e) => return Box::pin(ready(Err(AppError::Unauthorized(e.into())))),
+        };
+
+        if token.starts_with(TOKEN_PREFIX) {
+            let tokens = req.app_data::<web::Data<dyn AccessTokenRepository>>().cloned();
+            let required = required_scope(req);
+            return Box::pin(async move {
+                let tokens = tokens.ok_or_else(|| AppError::Internal("Access token repository not configured".into()))?;
+                authenticate_access_token(tokens.get_ref(), &token, required).await
+            });
+        }
+
+        let keys = match req.app_data::<web::Data<JwtKeys>>() {
+            Some(keys) => keys,
+            None => return Box::pin(ready(Err(AppError::Internal("Signing keys not configured".into())))),
+        };
+
+        let (user_id, session_id) = match decode_jwt(keys, &token) {
+            Ok(ids) => ids,
+            Err(e) => return Box::pin(ready(Err(AppError::Unauthorized(e.into())))),
+        };
+
+        let sessions = req.app_data::<web::Data<dyn SessionRepository>>().cloned();
+
+        Box::pin(async move {
+            let sessions = sessions.ok_or_else(|| AppError::Internal("Session repository not configured".into()))?;
+            authenticate_session(sessions.get_ref(), user_id, session_id).await
+        })
+    }
+}
+
+// Minimum role for a RequireRole guard
+pub trait RoleRequirement {
+    const MIN_ROLE: Role;
+}
+
+pub struct Moderator;
+pub struct Admin;
+
+impl RoleRequirement for Moderator {
+    const MIN_ROLE: Role = Role::Moderator;
+}
+
+impl RoleRequirement for Admin {
+    const MIN_ROLE: Role = Role::Admin;
+}
+
+// Extractor that authenticates like AuthenticatedUser and then requires at least R's role,
+// e.g. `admin: RequireRole<Admin>` in a handler signature. Higher roles always pass.
+pub struct RequireRole<R: RoleRequirement> {
+    pub user: AuthenticatedUser,
+    _role: PhantomData<R>,
+}
+
+impl<R: RoleRequirement + 'static> FromRequest for RequireRole<R> {
+    type Error = AppError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let user = AuthenticatedUser::from_request(req, payload);
+
+        Box::pin(async move {
+            let user = user.await?;
+
+            if user.role < R::MIN_ROLE {
+                tracing::warn!("User {} denied access requiring {:?}", user.id, R::MIN_ROLE);
+                return Err(AppError::Forbidden("Insufficient permissions".into()));
+            }
+
+            Ok(RequireRole { user, _role: PhantomData })
+        })
+    }
+}
+
+// Helper function to verify token and return user ID.
+// Only checks the signature and expiry; use the AuthenticatedUser extractor to also check revocation.
+pub fn verify_token(req: &HttpRequest) -> Result<Uuid, &'static str> {
+    let keys = req
+        .app_data::<web::Data<JwtKeys>>()
+        .ok_or("Signing keys unavailable")?;
+
+    bearer_token(req)
+        .and_then(|token| decode_jwt(keys, token))
+        .map(|(user_id, _)| user_id)
 }
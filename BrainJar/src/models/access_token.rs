@@ -0,0 +1,181 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::crypto::{generate_token, hash_token};
+use crate::models::user::Role;
+
+// Lets the auth extractor tell access tokens apart from JWTs without decoding
+pub const TOKEN_PREFIX: &str = "bjp_";
+const DISPLAY_PREFIX_LEN: usize = 12;
+pub const MAX_TOKENS_PER_USER: i64 = 50;
+
+// Every scope a token can be granted. `<resource>:write` also allows reads.
+pub const SCOPES: &[&str] = &[
+    "problems:read",
+    "problems:write",
+    "streaks:read",
+    "streaks:write",
+    "characters:read",
+    "characters:write",
+    "friends:read",
+    "friends:write",
+    "messages:read",
+    "messages:write",
+    "profile:read",
+    "profile:write",
+];
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AccessToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub token_prefix: String,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateAccessToken {
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub expires_in_days: Option<i64>,
+}
+
+// Returned once at creation; the plaintext token cannot be recovered afterwards
+#[derive(Debug, Serialize)]
+pub struct CreatedAccessToken {
+    #[serde(flatten)]
+    pub access_token: AccessToken,
+    pub token: String,
+}
+
+// What the auth extractor needs to know about a token presented on a request
+#[derive(Debug, sqlx::FromRow)]
+pub struct ResolvedAccessToken {
+    pub user_id: Uuid,
+    pub scopes: Vec<String>,
+    pub role: Role,
+}
+
+pub fn new_token() -> String {
+    format!("{}{}", TOKEN_PREFIX, generate_token(32))
+}
+
+// Shown in token listings so users can tell their tokens apart
+pub fn display_prefix(token: &str) -> &str {
+    &token[..DISPLAY_PREFIX_LEN]
+}
+
+pub fn is_valid_scope(scope: &str) -> bool {
+    SCOPES.contains(&scope)
+}
+
+// Whether a set of granted scopes covers `required` (e.g. "problems:read")
+pub fn scopes_allow(granted: &[String], required: &str) -> bool {
+    if granted.iter().any(|s| s == required) {
+        return true;
+    }
+
+    match required.strip_suffix(":read") {
+        Some(resource) => granted.iter().any(|s| s.strip_suffix(":write") == Some(resource)),
+        None => false,
+    }
+}
+
+impl AccessToken {
+    pub async fn create(
+        pool: &PgPool,
+        user_id: Uuid,
+        name: &str,
+        scopes: &[String],
+        expires_in_days: Option<i64>,
+    ) -> Result<(AccessToken, String), sqlx::Error> {
+        let token = new_token();
+        let expires_at = expires_in_days.map(|days| Utc::now() + Duration::days(days));
+
+        let access_token = sqlx::query_as::<_, AccessToken>(
+            "INSERT INTO personal_access_tokens (id, user_id, name, token_prefix, token_hash, scopes, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING *"
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(name)
+        .bind(display_prefix(&token))
+        .bind(hash_token(&token))
+        .bind(scopes)
+        .bind(expires_at)
+        .fetch_one(pool)
+        .await?;
+
+        Ok((access_token, token))
+    }
+
+    pub async fn count_active_for_user(pool: &PgPool, user_id: Uuid) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM personal_access_tokens
+             WHERE user_id = $1 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > NOW())"
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn list_for_user(pool: &PgPool, user_id: Uuid) -> Result<Vec<AccessToken>, sqlx::Error> {
+        sqlx::query_as::<_, AccessToken>(
+            "SELECT * FROM personal_access_tokens
+             WHERE user_id = $1 AND revoked_at IS NULL
+             ORDER BY created_at DESC"
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn revoke(pool: &PgPool, token_id: Uuid, user_id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE personal_access_tokens SET revoked_at = NOW()
+             WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+        )
+        .bind(token_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn revoke_all_for_user(pool: &PgPool, user_id: Uuid) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE personal_access_tokens SET revoked_at = NOW()
+             WHERE user_id = $1 AND revoked_at IS NULL"
+        )
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Look up a presented token and stamp its last use. Returns None for unknown, revoked or
+    // expired tokens and for tokens belonging to suspended accounts.
+    pub async fn resolve(pool: &PgPool, token: &str) -> Result<Option<ResolvedAccessToken>, sqlx::Error> {
+        sqlx::query_as::<_, ResolvedAccessToken>(
+            "UPDATE personal_access_tokens t SET last_used_at = NOW()
+             FROM users u
+             WHERE t.token_hash = $1 AND u.id = t.user_id
+               AND t.revoked_at IS NULL AND (t.expires_at IS NULL OR t.expires_at > NOW())
+               AND u.suspended_at IS NULL
+             RETURNING t.user_id, t.scopes, u.role"
+        )
+        .bind(hash_token(token))
+        .fetch_optional(pool)
+        .await
+    }
+}
@@ -0,0 +1,123 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+// Something that counts towards a user's day in the activity ledger
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ActivityKind {
+    ProblemCreated,
+    ProblemSolved,
+    SolutionPosted,
+    FeedbackGiven,
+}
+
+impl ActivityKind {
+    // The daily_activity column it's counted in
+    pub fn column(&self) -> &'static str {
+        match self {
+            ActivityKind::ProblemCreated => "problems_created",
+            ActivityKind::ProblemSolved => "problems_solved",
+            ActivityKind::SolutionPosted => "solutions_posted",
+            ActivityKind::FeedbackGiven => "feedback_given",
+        }
+    }
+}
+
+// One user's day. Days are in the user's timezone, ending streaks.grace_hours after
+// midnight like streak days.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct DailyActivity {
+    pub day: NaiveDate,
+    pub problems_created: i32,
+    pub problems_solved: i32,
+    pub solutions_posted: i32,
+    pub feedback_given: i32,
+}
+
+impl DailyActivity {
+    // Count `count` of `kind` on the user's `day`
+    pub async fn add_in(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        day: NaiveDate,
+        kind: ActivityKind,
+        count: i32,
+    ) -> Result<(), sqlx::Error> {
+        let column = kind.column();
+        sqlx::query(&format!(
+            "INSERT INTO daily_activity (user_id, day, {column})
+             VALUES ($1, $2, $3)
+             ON CONFLICT (user_id, day) DO UPDATE SET {column} = daily_activity.{column} + EXCLUDED.{column}"
+        ))
+        .bind(user_id)
+        .bind(day)
+        .bind(count)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    pub fn empty(day: NaiveDate) -> Self {
+        DailyActivity { day, problems_created: 0, problems_solved: 0, solutions_posted: 0, feedback_given: 0 }
+    }
+
+    pub fn add(&mut self, kind: ActivityKind, count: i32) {
+        match kind {
+            ActivityKind::ProblemCreated => self.problems_created += count,
+            ActivityKind::ProblemSolved => self.problems_solved += count,
+            ActivityKind::SolutionPosted => self.solutions_posted += count,
+            ActivityKind::FeedbackGiven => self.feedback_given += count,
+        }
+    }
+
+    pub fn total(&self) -> i32 {
+        self.problems_created + self.problems_solved + self.solutions_posted + self.feedback_given
+    }
+}
+
+// GET /api/streaks/heatmap?year=2025, the current year by default
+#[derive(Debug, Deserialize)]
+pub struct HeatmapParams {
+    pub year: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HeatmapDay {
+    #[serde(flatten)]
+    pub activity: DailyActivity,
+    pub total: i32,
+    // 0 for no activity, then 1-4 by quartile of the user's busiest day of the year
+    pub level: u8,
+}
+
+// Every day of a year, like a contribution graph
+#[derive(Debug, Serialize)]
+pub struct Heatmap {
+    pub year: i32,
+    pub active_days: usize,
+    pub total: i32,
+    pub days: Vec<HeatmapDay>,
+}
+
+impl Heatmap {
+    // `days` covers the whole year in order
+    pub fn new(year: i32, days: Vec<DailyActivity>) -> Self {
+        let busiest = days.iter().map(DailyActivity::total).max().unwrap_or(0);
+        let days: Vec<HeatmapDay> = days
+            .into_iter()
+            .map(|activity| {
+                let total = activity.total();
+                let level = if total == 0 { 0 } else { (1 + (total - 1) * 4 / busiest) as u8 };
+                HeatmapDay { activity, total, level }
+            })
+            .collect();
+
+        Heatmap {
+            year,
+            active_days: days.iter().filter(|d| d.total > 0).count(),
+            total: days.iter().map(|d| d.total).sum(),
+            days,
+        }
+    }
+}
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use sqlx::PgPool;
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Chat {
     pub id: Uuid,
     pub sender_id: Uuid,
@@ -18,6 +18,15 @@ pub struct CreateMessage {
     pub message: String,
 }
 
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ChatConversation {
+    pub user_id: Uuid,
+    pub username: String,
+    pub last_message: Option<String>,
+    pub last_message_time: Option<DateTime<Utc>>,
+    pub unread_count: i64,
+}
+
 impl Chat {
     pub async fn get_messages(
         pool: &PgPool,
@@ -3,7 +3,7 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
 // Friend model - represents accepted friendships only
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Friend {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -12,7 +12,7 @@ pub struct Friend {
 }
 
 // Friend request model - represents pending/accepted/rejected requests
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct FriendRequest {
     pub id: Uuid,
     pub sender_id: Uuid,
@@ -41,6 +41,31 @@ pub struct RespondToRequestDto {
     pub status: RequestStatus,
 }
 
+// Incoming request awaiting the receiver's answer
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PendingFriendRequest {
+    pub id: Uuid,
+    pub sender_id: Uuid,
+    pub username: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct FriendSummary {
+    pub id: Uuid,
+    pub friend_id: Uuid,
+    pub friend_username: String,
+    pub friend_email: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct UserSummary {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+}
+
 // Response DTOs with user details
 #[derive(Debug, Serialize)]
 pub struct FriendWithDetails {
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// Rolling windows ending now. Ledger days are compared with the UTC date the window starts on.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardWindow {
+    Weekly,
+    Monthly,
+    AllTime,
+}
+
+impl LeaderboardWindow {
+    // Start of the window, None for all time. Weekly covers today and the 6 days before it.
+    pub fn since(self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let days = match self {
+            LeaderboardWindow::Weekly => 6,
+            LeaderboardWindow::Monthly => 29,
+            LeaderboardWindow::AllTime => return None,
+        };
+        let today = now.date_naive().and_hms_opt(0, 0, 0)?.and_utc();
+        Some(today - Duration::days(days))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardMetric {
+    // Longest run of consecutive solve days inside the window. Frozen days don't count.
+    Streak,
+    // Problems solved for the first time
+    Solved,
+    // Solutions the problem's author accepted, by when the solution was posted
+    Accepted,
+    // Feedback from other users marking the user's problems as helpful
+    Helpful,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardScope {
+    Global,
+    // The caller and their friends
+    Friends,
+}
+
+// GET /api/leaderboards?window=weekly&metric=solved&scope=friends&limit=20
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardParams {
+    pub window: Option<LeaderboardWindow>,
+    pub metric: Option<LeaderboardMetric>,
+    pub scope: Option<LeaderboardScope>,
+    pub limit: Option<i64>,
+}
+
+// Users with a zero score aren't ranked. Ties share a rank, the next one skips (1, 1, 3).
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct LeaderboardEntry {
+    pub rank: i64,
+    pub user_id: Uuid,
+    pub username: String,
+    pub value: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Leaderboard {
+    pub window: LeaderboardWindow,
+    pub metric: LeaderboardMetric,
+    pub scope: LeaderboardScope,
+    pub entries: Vec<LeaderboardEntry>,
+    // The caller's own entry even when it's below the top N, None if they have no score
+    pub me: Option<LeaderboardEntry>,
+}
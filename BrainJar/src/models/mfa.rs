@@ -0,0 +1,176 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::crypto::{generate_token, hash_token};
+
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UserMfa {
+    pub user_id: Uuid,
+    pub totp_secret: String,
+    pub enabled_at: Option<DateTime<Utc>>,
+    pub last_used_step: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MfaCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DisableMfaRequest {
+    pub password: String,
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MfaLoginRequest {
+    pub mfa_token: String,
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+// Recovery codes are shown as xxxxx-xxxxx but compared without formatting
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.trim().to_lowercase().replace(['-', ' '], "")
+}
+
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let raw = generate_token(5);
+            format!("{}-{}", &raw[..5], &raw[5..])
+        })
+        .collect()
+}
+
+impl UserMfa {
+    pub async fn get(pool: &PgPool, user_id: Uuid) -> Result<Option<UserMfa>, sqlx::Error> {
+        sqlx::query_as::<_, UserMfa>("SELECT * FROM user_mfa WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await
+    }
+
+    pub async fn is_enabled(pool: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM user_mfa WHERE user_id = $1 AND enabled_at IS NOT NULL)"
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+    }
+
+    // Store a fresh secret for a pending enrollment, replacing any unconfirmed one.
+    // Returns None if two-factor is already enabled.
+    pub async fn start_enrollment(pool: &PgPool, user_id: Uuid, secret: &str) -> Result<Option<UserMfa>, sqlx::Error> {
+        sqlx::query_as::<_, UserMfa>(
+            "INSERT INTO user_mfa (user_id, totp_secret, created_at)
+             VALUES ($1, $2, NOW())
+             ON CONFLICT (user_id) DO UPDATE SET
+                totp_secret = EXCLUDED.totp_secret,
+                last_used_step = NULL,
+                created_at = EXCLUDED.created_at
+             WHERE user_mfa.enabled_at IS NULL
+             RETURNING *"
+        )
+        .bind(user_id)
+        .bind(secret)
+        .fetch_optional(pool)
+        .await
+    }
+
+    // Record the step of an accepted code. Returns false if a code for this or a later
+    // step was already used, which makes concurrent replays lose the race.
+    pub async fn record_step(pool: &PgPool, user_id: Uuid, step: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE user_mfa SET last_used_step = $2
+             WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)"
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn enable(pool: &PgPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE user_mfa SET enabled_at = NOW() WHERE user_id = $1")
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn disable(pool: &PgPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM user_mfa WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await
+    }
+
+    // Replace all recovery codes and return the new plaintext codes (shown to the user once)
+    pub async fn regenerate_recovery_codes(pool: &PgPool, user_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+        let codes = generate_recovery_codes();
+
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for code in &codes {
+            sqlx::query(
+                "INSERT INTO mfa_recovery_codes (id, user_id, code_hash) VALUES ($1, $2, $3)"
+            )
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(hash_token(&normalize_recovery_code(code)))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(codes)
+    }
+
+    pub async fn consume_recovery_code(pool: &PgPool, user_id: Uuid, code: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE mfa_recovery_codes SET used_at = NOW()
+             WHERE id = (
+                SELECT id FROM mfa_recovery_codes
+                WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+                LIMIT 1
+             ) AND used_at IS NULL"
+        )
+        .bind(user_id)
+        .bind(hash_token(&normalize_recovery_code(code)))
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn remaining_recovery_codes(pool: &PgPool, user_id: Uuid) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM mfa_recovery_codes WHERE user_id = $1 AND used_at IS NULL"
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+    }
+}
@@ -1,8 +1,18 @@
 pub mod user;
 pub mod problem;
+pub mod solution;
 pub mod streak;
 pub mod character;
 pub mod friend;
 pub mod chat;
 pub mod message;
 pub mod resource;
+pub mod search;
+pub mod tag;
+pub mod transfer;
+pub mod session;
+pub mod user_token;
+pub mod mfa;
+pub mod access_token;
+pub mod activity;
+pub mod leaderboard;
@@ -1,8 +1,67 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::de::{DeserializeOwned, IntoDeserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+use crate::diff::{diff_lines, DiffLine};
+
+// Owners always see their own problems, whatever the level
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, Default, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "problem_visibility", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    Private,
+    // The owner's friends
+    Friends,
+    #[default]
+    Public,
+}
+
+impl Visibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Visibility::Private => "private",
+            Visibility::Friends => "friends",
+            Visibility::Public => "public",
+        }
+    }
+}
+
+// Stored as the `difficulty` enum, shared with resources. Also accepts the capitalized
+// problem names and the beginner/intermediate/advanced scale resources used to have.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "difficulty", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    #[serde(alias = "Easy", alias = "beginner")]
+    Easy,
+    #[serde(alias = "Medium", alias = "intermediate")]
+    Medium,
+    #[serde(alias = "Hard", alias = "advanced")]
+    Hard,
+}
+
+impl Difficulty {
+    // Any of the names the serde aliases accept, ignoring case
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "easy" | "beginner" => Some(Difficulty::Easy),
+            "medium" | "intermediate" => Some(Difficulty::Medium),
+            "hard" | "advanced" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Problem {
     pub id: Uuid,
     pub title: String,
@@ -12,9 +71,11 @@ pub struct Problem {
     pub created_at: DateTime<Utc>,
     pub documentation_links: Option<Vec<String>>,
     pub video_references: Option<Vec<String>>,
-    pub difficulty_level: Option<String>,
+    pub difficulty_level: Option<Difficulty>,
     pub tags: Option<Vec<String>>,
-    pub solved: Option<bool>,
+    pub solved: bool,
+    pub updated_at: DateTime<Utc>,
+    pub visibility: Visibility,
 }
 
 // Simplified response struct for API responses
@@ -26,6 +87,9 @@ pub struct ProblemResponse {
     pub category: String,
     pub user_id: Uuid,
     pub solved: bool,
+    pub visibility: Visibility,
+    pub difficulty_level: Option<Difficulty>,
+    pub tags: Vec<String>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -34,6 +98,14 @@ pub struct CreateProblem {
     pub title: String,
     pub description: String,
     pub category: String,
+    // Public when creating, unchanged when updating if left out
+    pub visibility: Option<Visibility>,
+    // Unset when creating, unchanged when updating if left out
+    pub difficulty_level: Option<Difficulty>,
+    pub documentation_links: Option<Vec<String>>,
+    // Replace the problem's tags and extra catalog categories when present
+    pub tags: Option<Vec<String>>,
+    pub category_ids: Option<Vec<Uuid>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -44,4 +116,191 @@ pub struct UserQuery {
 #[derive(Debug, Deserialize)]
 pub struct UpdateProblemStatus {
     pub solved: bool,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateProblemVisibility {
+    pub visibility: Visibility,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CategoryCount {
+    pub name: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Default, sqlx::FromRow)]
+pub struct ProblemStats {
+    pub total_problems: i64,
+    pub solved_problems: i64,
+    pub easy_problems: i64,
+    pub medium_problems: i64,
+    pub hard_problems: i64,
+    pub categories_explored: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProblemFeedbackInput {
+    pub rating: Option<i32>,
+    pub feedback: Option<String>,
+    pub is_helpful: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ProblemFeedback {
+    pub id: Uuid,
+    pub problem_id: Uuid,
+    pub user_id: Uuid,
+    pub rating: Option<i32>,
+    pub feedback: Option<String>,
+    pub is_helpful: Option<bool>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+// Feedback joined with the author's name and avatar for display
+#[derive(Debug, sqlx::FromRow)]
+pub struct FeedbackWithAuthor {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub user_name: String,
+    pub user_avatar: Option<String>,
+    pub rating: Option<i32>,
+    pub feedback: Option<String>,
+    pub is_helpful: Option<bool>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProblemSort {
+    #[default]
+    Newest,
+    // Highest average feedback rating
+    Rating,
+    // Most feedback marked helpful
+    MostHelpful,
+    // Unsolved problems first, newest first within each group
+    UnsolvedFirst,
+}
+
+// Query string enums ignore case, e.g. ?difficulty=HARD
+fn any_case<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let Some(value) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    T::deserialize(IntoDeserializer::<D::Error>::into_deserializer(value.to_lowercase())).map(Some)
+}
+
+// Query string shared by every problem listing, e.g.
+// ?category=graphs&difficulty=hard&tags=bfs,dfs&solved=false&sort=rating&limit=20&cursor=...
+#[derive(Debug, Default, Deserialize)]
+pub struct ProblemListParams {
+    pub category: Option<String>,
+    // A catalog category, matching every problem mapped to it and not only its primary one
+    pub category_id: Option<Uuid>,
+    #[serde(default, deserialize_with = "any_case")]
+    pub difficulty: Option<Difficulty>,
+    // Comma separated, a problem has to carry all of them
+    pub tags: Option<String>,
+    pub solved: Option<bool>,
+    pub author: Option<Uuid>,
+    // Creation date range, RFC 3339
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "any_case")]
+    pub sort: Option<ProblemSort>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+// A problem in a listing, with its author and feedback totals
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ProblemListItem {
+    pub id: Uuid,
+    pub title: String,
+    pub description: String,
+    pub category: String,
+    pub user_id: Uuid,
+    pub created_by: String,
+    pub author_avatar_url: Option<String>,
+    pub difficulty_level: Option<Difficulty>,
+    pub tags: Vec<String>,
+    pub solved: bool,
+    pub visibility: Visibility,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub average_rating: f64,
+    pub feedback_count: i64,
+    pub helpful_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProblemPage {
+    pub problems: Vec<ProblemListItem>,
+    // Pass back as ?cursor= for the next page. None on the last page.
+    pub next_cursor: Option<String>,
+}
+
+// A snapshot of a problem's editable fields, written on creation and on every edit
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ProblemRevision {
+    pub id: Uuid,
+    pub problem_id: Uuid,
+    pub revision: i32,
+    pub user_id: Uuid,
+    pub username: String,
+    pub title: String,
+    pub description: String,
+    pub category: String,
+    pub changed_fields: Vec<String>,
+    pub restored_from: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevisionDiffParams {
+    pub from: i32,
+    pub to: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldDiff {
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+    pub lines: Vec<DiffLine>,
+}
+
+// What changed between two revisions, only listing fields that differ
+#[derive(Debug, Serialize)]
+pub struct RevisionDiff {
+    pub problem_id: Uuid,
+    pub from: i32,
+    pub to: i32,
+    pub fields: Vec<FieldDiff>,
+}
+
+impl RevisionDiff {
+    pub fn between(from: &ProblemRevision, to: &ProblemRevision) -> Self {
+        let fields = [
+            ("title", &from.title, &to.title),
+            ("description", &from.description, &to.description),
+            ("category", &from.category, &to.category),
+        ]
+        .into_iter()
+        .filter(|(_, before, after)| before != after)
+        .map(|(field, before, after)| FieldDiff {
+            field,
+            before: before.clone(),
+            after: after.clone(),
+            lines: diff_lines(before, after),
+        })
+        .collect();
+
+        RevisionDiff { problem_id: from.problem_id, from: from.revision, to: to.revision, fields }
+    }
+}
@@ -1,19 +1,10 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use chrono::{DateTime, Utc};
+use chrono::NaiveDateTime;
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
-pub struct ProblemFeedback {
-    pub id: Uuid,
-    pub problem_id: Uuid,
-    pub user_id: Uuid,
-    pub rating: Option<i32>,
-    pub feedback: Option<String>,
-    pub is_helpful: Option<bool>,
-    pub created_at: DateTime<Utc>,
-}
+use crate::models::problem::{Difficulty, ProblemListItem};
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct ProblemResource {
     pub id: Uuid,
     pub problem_id: Uuid,
@@ -24,23 +15,26 @@ pub struct ProblemResource {
     pub thumbnail_url: Option<String>,
     pub provider: Option<String>,
     pub duration: Option<String>,
-    pub difficulty_level: Option<DifficultyLevel>,
+    pub difficulty_level: Option<Difficulty>,
     pub is_recommended: bool,
-    pub created_at: DateTime<Utc>,
+    pub created_at: Option<NaiveDateTime>,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+// An entry of the category catalog. A problem belongs to the catalog category named by
+// its `category`, plus any linked in problem_category_mappings.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct ProblemCategory {
     pub id: Uuid,
     pub name: String,
     pub description: Option<String>,
     pub icon: Option<String>,
     pub color: Option<String>,
-    pub created_at: DateTime<Utc>,
+    pub created_at: Option<NaiveDateTime>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, sqlx::Type)]
 #[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum ResourceType {
     Video,
     Article,
@@ -48,25 +42,8 @@ pub enum ResourceType {
     Course,
 }
 
-#[derive(Debug, Serialize, Deserialize, Copy, Clone, sqlx::Type)]
-#[sqlx(type_name = "varchar", rename_all = "lowercase")]
-pub enum DifficultyLevel {
-    Beginner,
-    Intermediate,
-    Advanced,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct CreateFeedbackRequest {
-    pub problem_id: Uuid,
-    pub rating: Option<i32>,
-    pub feedback: Option<String>,
-    pub is_helpful: Option<bool>,
-}
-
 #[derive(Debug, Deserialize)]
 pub struct CreateResourceRequest {
-    pub problem_id: Uuid,
     pub resource_type: ResourceType,
     pub title: String,
     pub url: String,
@@ -74,38 +51,19 @@ pub struct CreateResourceRequest {
     pub thumbnail_url: Option<String>,
     pub provider: Option<String>,
     pub duration: Option<String>,
-    pub difficulty_level: Option<DifficultyLevel>,
+    pub difficulty_level: Option<Difficulty>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ProblemWithDetails {
-    pub id: Uuid,
-    pub title: String,
-    pub description: String,
-    pub difficulty: String,
-    pub status: String,
-    pub user_id: Uuid,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
-    
-    // Author details
-    pub author_username: String,
-    pub author_avatar_url: Option<String>,
-    
-    // Feedback statistics
-    pub average_rating: Option<f64>,
-    pub total_ratings: i64,
-    pub helpful_count: i64,
-    pub total_feedback: i64,
-    
-    // Categories
+// A problem with its author, feedback totals, categories and resources
+#[derive(Debug, Serialize)]
+pub struct ProblemDetails {
+    #[serde(flatten)]
+    pub problem: ProblemListItem,
     pub categories: Vec<ProblemCategory>,
-    
-    // Resources
     pub resources: Vec<ProblemResource>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize)]
 pub struct CategoryResourcesResponse {
     pub category: ProblemCategory,
     pub resources: Vec<ProblemResource>,
@@ -128,9 +86,3 @@ impl Default for ResourceType {
         ResourceType::Article
     }
 }
-
-impl Default for DifficultyLevel {
-    fn default() -> Self {
-        DifficultyLevel::Beginner
-    }
-}
@@ -0,0 +1,72 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SearchResultType {
+    Problem,
+    Solution,
+    Resource,
+}
+
+impl SearchResultType {
+    pub const ALL: [SearchResultType; 3] =
+        [SearchResultType::Problem, SearchResultType::Solution, SearchResultType::Resource];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchResultType::Problem => "problem",
+            SearchResultType::Solution => "solution",
+            SearchResultType::Resource => "resource",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<SearchResultType> {
+        SearchResultType::ALL.into_iter().find(|t| t.as_str() == value)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    pub q: Option<String>,
+    // Comma separated subset of problem,solution,resource. Everything when unset.
+    #[serde(rename = "type")]
+    pub types: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+// Validated form of SearchParams handed to the repository
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub text: String,
+    // Only problems this user may see, and their solutions and resources, are searched
+    pub viewer: Uuid,
+    pub types: Vec<SearchResultType>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+// One hit. `title` is the problem's title for solutions, and the snippet marks matched
+// words with <mark></mark>.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SearchResult {
+    pub result_type: SearchResultType,
+    pub id: Uuid,
+    pub problem_id: Uuid,
+    pub title: String,
+    pub snippet: String,
+    pub rank: f32,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResponse {
+    pub query: String,
+    pub results: Vec<SearchResult>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
@@ -0,0 +1,225 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::crypto::{generate_token, hash_token};
+use crate::models::user::Role;
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Session {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub revoked_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenPair {
+    pub token: String,
+    pub refresh_token: String,
+    pub token_type: &'static str,
+    pub expires_in: i64,
+}
+
+pub enum RefreshOutcome {
+    Rotated { session: Session, refresh_token: String },
+    // An already-used token was presented again; the whole session has been revoked
+    ReuseDetected,
+    Invalid,
+}
+
+#[derive(sqlx::FromRow)]
+struct RefreshTokenRow {
+    id: Uuid,
+    session_id: Uuid,
+    expires_at: DateTime<Utc>,
+    used_at: Option<DateTime<Utc>>,
+}
+
+impl Session {
+    // Start a new session and return it together with its first (plaintext) refresh token
+    pub async fn create(
+        pool: &PgPool,
+        user_id: Uuid,
+        user_agent: Option<String>,
+        ip_address: Option<String>,
+        ttl: Duration,
+    ) -> Result<(Session, String), sqlx::Error> {
+        let now = Utc::now();
+        let expires_at = now + ttl;
+        let mut tx = pool.begin().await?;
+
+        let session = sqlx::query_as::<_, Session>(
+            "INSERT INTO user_sessions (id, user_id, user_agent, ip_address, created_at, last_used_at, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $5, $6)
+             RETURNING *"
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(user_agent)
+        .bind(ip_address)
+        .bind(now)
+        .bind(expires_at)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let refresh_token = generate_token(32);
+        sqlx::query(
+            "INSERT INTO refresh_tokens (id, session_id, token_hash, created_at, expires_at)
+             VALUES ($1, $2, $3, $4, $5)"
+        )
+        .bind(Uuid::new_v4())
+        .bind(session.id)
+        .bind(hash_token(&refresh_token))
+        .bind(now)
+        .bind(expires_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok((session, refresh_token))
+    }
+
+    // Exchange a refresh token for a new one. Each token may be used exactly once;
+    // replaying a spent token revokes the session it belongs to.
+    pub async fn rotate(pool: &PgPool, refresh_token: &str) -> Result<RefreshOutcome, sqlx::Error> {
+        let now = Utc::now();
+        let mut tx = pool.begin().await?;
+
+        let token = sqlx::query_as::<_, RefreshTokenRow>(
+            "SELECT id, session_id, expires_at, used_at FROM refresh_tokens
+             WHERE token_hash = $1
+             FOR UPDATE"
+        )
+        .bind(hash_token(refresh_token))
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let token = match token {
+            Some(t) => t,
+            None => return Ok(RefreshOutcome::Invalid),
+        };
+
+        if token.used_at.is_some() {
+            sqlx::query(
+                "UPDATE user_sessions SET revoked_at = $1, revoked_reason = 'refresh_token_reuse'
+                 WHERE id = $2 AND revoked_at IS NULL"
+            )
+            .bind(now)
+            .bind(token.session_id)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+
+            tracing::warn!("Refresh token reuse detected, revoked session {}", token.session_id);
+            return Ok(RefreshOutcome::ReuseDetected);
+        }
+
+        let session = sqlx::query_as::<_, Session>(
+            "SELECT * FROM user_sessions WHERE id = $1 AND revoked_at IS NULL AND expires_at > $2"
+        )
+        .bind(token.session_id)
+        .bind(now)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let session = match session {
+            Some(s) if token.expires_at > now => s,
+            _ => return Ok(RefreshOutcome::Invalid),
+        };
+
+        sqlx::query("UPDATE refresh_tokens SET used_at = $1 WHERE id = $2")
+            .bind(now)
+            .bind(token.id)
+            .execute(&mut *tx)
+            .await?;
+
+        let new_token = generate_token(32);
+        sqlx::query(
+            "INSERT INTO refresh_tokens (id, session_id, token_hash, created_at, expires_at)
+             VALUES ($1, $2, $3, $4, $5)"
+        )
+        .bind(Uuid::new_v4())
+        .bind(session.id)
+        .bind(hash_token(&new_token))
+        .bind(now)
+        .bind(session.expires_at)
+        .execute(&mut *tx)
+        .await?;
+
+        let session = sqlx::query_as::<_, Session>(
+            "UPDATE user_sessions SET last_used_at = $1 WHERE id = $2 RETURNING *"
+        )
+        .bind(now)
+        .bind(session.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(RefreshOutcome::Rotated { session, refresh_token: new_token })
+    }
+
+    // Current role of the session's user, or None if the session was revoked, has expired
+    // or the account is suspended. Read on every request so role changes apply immediately.
+    pub async fn active_role(pool: &PgPool, session_id: Uuid, user_id: Uuid) -> Result<Option<Role>, sqlx::Error> {
+        sqlx::query_scalar::<_, Role>(
+            "SELECT u.role FROM user_sessions s
+             JOIN users u ON u.id = s.user_id
+             WHERE s.id = $1 AND s.user_id = $2 AND s.revoked_at IS NULL AND s.expires_at > NOW()
+               AND u.suspended_at IS NULL"
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn list_active_for_user(pool: &PgPool, user_id: Uuid) -> Result<Vec<Session>, sqlx::Error> {
+        sqlx::query_as::<_, Session>(
+            "SELECT * FROM user_sessions
+             WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+             ORDER BY last_used_at DESC"
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn revoke(pool: &PgPool, session_id: Uuid, user_id: Uuid, reason: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE user_sessions SET revoked_at = NOW(), revoked_reason = $3
+             WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .bind(reason)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn revoke_all_for_user(pool: &PgPool, user_id: Uuid, reason: &str) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE user_sessions SET revoked_at = NOW(), revoked_reason = $2
+             WHERE user_id = $1 AND revoked_at IS NULL"
+        )
+        .bind(user_id)
+        .bind(reason)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
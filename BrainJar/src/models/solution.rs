@@ -2,7 +2,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct ProblemSolution {
     pub id: Uuid,
     pub problem_id: Uuid,
@@ -10,15 +10,20 @@ pub struct ProblemSolution {
     pub solution_text: String,
     pub created_at: DateTime<Utc>,
     pub metadata: Option<serde_json::Value>,
+    pub is_accepted: bool,
 }
 
+// Unknown fields are rejected rather than silently dropped, e.g. an `explanation` meant
+// to go in solution_text or metadata
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CreateSolution {
     pub solution_text: String,
     pub metadata: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Serialize)]
+// A problem the user has submitted a solution to, with its author
+#[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct SolvedProblemResponse {
     pub problem_id: Uuid,
     pub title: String,
@@ -32,7 +37,8 @@ pub struct SolvedProblemResponse {
     pub problem_creator_avatar: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+// A solution listed under its problem, with the solver's name
+#[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct SolutionResponse {
     pub solution_id: Uuid,
     pub user_id: Uuid,
@@ -40,4 +46,37 @@ pub struct SolutionResponse {
     pub avatar: Option<String>,
     pub solution_text: String,
     pub created_at: DateTime<Utc>,
+    pub is_accepted: bool,
+    pub upvotes: i64,
+    pub downvotes: i64,
+    pub score: i64,
+}
+
+// 1 for an upvote, -1 for a downvote, 0 to take the vote back
+#[derive(Debug, Deserialize)]
+pub struct SolutionVoteInput {
+    pub value: i16,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SolutionVoteTotals {
+    pub solution_id: Uuid,
+    pub upvotes: i64,
+    pub downvotes: i64,
+    pub score: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SolutionSort {
+    // Accepted solution first, then by score, newest breaking ties
+    #[default]
+    Top,
+    Newest,
+    Oldest,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SolutionListQuery {
+    pub sort: Option<SolutionSort>,
 }
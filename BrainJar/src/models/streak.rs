@@ -1,9 +1,14 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use chrono::{DateTime, Utc, Duration};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
 use sqlx::PgPool;
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+use crate::config::StreakConfig;
+use crate::models::activity::{ActivityKind, DailyActivity};
+use crate::models::user::parse_timezone;
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Streak {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -12,10 +17,33 @@ pub struct Streak {
     pub created_at: DateTime<Utc>,
     pub longest_streak: i32,
     pub problems_solved_today: i32,
+    pub freezes_available: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "streak_freeze_kind", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum FreezeKind {
+    // Reaching a multiple of streaks.freeze_every_days
+    Earned,
+    // Credited by an admin once a purchase has gone through
+    Purchased,
+    // Spent covering a missed day
+    Used,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct StreakFreezeEvent {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub kind: FreezeKind,
+    // The day a used freeze covered
+    pub day: Option<NaiveDate>,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct UpdateStreak {
+pub struct GrantFreezesRequest {
     pub count: i32,
 }
 
@@ -27,66 +55,217 @@ pub struct StreakStats {
     pub last_active: DateTime<Utc>,
     pub streak_percentage: f32, // Percentage of days active in the last 30 days
     pub weekly_activity: Vec<bool>, // Last 7 days of activity
+    pub freezes_available: i32,
+    pub freezes_used: Vec<StreakFreezeEvent>, // Newest first
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct StreakLeaderboardEntry {
+    pub username: String,
+    pub current_streak: i32,
+    pub longest_streak: i32,
+    pub problems_solved_today: i32,
+}
+
+// The calendar day `at` falls on in `tz`
+pub fn local_date(at: DateTime<Utc>, tz: Tz) -> NaiveDate {
+    at.with_timezone(&tz).date_naive()
+}
+
+// The streak day `at` counts for: the local day, or the one before during the grace hours
+// after midnight
+pub fn streak_day(at: DateTime<Utc>, tz: Tz, rules: &StreakConfig) -> NaiveDate {
+    local_date(at - Duration::hours(rules.grace_hours), tz)
+}
+
+// A streak as rebuilt from the activity ledger and the freezes used
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct DerivedStreak {
+    pub count: i32,
+    pub longest_streak: i32,
+    // Solves on the last day with any
+    pub problems_solved_today: i32,
+}
+
+// POST /api/admin/streaks/recompute?dry_run=true
+#[derive(Debug, Deserialize)]
+pub struct RecomputeStreaksParams {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+// A stored streak that doesn't match its history
+#[derive(Debug, Serialize)]
+pub struct StreakDiscrepancy {
+    pub user_id: Uuid,
+    pub stored_count: i32,
+    pub derived_count: i32,
+    pub stored_longest_streak: i32,
+    pub derived_longest_streak: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StreakAudit {
+    pub dry_run: bool,
+    pub checked: usize,
+    pub repaired: usize,
+    pub discrepancies: Vec<StreakDiscrepancy>,
+}
+
+// What a solve does to a streak
+#[derive(Debug)]
+pub struct SolveOutcome {
+    pub count: i32,
+    pub longest_streak: i32,
+    pub problems_solved_today: i32,
+    pub freezes_available: i32,
+    // Missed days covered by a freeze, oldest first
+    pub frozen_days: Vec<NaiveDate>,
+    pub earned_freeze: bool,
 }
 
 impl Streak {
-    pub async fn update_for_problem_solve(pool: &PgPool, user_id: Uuid) -> Result<Streak, sqlx::Error> {
+    // The streak after a solve at `now`. A gap of missed days is bridged if there are
+    // enough freezes to cover all of it, otherwise the streak restarts and the freezes are
+    // kept for next time.
+    pub fn after_solve(existing: Option<&Streak>, now: DateTime<Utc>, tz: Tz, rules: &StreakConfig) -> SolveOutcome {
+        let today = streak_day(now, tz, rules);
+        let mut outcome = SolveOutcome {
+            count: 1,
+            longest_streak: 1,
+            problems_solved_today: 1,
+            freezes_available: 0,
+            frozen_days: Vec::new(),
+            earned_freeze: false,
+        };
+
+        if let Some(streak) = existing {
+            let last_day = streak_day(streak.last_active, tz, rules);
+            let missed = (today - last_day).num_days() - 1;
+            outcome.longest_streak = streak.longest_streak;
+            outcome.freezes_available = streak.freezes_available;
+
+            if missed < 0 && streak.count > 0 {
+                // Same day - just increment problems solved today
+                outcome.count = streak.count;
+                outcome.problems_solved_today = streak.problems_solved_today + 1;
+                return outcome;
+            }
+            // A streak created before any solve starts at the first one
+            let missed = missed.max(0);
+
+            if missed <= streak.freezes_available as i64 {
+                // Next day, or a gap the freezes cover - continue streak
+                outcome.count = streak.count + 1;
+                outcome.freezes_available -= missed as i32;
+                outcome.frozen_days = (1..=missed).map(|i| last_day + Duration::days(i)).collect();
+            }
+            // Otherwise the streak is broken and restarts at 1
+        }
+
+        outcome.longest_streak = outcome.longest_streak.max(outcome.count);
+
+        if outcome.count % rules.freeze_every_days == 0 && outcome.freezes_available < rules.max_earned_freezes {
+            outcome.freezes_available += 1;
+            outcome.earned_freeze = true;
+        }
+        outcome
+    }
+
+    // Count the user's first solve of `problem_id` in the ledger and their streak. None if
+    // they had already solved it.
+    pub async fn update_for_problem_solve(
+        pool: &PgPool,
+        user_id: Uuid,
+        problem_id: Uuid,
+        rules: &StreakConfig,
+    ) -> Result<Option<Streak>, sqlx::Error> {
         let now = Utc::now();
-        
-        // Get existing streak
-        let existing_streak = sqlx::query_as::<_, Streak>(
-            "SELECT * FROM streaks WHERE user_id = $1"
+        let tz = Streak::timezone(pool, user_id).await?;
+        let mut tx = pool.begin().await?;
+
+        let first_solve = sqlx::query(
+            "INSERT INTO problem_solves (user_id, problem_id, solved_at) VALUES ($1, $2, $3)
+             ON CONFLICT (user_id, problem_id) DO NOTHING"
         )
         .bind(user_id)
-        .fetch_optional(pool)
-        .await?;
-        
-        let (count, longest_streak, problems_solved_today) = match existing_streak {
-            Some(streak) => {
-                let days_since_last_active = (now.date_naive() - streak.last_active.date_naive()).num_days();
-                
-                match days_since_last_active {
-                    0 => {
-                        // Same day - just increment problems solved today
-                        (streak.count, streak.longest_streak, streak.problems_solved_today + 1)
-                    },
-                    1 => {
-                        // Next day - continue streak
-                        let new_count = streak.count + 1;
-                        let new_longest = if new_count > streak.longest_streak { new_count } else { streak.longest_streak };
-                        (new_count, new_longest, 1)
-                    },
-                    _ => {
-                        // Streak broken - reset
-                        (1, streak.longest_streak, 1)
-                    }
-                }
-            },
-            None => (1, 1, 1)
-        };
+        .bind(problem_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+        if !first_solve {
+            return Ok(None);
+        }
+        DailyActivity::add_in(&mut tx, user_id, streak_day(now, tz, rules), ActivityKind::ProblemSolved, 1).await?;
+
+        // Get existing streak, locked so two solves can't spend the same freeze
+        let existing_streak = sqlx::query_as::<_, Streak>("SELECT * FROM streaks WHERE user_id = $1 FOR UPDATE")
+            .bind(user_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let outcome = Streak::after_solve(existing_streak.as_ref(), now, tz, rules);
         
         // Insert or update the streak
         let streak = sqlx::query_as::<_, Streak>(
-            "INSERT INTO streaks (id, user_id, count, last_active, created_at, longest_streak, problems_solved_today)
-             VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "INSERT INTO streaks (id, user_id, count, last_active, created_at, longest_streak, problems_solved_today,
+                                  freezes_available)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
              ON CONFLICT (user_id) DO UPDATE SET 
                 count = EXCLUDED.count,
                 last_active = EXCLUDED.last_active,
                 longest_streak = EXCLUDED.longest_streak,
-                problems_solved_today = EXCLUDED.problems_solved_today
+                problems_solved_today = EXCLUDED.problems_solved_today,
+                freezes_available = EXCLUDED.freezes_available
              RETURNING *"
         )
         .bind(Uuid::new_v4())
         .bind(user_id)
-        .bind(count)
+        .bind(outcome.count)
         .bind(now)
         .bind(now)
-        .bind(longest_streak)
-        .bind(problems_solved_today)
-        .fetch_one(pool)
+        .bind(outcome.longest_streak)
+        .bind(outcome.problems_solved_today)
+        .bind(outcome.freezes_available)
+        .fetch_one(&mut *tx)
         .await?;
 
-        Ok(streak)
+        for day in &outcome.frozen_days {
+            Streak::record_freeze_event(&mut tx, user_id, FreezeKind::Used, Some(*day)).await?;
+        }
+        if outcome.earned_freeze {
+            Streak::record_freeze_event(&mut tx, user_id, FreezeKind::Earned, None).await?;
+        }
+
+        tx.commit().await?;
+        Ok(Some(streak))
+    }
+
+    pub async fn record_freeze_event(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: Uuid,
+        kind: FreezeKind,
+        day: Option<NaiveDate>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO streak_freeze_events (id, user_id, kind, day) VALUES ($1, $2, $3, $4)")
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(kind)
+            .bind(day)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    // The zone the user's streak days are counted in
+    pub async fn timezone(pool: &PgPool, user_id: Uuid) -> Result<Tz, sqlx::Error> {
+        let name = sqlx::query_scalar::<_, String>("SELECT timezone FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(name.as_deref().and_then(parse_timezone).unwrap_or(Tz::UTC))
     }
 
     pub async fn get_by_user_id(pool: &PgPool, user_id: Uuid) -> Result<Option<Streak>, sqlx::Error> {
@@ -98,6 +277,7 @@ impl Streak {
         .await
     }
 
+    // An empty streak; only solves add to it
     pub async fn create_new(pool: &PgPool, user_id: Uuid) -> Result<Streak, sqlx::Error> {
         let now = Utc::now();
         sqlx::query_as::<_, Streak>(
@@ -107,56 +287,220 @@ impl Streak {
         )
         .bind(Uuid::new_v4())
         .bind(user_id)
-        .bind(1)
+        .bind(0)
         .bind(now)
         .bind(now)
-        .bind(1)
-        .bind(1)
+        .bind(0)
+        .bind(0)
         .fetch_one(pool)
         .await
     }
 
-    pub async fn get_stats(&self, pool: &PgPool) -> Result<StreakStats, sqlx::Error> {
+    // (current_streak, problems_solved_today) as of `now`. The stored counts are only
+    // written on a solve, so a streak has lapsed once more days were missed than the
+    // freezes can cover.
+    pub fn current(&self, now: DateTime<Utc>, tz: Tz, rules: &StreakConfig) -> (i32, i32) {
+        let today = streak_day(now, tz, rules);
+        let last_day = streak_day(self.last_active, tz, rules);
+
+        let alive_until = last_day + Duration::days(1 + self.freezes_available as i64);
+        let count = if today <= alive_until { self.count } else { 0 };
+        let solved_today = if last_day == today { self.problems_solved_today } else { 0 };
+        (count, solved_today)
+    }
+
+    // The streak from the ledger's days with a solve and the days a freeze covered, both in
+    // order. Consecutive covered days make one streak; frozen days keep it going without
+    // adding to it.
+    pub fn derive(solve_days: &[DailyActivity], frozen_days: &[NaiveDate]) -> DerivedStreak {
+        let days: Vec<NaiveDate> = solve_days.iter().map(|a| a.day).collect();
+        let mut covered: Vec<(NaiveDate, bool)> = days
+            .iter()
+            .map(|day| (*day, true))
+            .chain(frozen_days.iter().filter(|day| !days.contains(day)).map(|day| (*day, false)))
+            .collect();
+        covered.sort_unstable();
+
+        let (mut count, mut longest) = (0, 0);
+        let mut previous: Option<NaiveDate> = None;
+        for (day, solved) in covered {
+            if previous.is_some_and(|p| day - p > Duration::days(1)) {
+                count = 0;
+            }
+            if solved {
+                count += 1;
+                longest = longest.max(count);
+            }
+            previous = Some(day);
+        }
+
+        DerivedStreak {
+            count,
+            longest_streak: longest,
+            problems_solved_today: solve_days.last().map_or(0, |a| a.problems_solved),
+        }
+    }
+
+    // Stats from the days they solved something in the last 30 streak days
+    pub fn stats(
+        &self,
+        active_days: &[NaiveDate],
+        freezes_used: Vec<StreakFreezeEvent>,
+        tz: Tz,
+        rules: &StreakConfig,
+    ) -> StreakStats {
         let now = Utc::now();
-        
-        // Calculate streak percentage for last 30 days
-        let thirty_days_ago = now - Duration::days(30);
-        let active_days = sqlx::query!(
-            "SELECT COUNT(DISTINCT DATE(created_at)) as active_days
-             FROM problems 
-             WHERE user_id = $1 AND created_at >= $2",
-            self.user_id,
-            thirty_days_ago
-        )
-        .fetch_one(pool)
-        .await?;
+        let today = streak_day(now, tz, rules);
+        let thirty_days_ago = today - Duration::days(30);
 
-        let streak_percentage = (active_days.active_days.unwrap_or(0) as f32 / 30.0) * 100.0;
+        // Calculate streak percentage for last 30 days
+        let recent_days = active_days.iter().filter(|day| **day >= thirty_days_ago).count();
+        let streak_percentage = (recent_days as f32 / 30.0) * 100.0;
 
         // Get weekly activity (last 7 days)
-        let mut weekly_activity = Vec::new();
-        for i in 0..7 {
-            let day = now.date_naive() - Duration::days(6 - i);
-            let has_activity = sqlx::query!(
-                "SELECT COUNT(*) as count
-                 FROM problems 
-                 WHERE user_id = $1 AND DATE(created_at) = $2",
-                self.user_id,
-                day
-            )
-            .fetch_one(pool)
-            .await?;
+        let weekly_activity = (0..7)
+            .map(|i| today - Duration::days(6 - i))
+            .map(|day| active_days.contains(&day))
+            .collect();
 
-            weekly_activity.push(has_activity.count.unwrap_or(0) > 0);
-        }
+        let (current_streak, problems_solved_today) = self.current(now, tz, rules);
 
-        Ok(StreakStats {
-            current_streak: self.count,
+        StreakStats {
+            current_streak,
             longest_streak: self.longest_streak,
-            problems_solved_today: self.problems_solved_today,
+            problems_solved_today,
             last_active: self.last_active,
             streak_percentage,
             weekly_activity,
-        })
+            freezes_available: self.freezes_available,
+            freezes_used,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 3, day, hour, 0, 0).unwrap()
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    fn streak(count: i32, last_active: DateTime<Utc>, freezes_available: i32) -> Streak {
+        Streak {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            count,
+            last_active,
+            created_at: last_active,
+            longest_streak: count,
+            problems_solved_today: 1,
+            freezes_available,
+        }
+    }
+
+    fn solved(day: u32, problems_solved: i32) -> DailyActivity {
+        DailyActivity { day: date(day), problems_created: 0, problems_solved, solutions_posted: 0, feedback_given: 0 }
+    }
+
+    #[test]
+    fn grace_hours_count_for_the_day_before() {
+        let rules = StreakConfig::default();
+
+        assert_eq!(streak_day(at(10, 1), chrono_tz::UTC, &rules), date(9));
+        assert_eq!(streak_day(at(10, 2), chrono_tz::UTC, &rules), date(10));
+        // 23:30 UTC on the 9th is 01:30 on the 10th in Berlin, still the 9th's streak day
+        let late = Utc.with_ymd_and_hms(2025, 3, 9, 23, 30, 0).unwrap();
+        assert_eq!(streak_day(late, chrono_tz::Europe::Berlin, &rules), date(9));
+    }
+
+    #[test]
+    fn first_solve_starts_a_streak() {
+        let outcome = Streak::after_solve(None, at(10, 12), chrono_tz::UTC, &StreakConfig::default());
+
+        assert_eq!((outcome.count, outcome.longest_streak, outcome.problems_solved_today), (1, 1, 1));
+        assert!(outcome.frozen_days.is_empty());
+    }
+
+    #[test]
+    fn same_day_solves_only_add_to_today() {
+        let existing = streak(3, at(10, 9), 0);
+        let outcome = Streak::after_solve(Some(&existing), at(10, 20), chrono_tz::UTC, &StreakConfig::default());
+
+        assert_eq!((outcome.count, outcome.problems_solved_today), (3, 2));
+    }
+
+    #[test]
+    fn next_day_solve_extends_the_streak() {
+        let existing = streak(3, at(10, 9), 0);
+        let outcome = Streak::after_solve(Some(&existing), at(11, 9), chrono_tz::UTC, &StreakConfig::default());
+
+        assert_eq!((outcome.count, outcome.longest_streak, outcome.problems_solved_today), (4, 4, 1));
+    }
+
+    #[test]
+    fn freezes_bridge_a_gap_they_fully_cover() {
+        let existing = streak(3, at(10, 9), 2);
+        let outcome = Streak::after_solve(Some(&existing), at(13, 9), chrono_tz::UTC, &StreakConfig::default());
+
+        assert_eq!((outcome.count, outcome.freezes_available), (4, 0));
+        assert_eq!(outcome.frozen_days, [date(11), date(12)]);
+    }
+
+    #[test]
+    fn gap_longer_than_the_freezes_restarts_and_keeps_them() {
+        let mut existing = streak(3, at(10, 9), 1);
+        existing.longest_streak = 5;
+        let outcome = Streak::after_solve(Some(&existing), at(13, 9), chrono_tz::UTC, &StreakConfig::default());
+
+        assert_eq!((outcome.count, outcome.longest_streak, outcome.freezes_available), (1, 5, 1));
+        assert!(outcome.frozen_days.is_empty());
+    }
+
+    #[test]
+    fn freezes_are_earned_up_to_the_cap() {
+        let rules = StreakConfig::default();
+
+        let outcome = Streak::after_solve(Some(&streak(6, at(10, 9), 0)), at(11, 9), chrono_tz::UTC, &rules);
+        assert_eq!((outcome.count, outcome.freezes_available, outcome.earned_freeze), (7, 1, true));
+
+        let capped = streak(6, at(10, 9), rules.max_earned_freezes);
+        let outcome = Streak::after_solve(Some(&capped), at(11, 9), chrono_tz::UTC, &rules);
+        assert_eq!((outcome.freezes_available, outcome.earned_freeze), (rules.max_earned_freezes, false));
+    }
+
+    #[test]
+    fn current_lapses_once_the_freezes_run_out() {
+        let rules = StreakConfig::default();
+        let existing = streak(4, at(10, 9), 1);
+
+        assert_eq!(existing.current(at(10, 23), chrono_tz::UTC, &rules), (4, 1));
+        assert_eq!(existing.current(at(12, 9), chrono_tz::UTC, &rules), (4, 0));
+        assert_eq!(existing.current(at(13, 9), chrono_tz::UTC, &rules), (0, 0));
+    }
+
+    #[test]
+    fn derive_counts_consecutive_solve_days() {
+        let derived = Streak::derive(&[solved(1, 1), solved(2, 2), solved(4, 1), solved(5, 3)], &[]);
+
+        assert_eq!(derived, DerivedStreak { count: 2, longest_streak: 2, problems_solved_today: 3 });
+    }
+
+    #[test]
+    fn derive_bridges_frozen_days_without_counting_them() {
+        let derived = Streak::derive(&[solved(1, 1), solved(2, 1), solved(5, 1)], &[date(3), date(4)]);
+
+        assert_eq!(derived, DerivedStreak { count: 3, longest_streak: 3, problems_solved_today: 1 });
+    }
+
+    #[test]
+    fn derive_of_nothing_is_zero() {
+        assert_eq!(Streak::derive(&[], &[date(3)]), DerivedStreak { count: 0, longest_streak: 0, problems_solved_today: 0 });
     }
 }
@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const MAX_TAG_LENGTH: usize = 50;
+pub const MAX_TAGS_PER_PROBLEM: usize = 10;
+
+// Lowercase with every run of other characters collapsed into '-', so "Binary Search"
+// and "binary_search" are the same tag. Keeps + # . for names like c++ and c#. The tags
+// migration applies the same rule to existing data. None if nothing is left.
+pub fn normalize_tag(raw: &str) -> Option<String> {
+    let mut name = String::with_capacity(raw.len());
+    for c in raw.trim().to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() || matches!(c, '+' | '#' | '.') {
+            name.push(c);
+        } else if !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+
+    // Cut to length before trimming, so a cut that lands on a separator can't leave a trailing '-'
+    let name: String = name.chars().take(MAX_TAG_LENGTH).collect();
+    Some(name.trim_matches('-').to_string()).filter(|n| !n.is_empty())
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Tag {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// A tag with the number of problems carrying it
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TagUsage {
+    pub id: Uuid,
+    pub name: String,
+    pub usage_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TagAlias {
+    pub alias: String,
+    pub tag_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+// GET /api/tags?q=bin&limit=10. Without q, the most used tags.
+#[derive(Debug, Deserialize)]
+pub struct TagQuery {
+    pub q: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetTagsRequest {
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetCategoriesRequest {
+    pub category_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeTagRequest {
+    // The tag that survives
+    pub into: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTagAliasRequest {
+    pub alias: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_separators_and_case() {
+        assert_eq!(normalize_tag("  Binary Search ").as_deref(), Some("binary-search"));
+        assert_eq!(normalize_tag("binary__search").as_deref(), Some("binary-search"));
+        assert_eq!(normalize_tag("C++").as_deref(), Some("c++"));
+        assert_eq!(normalize_tag(" -_- "), None);
+    }
+
+    #[test]
+    fn truncates_before_trimming_separators() {
+        // The 50th character is the separator between the two words
+        let raw = format!("{} tail", "a".repeat(MAX_TAG_LENGTH - 1));
+        let name = normalize_tag(&raw).unwrap();
+
+        assert_eq!(name, "a".repeat(MAX_TAG_LENGTH - 1));
+        assert!(name.len() <= MAX_TAG_LENGTH);
+    }
+}
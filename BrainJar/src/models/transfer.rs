@@ -0,0 +1,148 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::problem::{Difficulty, Problem, Visibility};
+use crate::models::solution::SolutionResponse;
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferFormat {
+    #[default]
+    Json,
+    Csv,
+    // Problems as documents of YAML-style front matter followed by the description
+    #[serde(alias = "md")]
+    Markdown,
+}
+
+impl TransferFormat {
+    // For imports without ?format=
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        match content_type.split(';').next()?.trim() {
+            "application/json" => Some(TransferFormat::Json),
+            "text/csv" => Some(TransferFormat::Csv),
+            "text/markdown" => Some(TransferFormat::Markdown),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            TransferFormat::Json => "application/json",
+            TransferFormat::Csv => "text/csv; charset=utf-8",
+            TransferFormat::Markdown => "text/markdown; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TransferFormat::Json => "json",
+            TransferFormat::Csv => "csv",
+            TransferFormat::Markdown => "md",
+        }
+    }
+}
+
+// POST /api/problems/import?format=csv&dry_run=true
+#[derive(Debug, Deserialize)]
+pub struct ImportParams {
+    pub format: Option<TransferFormat>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportParams {
+    pub format: Option<TransferFormat>,
+}
+
+// A problem as read from an import file, before validation
+#[derive(Debug, Default)]
+pub struct ImportRow {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub difficulty: Option<String>,
+    pub tags: Vec<String>,
+    pub links: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportRowReport {
+    // 1-based position in the file, not counting a CSV header
+    pub row: usize,
+    pub title: Option<String>,
+    // Field name -> what's wrong with it. Empty for valid rows.
+    pub errors: serde_json::Map<String, serde_json::Value>,
+    pub problem_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub total: usize,
+    pub valid: usize,
+    pub imported: usize,
+    pub rows: Vec<ImportRowReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportedSolution {
+    pub id: Uuid,
+    pub author: String,
+    pub solution_text: String,
+    pub is_accepted: bool,
+    pub score: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+// Field names match what import reads, so a JSON export can be imported again. Problems
+// of other users that the caller answered come with `owned: false` and only the caller's
+// solutions.
+#[derive(Debug, Serialize)]
+pub struct ExportedProblem {
+    pub id: Uuid,
+    pub title: String,
+    pub description: String,
+    pub category: String,
+    pub difficulty: Option<Difficulty>,
+    pub tags: Vec<String>,
+    pub links: Vec<String>,
+    pub solved: bool,
+    pub visibility: Visibility,
+    pub owned: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub solutions: Vec<ExportedSolution>,
+}
+
+impl ExportedProblem {
+    pub fn new(problem: Problem, owned: bool, solutions: Vec<SolutionResponse>) -> Self {
+        ExportedProblem {
+            id: problem.id,
+            title: problem.title,
+            description: problem.description,
+            category: problem.category,
+            difficulty: problem.difficulty_level,
+            tags: problem.tags.unwrap_or_default(),
+            links: problem.documentation_links.unwrap_or_default(),
+            solved: problem.solved,
+            visibility: problem.visibility,
+            owned,
+            created_at: problem.created_at,
+            updated_at: problem.updated_at,
+            solutions: solutions
+                .into_iter()
+                .map(|s| ExportedSolution {
+                    id: s.solution_id,
+                    author: s.user_name,
+                    solution_text: s.solution_text,
+                    is_accepted: s.is_accepted,
+                    score: s.score,
+                    created_at: s.created_at,
+                })
+                .collect(),
+        }
+    }
+}
@@ -1,8 +1,26 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+// Failed logins allowed before the account is locked. Each further failure doubles
+// the lockout, starting at LOCKOUT_BASE_SECONDS and capped at LOCKOUT_MAX_SECONDS.
+pub const LOCKOUT_THRESHOLD: i32 = 5;
+const LOCKOUT_BASE_SECONDS: f64 = 30.0;
+const LOCKOUT_MAX_SECONDS: f64 = 3600.0;
+
+// Ordered from least to most privileged so guards can compare with >=
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, sqlx::Type)]
+#[sqlx(type_name = "user_role", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Moderator,
+    Admin,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct User {
     pub id: Uuid,
     pub username: String,
@@ -10,6 +28,12 @@ pub struct User {
     #[serde(skip_serializing)]
     pub password_hash: String,
     pub created_at: DateTime<Utc>,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub role: Role,
+    pub suspended_at: Option<DateTime<Utc>>,
+    pub locked_until: Option<DateTime<Utc>>,
+    // IANA name, e.g. "America/Los_Angeles". Streak days start at midnight here.
+    pub timezone: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -40,6 +64,22 @@ pub struct LoginUser {
     pub password: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct UpdateProfileRequest {
     pub username: Option<String>,
@@ -48,6 +88,107 @@ pub struct UpdateProfileRequest {
     pub theme_preference: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdateTimezoneRequest {
+    pub timezone: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdminUserQuery {
+    pub search: Option<String>,
+    pub role: Option<Role>,
+    pub suspended: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateRoleRequest {
+    pub role: Role,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuspendUserRequest {
+    pub reason: Option<String>,
+}
+
+// How long to lock an account after its `failed_attempts`-th consecutive failure, if at all.
+// Mirrors the calculation in record_failed_login for backends without SQL.
+pub fn lockout_duration(failed_attempts: i32) -> Option<Duration> {
+    if failed_attempts < LOCKOUT_THRESHOLD {
+        return None;
+    }
+
+    let seconds = (LOCKOUT_BASE_SECONDS * 2f64.powi(failed_attempts - LOCKOUT_THRESHOLD)).min(LOCKOUT_MAX_SECONDS);
+    Some(Duration::milliseconds((seconds * 1000.0) as i64))
+}
+
+// A zone from the IANA database, e.g. "Europe/Berlin" or "UTC"
+pub fn parse_timezone(name: &str) -> Option<Tz> {
+    name.trim().parse().ok()
+}
+
+impl User {
+    pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+    }
+
+    // Unknown names (which the API never stores) count as UTC
+    pub fn tz(&self) -> Tz {
+        parse_timezone(&self.timezone).unwrap_or(Tz::UTC)
+    }
+
+    // Seconds until a locked account accepts logins again, if it is locked
+    pub fn lockout_remaining(&self) -> Option<i64> {
+        self.locked_until
+            .map(|until| (until - Utc::now()).num_seconds())
+            .filter(|secs| *secs > 0)
+    }
+
+    // Count a failed login and lock the account once the threshold is reached
+    pub async fn record_failed_login(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE users SET
+                failed_login_attempts = failed_login_attempts + 1,
+                locked_until = CASE
+                    WHEN failed_login_attempts + 1 >= $2 THEN NOW() + make_interval(
+                        secs => LEAST($4, $3 * power(2, failed_login_attempts + 1 - $2))
+                    )
+                    ELSE locked_until
+                END
+             WHERE id = $1"
+        )
+        .bind(id)
+        .bind(LOCKOUT_THRESHOLD)
+        .bind(LOCKOUT_BASE_SECONDS)
+        .bind(LOCKOUT_MAX_SECONDS)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn clear_failed_logins(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE users SET failed_login_attempts = 0, locked_until = NULL
+             WHERE id = $1 AND (failed_login_attempts > 0 OR locked_until IS NOT NULL)"
+        )
+        .bind(id)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_by_email(pool: &PgPool, email: &str) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query_as::<_, User>("SELECT * FROM users WHERE email = $1")
+            .bind(email)
+            .fetch_optional(pool)
+            .await
+    }
+}
+
 impl From<User> for UserProfile {
     fn from(user: User) -> Self {
         UserProfile {
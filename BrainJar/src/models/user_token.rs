@@ -0,0 +1,74 @@
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::TokenConfig;
+use crate::crypto::{generate_token, hash_token};
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TokenPurpose {
+    PasswordReset,
+    EmailVerification,
+}
+
+impl TokenPurpose {
+    pub fn ttl(&self, config: &TokenConfig) -> Duration {
+        match self {
+            TokenPurpose::PasswordReset => Duration::minutes(config.password_reset_ttl_minutes),
+            TokenPurpose::EmailVerification => Duration::hours(config.email_verification_ttl_hours),
+        }
+    }
+}
+
+pub struct UserToken;
+
+impl UserToken {
+    // Issue a new emailed token, invalidating any earlier unused token for the same purpose
+    pub async fn issue(pool: &PgPool, user_id: Uuid, purpose: TokenPurpose, ttl: Duration) -> Result<String, sqlx::Error> {
+        let now = Utc::now();
+        let token = generate_token(32);
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            "UPDATE user_tokens SET used_at = $3
+             WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL"
+        )
+        .bind(user_id)
+        .bind(purpose)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO user_tokens (id, user_id, purpose, token_hash, created_at, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6)"
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(purpose)
+        .bind(hash_token(&token))
+        .bind(now)
+        .bind(now + ttl)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(token)
+    }
+
+    // Mark a token as used and return its owner, or None if it is unknown, expired or already used
+    pub async fn consume(pool: &PgPool, token: &str, purpose: TokenPurpose) -> Result<Option<Uuid>, sqlx::Error> {
+        sqlx::query_scalar::<_, Uuid>(
+            "UPDATE user_tokens SET used_at = NOW()
+             WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > NOW()
+             RETURNING user_id"
+        )
+        .bind(hash_token(token))
+        .bind(purpose)
+        .fetch_optional(pool)
+        .await
+    }
+}
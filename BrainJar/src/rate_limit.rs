@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{header, Method},
+    middleware::Next,
+    web, Error, ResponseError,
+};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::crypto::hash_token;
+use crate::error::AppError;
+use crate::middleware::verify_token;
+use crate::models::access_token::TOKEN_PREFIX;
+
+// Unauthenticated endpoints that are worth brute-forcing, limited per client IP
+const AUTH_PATHS: &[&str] = &[
+    "/api/auth/login",
+    "/api/auth/register",
+    "/api/auth/refresh",
+    "/api/auth/forgot-password",
+    "/api/auth/reset-password",
+    "/api/auth/resend-verification",
+    "/api/auth/mfa/verify",
+];
+
+const MESSAGE_PATHS: &[&str] = &["/api/messages/send", "/api/chat"];
+
+// Buckets are dropped once idle long enough to have refilled, checked when the map grows past this
+const PRUNE_THRESHOLD: usize = 10_000;
+
+// Token bucket: `burst` requests at once, refilled at `per_minute`
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Limit {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+impl Limit {
+    fn refill_per_second(&self) -> f64 {
+        self.per_minute.max(1) as f64 / 60.0
+    }
+
+    fn time_to_full(&self) -> Duration {
+        Duration::from_secs_f64(self.burst as f64 / self.refill_per_second())
+    }
+}
+
+// The `rate_limit` section of AppConfig
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    // Use X-Forwarded-For / Forwarded for the client IP. Only safe behind a proxy that sets it.
+    pub trust_proxy: bool,
+    pub auth_per_ip: Limit,
+    pub api_per_ip: Limit,
+    pub api_per_user: Limit,
+    pub messages_per_user: Limit,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            enabled: true,
+            trust_proxy: false,
+            auth_per_ip: Limit { burst: 10, per_minute: 10 },
+            api_per_ip: Limit { burst: 120, per_minute: 300 },
+            api_per_user: Limit { burst: 60, per_minute: 120 },
+            messages_per_user: Limit { burst: 10, per_minute: 20 },
+        }
+    }
+}
+
+pub enum Decision {
+    Allowed,
+    Limited { retry_after: Duration },
+}
+
+// Where buckets live. The in-process store is enough for a single instance; a shared
+// store (e.g. Redis) can implement this to limit across several.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    // Take one token from the bucket for `key`
+    async fn acquire(&self, key: &str, limit: Limit) -> Decision;
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    time_to_full: Duration,
+}
+
+#[derive(Default)]
+pub struct InMemoryRateLimitStore {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+#[async_trait]
+impl RateLimitStore for InMemoryRateLimitStore {
+    async fn acquire(&self, key: &str, limit: Limit) -> Decision {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() > PRUNE_THRESHOLD {
+            buckets.retain(|_, b| now.duration_since(b.updated) < b.time_to_full);
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert_with(|| Bucket {
+            tokens: limit.burst as f64,
+            updated: now,
+            time_to_full: limit.time_to_full(),
+        });
+
+        let rate = limit.refill_per_second();
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(limit.burst as f64);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Decision::Allowed
+        } else {
+            Decision::Limited {
+                retry_after: Duration::from_secs_f64((1.0 - bucket.tokens) / rate),
+            }
+        }
+    }
+}
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig, store: Arc<dyn RateLimitStore>) -> Self {
+        RateLimiter { config, store }
+    }
+
+    // Every bucket a request has to take a token from
+    fn buckets_for(&self, req: &ServiceRequest) -> Vec<(String, Limit)> {
+        let path = req.path();
+        if !path.starts_with("/api/") || req.method() == Method::OPTIONS {
+            return Vec::new();
+        }
+
+        let ip = if self.config.trust_proxy {
+            req.connection_info().realip_remote_addr().unwrap_or("unknown").to_string()
+        } else {
+            req.peer_addr().map(|addr| addr.ip().to_string()).unwrap_or_else(|| "unknown".to_string())
+        };
+
+        let mut buckets = vec![(format!("ip:{}", ip), self.config.api_per_ip)];
+        let identity = caller_identity(req);
+
+        if let Some(identity) = &identity {
+            buckets.push((identity.clone(), self.config.api_per_user));
+        }
+
+        if req.method() == Method::POST {
+            if AUTH_PATHS.contains(&path) {
+                buckets.push((format!("auth:{}", ip), self.config.auth_per_ip));
+            }
+
+            if MESSAGE_PATHS.contains(&path) {
+                let sender = identity.unwrap_or_else(|| format!("ip:{}", ip));
+                buckets.push((format!("messages:{}", sender), self.config.messages_per_user));
+            }
+        }
+
+        buckets
+    }
+}
+
+// Key for the authenticated caller without touching the database: the user id from a
+// valid JWT, or a hash of a personal access token (which is checked later by the extractor)
+fn caller_identity(req: &ServiceRequest) -> Option<String> {
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")?;
+
+    if token.starts_with(TOKEN_PREFIX) {
+        return Some(format!("token:{}", hash_token(token)));
+    }
+
+    verify_token(req.request()).ok().map(|user_id| format!("user:{}", user_id))
+}
+
+// Wrapped around the whole app with `middleware::from_fn`. Requests without a
+// `web::Data<RateLimiter>` or with limiting disabled pass straight through.
+pub async fn rate_limit(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let limiter = req
+        .app_data::<web::Data<RateLimiter>>()
+        .filter(|limiter| limiter.config.enabled)
+        .cloned();
+
+    if let Some(limiter) = limiter {
+        for (key, limit) in limiter.buckets_for(&req) {
+            if let Decision::Limited { retry_after } = limiter.store.acquire(&key, limit).await {
+                let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+                tracing::warn!("Rate limit exceeded for {} on {}", key, req.path());
+                let error = AppError::RateLimited {
+                    message: "Too many requests".to_string(),
+                    retry_after: retry_after_secs,
+                };
+                return Ok(req.into_response(error.error_response()).map_into_right_body());
+            }
+        }
+    }
+
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
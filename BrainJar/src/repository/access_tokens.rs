@@ -0,0 +1,174 @@
+use std::cmp::Reverse;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::crypto::hash_token;
+use crate::models::access_token::{display_prefix, new_token, AccessToken, ResolvedAccessToken};
+
+use super::{InMemoryStore, RepoResult};
+
+// Personal access tokens
+#[async_trait]
+pub trait AccessTokenRepository: Send + Sync {
+    // Create a token and return it together with its plaintext value
+    async fn create(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        scopes: &[String],
+        expires_in_days: Option<i64>,
+    ) -> RepoResult<(AccessToken, String)>;
+    async fn count_active_for_user(&self, user_id: Uuid) -> RepoResult<i64>;
+    async fn list_for_user(&self, user_id: Uuid) -> RepoResult<Vec<AccessToken>>;
+    async fn revoke(&self, token_id: Uuid, user_id: Uuid) -> RepoResult<bool>;
+    // Returns how many tokens were still active
+    async fn revoke_all_for_user(&self, user_id: Uuid) -> RepoResult<u64>;
+    // Look up a presented token and stamp its last use. Returns None for unknown, revoked or
+    // expired tokens and for tokens belonging to suspended accounts.
+    async fn resolve(&self, token: &str) -> RepoResult<Option<ResolvedAccessToken>>;
+}
+
+#[async_trait]
+impl AccessTokenRepository for PgPool {
+    async fn create(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        scopes: &[String],
+        expires_in_days: Option<i64>,
+    ) -> RepoResult<(AccessToken, String)> {
+        Ok(AccessToken::create(self, user_id, name, scopes, expires_in_days).await?)
+    }
+
+    async fn count_active_for_user(&self, user_id: Uuid) -> RepoResult<i64> {
+        Ok(AccessToken::count_active_for_user(self, user_id).await?)
+    }
+
+    async fn list_for_user(&self, user_id: Uuid) -> RepoResult<Vec<AccessToken>> {
+        Ok(AccessToken::list_for_user(self, user_id).await?)
+    }
+
+    async fn revoke(&self, token_id: Uuid, user_id: Uuid) -> RepoResult<bool> {
+        Ok(AccessToken::revoke(self, token_id, user_id).await?)
+    }
+
+    async fn revoke_all_for_user(&self, user_id: Uuid) -> RepoResult<u64> {
+        Ok(AccessToken::revoke_all_for_user(self, user_id).await?)
+    }
+
+    async fn resolve(&self, token: &str) -> RepoResult<Option<ResolvedAccessToken>> {
+        Ok(AccessToken::resolve(self, token).await?)
+    }
+}
+
+fn is_active(token: &AccessToken) -> bool {
+    token.revoked_at.is_none() && token.expires_at.is_none_or(|expires_at| expires_at > Utc::now())
+}
+
+#[async_trait]
+impl AccessTokenRepository for InMemoryStore {
+    async fn create(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        scopes: &[String],
+        expires_in_days: Option<i64>,
+    ) -> RepoResult<(AccessToken, String)> {
+        let token = new_token();
+        let access_token = AccessToken {
+            id: Uuid::new_v4(),
+            user_id,
+            name: name.to_string(),
+            token_prefix: display_prefix(&token).to_string(),
+            scopes: scopes.to_vec(),
+            created_at: Utc::now(),
+            last_used_at: None,
+            expires_at: expires_in_days.map(|days| Utc::now() + Duration::days(days)),
+            revoked_at: None,
+        };
+
+        self.state().access_tokens.insert(hash_token(&token), access_token.clone());
+        Ok((access_token, token))
+    }
+
+    async fn count_active_for_user(&self, user_id: Uuid) -> RepoResult<i64> {
+        Ok(self
+            .state()
+            .access_tokens
+            .values()
+            .filter(|t| t.user_id == user_id && is_active(t))
+            .count() as i64)
+    }
+
+    async fn list_for_user(&self, user_id: Uuid) -> RepoResult<Vec<AccessToken>> {
+        let mut tokens: Vec<AccessToken> = self
+            .state()
+            .access_tokens
+            .values()
+            .filter(|t| t.user_id == user_id && t.revoked_at.is_none())
+            .cloned()
+            .collect();
+
+        tokens.sort_by_key(|x| Reverse(x.created_at));
+        Ok(tokens)
+    }
+
+    async fn revoke(&self, token_id: Uuid, user_id: Uuid) -> RepoResult<bool> {
+        let mut state = self.state();
+        let token = state
+            .access_tokens
+            .values_mut()
+            .find(|t| t.id == token_id && t.user_id == user_id && t.revoked_at.is_none());
+
+        Ok(match token {
+            Some(token) => {
+                token.revoked_at = Some(Utc::now());
+                true
+            }
+            None => false,
+        })
+    }
+
+    async fn revoke_all_for_user(&self, user_id: Uuid) -> RepoResult<u64> {
+        let mut state = self.state();
+        let now = Utc::now();
+        let mut revoked = 0;
+        for token in state.access_tokens.values_mut().filter(|t| t.user_id == user_id && t.revoked_at.is_none()) {
+            token.revoked_at = Some(now);
+            revoked += 1;
+        }
+        Ok(revoked)
+    }
+
+    async fn resolve(&self, token: &str) -> RepoResult<Option<ResolvedAccessToken>> {
+        let mut state = self.state();
+
+        let Some(access_token) = state.access_tokens.get(&hash_token(token)).filter(|t| is_active(t)) else {
+            return Ok(None);
+        };
+
+        let Some(role) = state
+            .users
+            .get(&access_token.user_id)
+            .filter(|record| record.user.suspended_at.is_none())
+            .map(|record| record.user.role)
+        else {
+            return Ok(None);
+        };
+
+        let resolved = ResolvedAccessToken {
+            user_id: access_token.user_id,
+            scopes: access_token.scopes.clone(),
+            role,
+        };
+
+        if let Some(access_token) = state.access_tokens.get_mut(&hash_token(token)) {
+            access_token.last_used_at = Some(Utc::now());
+        }
+
+        Ok(Some(resolved))
+    }
+}
@@ -0,0 +1,111 @@
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::StreakConfig;
+use crate::models::activity::{ActivityKind, DailyActivity};
+use crate::models::streak::{streak_day, Streak};
+
+use super::memory::State;
+use super::{InMemoryStore, RepoResult};
+
+// The per-user, per-day activity ledger behind streaks and the contribution heatmap
+#[async_trait]
+pub trait ActivityRepository: Send + Sync {
+    // Count `count` of `kind` towards the user's current streak day
+    async fn record(&self, user_id: Uuid, kind: ActivityKind, count: i32, rules: &StreakConfig) -> RepoResult<()>;
+    // Every day from `from` to `to` inclusive, in order, days without activity included
+    async fn range(&self, user_id: Uuid, from: NaiveDate, to: NaiveDate) -> RepoResult<Vec<DailyActivity>>;
+    // Days with at least one solve, oldest first, from `since` if given
+    async fn solve_days(&self, user_id: Uuid, since: Option<NaiveDate>) -> RepoResult<Vec<DailyActivity>>;
+}
+
+#[async_trait]
+impl ActivityRepository for PgPool {
+    async fn record(&self, user_id: Uuid, kind: ActivityKind, count: i32, rules: &StreakConfig) -> RepoResult<()> {
+        let tz = Streak::timezone(self, user_id).await?;
+        let mut conn = self.acquire().await?;
+        DailyActivity::add_in(&mut conn, user_id, streak_day(Utc::now(), tz, rules), kind, count).await?;
+        Ok(())
+    }
+
+    async fn range(&self, user_id: Uuid, from: NaiveDate, to: NaiveDate) -> RepoResult<Vec<DailyActivity>> {
+        Ok(sqlx::query_as::<_, DailyActivity>(
+            "SELECT d::date AS day,
+                    COALESCE(a.problems_created, 0) AS problems_created,
+                    COALESCE(a.problems_solved, 0) AS problems_solved,
+                    COALESCE(a.solutions_posted, 0) AS solutions_posted,
+                    COALESCE(a.feedback_given, 0) AS feedback_given
+             FROM generate_series($2::date, $3::date, INTERVAL '1 day') AS d
+             LEFT JOIN daily_activity a ON a.user_id = $1 AND a.day = d::date
+             ORDER BY d"
+        )
+        .bind(user_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn solve_days(&self, user_id: Uuid, since: Option<NaiveDate>) -> RepoResult<Vec<DailyActivity>> {
+        Ok(sqlx::query_as::<_, DailyActivity>(
+            "SELECT day, problems_created, problems_solved, solutions_posted, feedback_given
+             FROM daily_activity
+             WHERE user_id = $1 AND problems_solved > 0 AND ($2::date IS NULL OR day >= $2)
+             ORDER BY day"
+        )
+        .bind(user_id)
+        .bind(since)
+        .fetch_all(self)
+        .await?)
+    }
+}
+
+impl State {
+    pub(super) fn add_activity(&mut self, user_id: Uuid, day: NaiveDate, kind: ActivityKind, count: i32) {
+        self.daily_activity
+            .entry(user_id)
+            .or_default()
+            .entry(day)
+            .or_insert_with(|| DailyActivity::empty(day))
+            .add(kind, count);
+    }
+
+    pub(super) fn solve_days(&self, user_id: Uuid, since: Option<NaiveDate>) -> Vec<DailyActivity> {
+        self.daily_activity
+            .get(&user_id)
+            .into_iter()
+            .flat_map(|days| days.range(since.unwrap_or(NaiveDate::MIN)..))
+            .map(|(_, activity)| activity)
+            .filter(|activity| activity.problems_solved > 0)
+            .cloned()
+            .collect()
+    }
+}
+
+#[async_trait]
+impl ActivityRepository for InMemoryStore {
+    async fn record(&self, user_id: Uuid, kind: ActivityKind, count: i32, rules: &StreakConfig) -> RepoResult<()> {
+        let mut state = self.state();
+        let day = streak_day(Utc::now(), state.timezone(user_id), rules);
+        state.add_activity(user_id, day, kind, count);
+        Ok(())
+    }
+
+    async fn range(&self, user_id: Uuid, from: NaiveDate, to: NaiveDate) -> RepoResult<Vec<DailyActivity>> {
+        let state = self.state();
+        let recorded = state.daily_activity.get(&user_id);
+
+        Ok(from
+            .iter_days()
+            .take_while(|day| *day <= to)
+            .map(|day| recorded.and_then(|days| days.get(&day)).cloned().unwrap_or_else(|| DailyActivity::empty(day)))
+            .collect())
+    }
+
+    async fn solve_days(&self, user_id: Uuid, since: Option<NaiveDate>) -> RepoResult<Vec<DailyActivity>> {
+        Ok(self.state().solve_days(user_id, since))
+    }
+}
+
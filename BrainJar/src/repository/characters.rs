@@ -0,0 +1,91 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::character::{Character, CreateCharacter, UpdateCharacter};
+
+use super::{InMemoryStore, RepoResult};
+
+// Each user's character (avatar, bio, personality)
+#[async_trait]
+pub trait CharacterRepository: Send + Sync {
+    async fn find_for_user(&self, user_id: Uuid) -> RepoResult<Option<Character>>;
+    // Create the user's character or replace every field of the existing one
+    async fn upsert(&self, user_id: Uuid, character: CreateCharacter) -> RepoResult<Character>;
+    // Change only the fields that are set. None if the user has no character yet.
+    async fn update(&self, user_id: Uuid, changes: &UpdateCharacter) -> RepoResult<Option<Character>>;
+}
+
+#[async_trait]
+impl CharacterRepository for PgPool {
+    async fn find_for_user(&self, user_id: Uuid) -> RepoResult<Option<Character>> {
+        Ok(Character::get_by_user_id(self, user_id).await?)
+    }
+
+    async fn upsert(&self, user_id: Uuid, character: CreateCharacter) -> RepoResult<Character> {
+        Ok(Character::create_or_update_for_user(self, user_id, character).await?)
+    }
+
+    async fn update(&self, user_id: Uuid, changes: &UpdateCharacter) -> RepoResult<Option<Character>> {
+        Ok(sqlx::query_as::<_, Character>(
+            "UPDATE characters
+             SET name = COALESCE($2, name),
+                 avatar_url = COALESCE($3, avatar_url),
+                 bio = COALESCE($4, bio),
+                 personality_traits = COALESCE($5, personality_traits)
+             WHERE user_id = $1
+             RETURNING *"
+        )
+        .bind(user_id)
+        .bind(&changes.name)
+        .bind(&changes.avatar_url)
+        .bind(&changes.bio)
+        .bind(&changes.personality_traits)
+        .fetch_optional(self)
+        .await?)
+    }
+}
+
+#[async_trait]
+impl CharacterRepository for InMemoryStore {
+    async fn find_for_user(&self, user_id: Uuid) -> RepoResult<Option<Character>> {
+        Ok(self.state().characters.get(&user_id).cloned())
+    }
+
+    async fn upsert(&self, user_id: Uuid, character: CreateCharacter) -> RepoResult<Character> {
+        let mut state = self.state();
+        let existing = state.characters.get(&user_id);
+
+        let character = Character {
+            id: existing.map_or_else(Uuid::new_v4, |c| c.id),
+            user_id,
+            name: Some(character.name.unwrap_or_else(|| "Character".to_string())),
+            avatar_url: character.avatar_url,
+            bio: character.bio,
+            personality_traits: character.personality_traits.unwrap_or_default(),
+            created_at: existing.map_or_else(|| Some(Utc::now()), |c| c.created_at),
+        };
+
+        state.characters.insert(user_id, character.clone());
+        Ok(character)
+    }
+
+    async fn update(&self, user_id: Uuid, changes: &UpdateCharacter) -> RepoResult<Option<Character>> {
+        Ok(self.state().characters.get_mut(&user_id).map(|character| {
+            if let Some(name) = &changes.name {
+                character.name = Some(name.clone());
+            }
+            if let Some(avatar_url) = &changes.avatar_url {
+                character.avatar_url = Some(avatar_url.clone());
+            }
+            if let Some(bio) = &changes.bio {
+                character.bio = Some(bio.clone());
+            }
+            if let Some(traits) = &changes.personality_traits {
+                character.personality_traits = traits.clone();
+            }
+            character.clone()
+        }))
+    }
+}
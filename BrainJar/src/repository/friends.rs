@@ -0,0 +1,281 @@
+use std::cmp::Reverse;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::friend::{
+    Friend, FriendRequest, FriendSummary, PendingFriendRequest, RequestStatus, UserSummary,
+};
+use crate::models::user::User;
+
+use super::{InMemoryStore, RepoResult};
+
+// Friend requests and the friendships they create once accepted
+#[async_trait]
+pub trait FriendRepository: Send + Sync {
+    async fn send_request(&self, sender_id: Uuid, receiver_id: Uuid) -> RepoResult<()>;
+    async fn pending_requests(&self, receiver_id: Uuid) -> RepoResult<Vec<PendingFriendRequest>>;
+    // Accept or reject a pending request addressed to `receiver_id`. Accepting makes both
+    // users friends. Returns false if there is no such pending request.
+    async fn respond(&self, request_id: Uuid, receiver_id: Uuid, accept: bool) -> RepoResult<bool>;
+    async fn list_friends(&self, user_id: Uuid) -> RepoResult<Vec<FriendSummary>>;
+    // Users who are neither friends nor have a pending or accepted request with `user_id`
+    async fn suggestions(&self, user_id: Uuid) -> RepoResult<Vec<User>>;
+    // Other users whose username contains `term`, case-insensitively
+    async fn search_users(&self, user_id: Uuid, term: &str) -> RepoResult<Vec<UserSummary>>;
+    async fn are_friends(&self, user_id: Uuid, other_id: Uuid) -> RepoResult<bool>;
+}
+
+const SUGGESTION_LIMIT: usize = 20;
+const SEARCH_LIMIT: usize = 20;
+
+#[async_trait]
+impl FriendRepository for PgPool {
+    async fn send_request(&self, sender_id: Uuid, receiver_id: Uuid) -> RepoResult<()> {
+        sqlx::query(
+            "INSERT INTO friend_requests (sender_id, receiver_id, status) VALUES ($1, $2, 'pending')"
+        )
+        .bind(sender_id)
+        .bind(receiver_id)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn pending_requests(&self, receiver_id: Uuid) -> RepoResult<Vec<PendingFriendRequest>> {
+        Ok(sqlx::query_as::<_, PendingFriendRequest>(
+            "SELECT fr.id, fr.sender_id, u.username, fr.created_at
+             FROM friend_requests fr
+             JOIN users u ON fr.sender_id = u.id
+             WHERE fr.receiver_id = $1 AND fr.status = 'pending'
+             ORDER BY fr.created_at DESC"
+        )
+        .bind(receiver_id)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn respond(&self, request_id: Uuid, receiver_id: Uuid, accept: bool) -> RepoResult<bool> {
+        let status = if accept { RequestStatus::Accepted } else { RequestStatus::Rejected };
+
+        // The friendship rows are created by the trigger on friend_requests
+        let result = sqlx::query(
+            "UPDATE friend_requests SET status = $3, updated_at = NOW()
+             WHERE id = $1 AND receiver_id = $2 AND status = 'pending'"
+        )
+        .bind(request_id)
+        .bind(receiver_id)
+        .bind(status)
+        .execute(self)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_friends(&self, user_id: Uuid) -> RepoResult<Vec<FriendSummary>> {
+        Ok(sqlx::query_as::<_, FriendSummary>(
+            "SELECT f.id, f.friend_id, u.username AS friend_username, u.email AS friend_email, f.created_at
+             FROM friends f
+             JOIN users u ON f.friend_id = u.id
+             WHERE f.user_id = $1
+             ORDER BY f.created_at DESC"
+        )
+        .bind(user_id)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn suggestions(&self, user_id: Uuid) -> RepoResult<Vec<User>> {
+        Ok(sqlx::query_as::<_, User>(
+            "SELECT u.*
+             FROM users u
+             WHERE u.id != $1
+             AND u.id NOT IN (SELECT friend_id FROM friends WHERE user_id = $1)
+             AND u.id NOT IN (SELECT receiver_id FROM friend_requests WHERE sender_id = $1 AND status IN ('pending', 'accepted'))
+             AND u.id NOT IN (SELECT sender_id FROM friend_requests WHERE receiver_id = $1 AND status IN ('pending', 'accepted'))
+             ORDER BY u.username
+             LIMIT $2"
+        )
+        .bind(user_id)
+        .bind(SUGGESTION_LIMIT as i64)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn search_users(&self, user_id: Uuid, term: &str) -> RepoResult<Vec<UserSummary>> {
+        Ok(sqlx::query_as::<_, UserSummary>(
+            "SELECT u.id, u.username, u.email
+             FROM users u
+             WHERE u.id != $1 AND LOWER(u.username) LIKE $2
+             ORDER BY u.username
+             LIMIT $3"
+        )
+        .bind(user_id)
+        .bind(format!("%{}%", term.to_lowercase()))
+        .bind(SEARCH_LIMIT as i64)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn are_friends(&self, user_id: Uuid, other_id: Uuid) -> RepoResult<bool> {
+        Ok(sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(
+                SELECT 1 FROM friends
+                WHERE (user_id = $1 AND friend_id = $2)
+                   OR (user_id = $2 AND friend_id = $1)
+             )"
+        )
+        .bind(user_id)
+        .bind(other_id)
+        .fetch_one(self)
+        .await?)
+    }
+}
+
+fn befriend(friends: &mut Vec<Friend>, user_id: Uuid, friend_id: Uuid) {
+    if !friends.iter().any(|f| f.user_id == user_id && f.friend_id == friend_id) {
+        friends.push(Friend { id: Uuid::new_v4(), user_id, friend_id, created_at: Utc::now() });
+    }
+}
+
+#[async_trait]
+impl FriendRepository for InMemoryStore {
+    async fn send_request(&self, sender_id: Uuid, receiver_id: Uuid) -> RepoResult<()> {
+        let mut state = self.state();
+        if !state.users.contains_key(&receiver_id) {
+            return Err(AppError::NotFound("User not found".into()));
+        }
+
+        if state
+            .friend_requests
+            .iter()
+            .any(|r| r.sender_id == sender_id && r.receiver_id == receiver_id)
+        {
+            return Err(AppError::Conflict("Friend request already sent".into()));
+        }
+
+        let now = Utc::now();
+        state.friend_requests.push(FriendRequest {
+            id: Uuid::new_v4(),
+            sender_id,
+            receiver_id,
+            status: RequestStatus::Pending,
+            created_at: now,
+            updated_at: now,
+        });
+
+        Ok(())
+    }
+
+    async fn pending_requests(&self, receiver_id: Uuid) -> RepoResult<Vec<PendingFriendRequest>> {
+        let state = self.state();
+        let mut requests: Vec<PendingFriendRequest> = state
+            .friend_requests
+            .iter()
+            .filter(|r| r.receiver_id == receiver_id && matches!(r.status, RequestStatus::Pending))
+            .filter_map(|r| {
+                Some(PendingFriendRequest {
+                    id: r.id,
+                    sender_id: r.sender_id,
+                    username: state.username(r.sender_id)?,
+                    created_at: r.created_at,
+                })
+            })
+            .collect();
+
+        requests.sort_by_key(|x| Reverse(x.created_at));
+        Ok(requests)
+    }
+
+    async fn respond(&self, request_id: Uuid, receiver_id: Uuid, accept: bool) -> RepoResult<bool> {
+        let mut state = self.state();
+        let Some(request) = state.friend_requests.iter_mut().find(|r| {
+            r.id == request_id && r.receiver_id == receiver_id && matches!(r.status, RequestStatus::Pending)
+        }) else {
+            return Ok(false);
+        };
+
+        request.status = if accept { RequestStatus::Accepted } else { RequestStatus::Rejected };
+        request.updated_at = Utc::now();
+
+        if accept {
+            let sender_id = request.sender_id;
+            befriend(&mut state.friends, sender_id, receiver_id);
+            befriend(&mut state.friends, receiver_id, sender_id);
+        }
+
+        Ok(true)
+    }
+
+    async fn list_friends(&self, user_id: Uuid) -> RepoResult<Vec<FriendSummary>> {
+        let state = self.state();
+        let mut friends: Vec<FriendSummary> = state
+            .friends
+            .iter()
+            .filter(|f| f.user_id == user_id)
+            .filter_map(|f| {
+                let friend = &state.users.get(&f.friend_id)?.user;
+                Some(FriendSummary {
+                    id: f.id,
+                    friend_id: f.friend_id,
+                    friend_username: friend.username.clone(),
+                    friend_email: Some(friend.email.clone()),
+                    created_at: f.created_at,
+                })
+            })
+            .collect();
+
+        friends.sort_by_key(|x| Reverse(x.created_at));
+        Ok(friends)
+    }
+
+    async fn suggestions(&self, user_id: Uuid) -> RepoResult<Vec<User>> {
+        let state = self.state();
+        let connected = |other: Uuid| {
+            state.friends.iter().any(|f| f.user_id == user_id && f.friend_id == other)
+                || state.friend_requests.iter().any(|r| {
+                    !matches!(r.status, RequestStatus::Rejected)
+                        && ((r.sender_id == user_id && r.receiver_id == other)
+                            || (r.sender_id == other && r.receiver_id == user_id))
+                })
+        };
+
+        let mut users: Vec<User> = state
+            .users
+            .values()
+            .map(|record| &record.user)
+            .filter(|u| u.id != user_id && !connected(u.id))
+            .cloned()
+            .collect();
+
+        users.sort_by(|a, b| a.username.cmp(&b.username));
+        users.truncate(SUGGESTION_LIMIT);
+        Ok(users)
+    }
+
+    async fn search_users(&self, user_id: Uuid, term: &str) -> RepoResult<Vec<UserSummary>> {
+        let term = term.to_lowercase();
+        let mut users: Vec<UserSummary> = self
+            .state()
+            .users
+            .values()
+            .map(|record| &record.user)
+            .filter(|u| u.id != user_id && u.username.to_lowercase().contains(&term))
+            .map(|u| UserSummary { id: u.id, username: u.username.clone(), email: u.email.clone() })
+            .collect();
+
+        users.sort_by(|a, b| a.username.cmp(&b.username));
+        users.truncate(SEARCH_LIMIT);
+        Ok(users)
+    }
+
+    async fn are_friends(&self, user_id: Uuid, other_id: Uuid) -> RepoResult<bool> {
+        Ok(self.state().friends.iter().any(|f| {
+            (f.user_id == user_id && f.friend_id == other_id) || (f.user_id == other_id && f.friend_id == user_id)
+        }))
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::leaderboard::{LeaderboardEntry, LeaderboardMetric, LeaderboardScope};
+
+use super::memory::State;
+use super::{InMemoryStore, RepoResult};
+
+pub struct LeaderboardFilter {
+    // Whose rank comes back alongside the top entries, and whose friends `Friends` means
+    pub caller: Uuid,
+    pub metric: LeaderboardMetric,
+    pub scope: LeaderboardScope,
+    // Only activity from here on counts, everything when None
+    pub since: Option<DateTime<Utc>>,
+    pub limit: i64,
+}
+
+// The top `limit` entries, plus the caller's entry wherever it ranks
+pub struct Ranking {
+    pub entries: Vec<LeaderboardEntry>,
+    pub caller: Option<LeaderboardEntry>,
+}
+
+#[async_trait]
+pub trait LeaderboardRepository: Send + Sync {
+    async fn rank(&self, filter: &LeaderboardFilter) -> RepoResult<Ranking>;
+}
+
+// Per-user score for each metric from activity at or after $2 (NULL for all time). Ledger
+// days are compared with the UTC date $2 falls on.
+fn scores_sql(metric: LeaderboardMetric) -> &'static str {
+    match metric {
+        // Gaps and islands: consecutive days minus their row number land on the same date
+        LeaderboardMetric::Streak => {
+            "SELECT user_id, MAX(run) AS value
+             FROM (
+                SELECT user_id, COUNT(*) AS run
+                FROM (
+                    SELECT user_id, day - (ROW_NUMBER() OVER (PARTITION BY user_id ORDER BY day))::int AS island
+                    FROM daily_activity
+                    WHERE problems_solved > 0 AND ($2::timestamptz IS NULL OR day >= DATE($2 AT TIME ZONE 'UTC'))
+                ) solve_days
+                GROUP BY user_id, island
+             ) runs
+             GROUP BY user_id"
+        }
+        LeaderboardMetric::Solved => {
+            "SELECT user_id, SUM(problems_solved)::BIGINT AS value
+             FROM daily_activity
+             WHERE problems_solved > 0 AND ($2::timestamptz IS NULL OR day >= DATE($2 AT TIME ZONE 'UTC'))
+             GROUP BY user_id"
+        }
+        LeaderboardMetric::Accepted => {
+            "SELECT user_id, COUNT(*) AS value
+             FROM problem_solutions
+             WHERE is_accepted AND ($2::timestamptz IS NULL OR created_at >= $2)
+             GROUP BY user_id"
+        }
+        // problem_feedback.created_at is a UTC timestamp without a zone
+        LeaderboardMetric::Helpful => {
+            "SELECT p.user_id, COUNT(*) AS value
+             FROM problem_feedback f
+             JOIN problems p ON p.id = f.problem_id
+             WHERE f.is_helpful AND f.user_id <> p.user_id
+               AND ($2::timestamptz IS NULL OR f.created_at >= $2 AT TIME ZONE 'UTC')
+             GROUP BY p.user_id"
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct RankedRow {
+    #[sqlx(flatten)]
+    entry: LeaderboardEntry,
+    in_top: bool,
+}
+
+#[async_trait]
+impl LeaderboardRepository for PgPool {
+    async fn rank(&self, filter: &LeaderboardFilter) -> RepoResult<Ranking> {
+        // Ranked in one pass over the scores; the position breaks ties by username so the
+        // cut-off at `limit` is stable
+        let rows = sqlx::query_as::<_, RankedRow>(&format!(
+            "WITH scores AS ({scores}),
+             ranked AS (
+                SELECT s.user_id, u.username, s.value::BIGINT AS value,
+                       RANK() OVER (ORDER BY s.value DESC) AS rank,
+                       ROW_NUMBER() OVER (ORDER BY s.value DESC, u.username) AS position
+                FROM scores s
+                JOIN users u ON u.id = s.user_id
+                WHERE s.value > 0 AND u.suspended_at IS NULL
+                  AND (NOT $3 OR s.user_id = $1
+                       OR s.user_id IN (SELECT friend_id FROM friends WHERE user_id = $1)
+                       OR s.user_id IN (SELECT user_id FROM friends WHERE friend_id = $1))
+             )
+             SELECT rank, user_id, username, value, position <= $4 AS in_top
+             FROM ranked
+             WHERE position <= $4 OR user_id = $1
+             ORDER BY position",
+            scores = scores_sql(filter.metric)
+        ))
+        .bind(filter.caller)
+        .bind(filter.since)
+        .bind(filter.scope == LeaderboardScope::Friends)
+        .bind(filter.limit)
+        .fetch_all(self)
+        .await?;
+
+        let caller = rows.iter().find(|row| row.entry.user_id == filter.caller).map(|row| row.entry.clone());
+        let entries = rows.into_iter().filter(|row| row.in_top).map(|row| row.entry).collect();
+
+        Ok(Ranking { entries, caller })
+    }
+}
+
+impl State {
+    fn leaderboard_scores(&self, metric: LeaderboardMetric, since: Option<DateTime<Utc>>) -> HashMap<Uuid, i64> {
+        let since_day = since.map(|at| at.date_naive());
+        let mut scores: HashMap<Uuid, i64> = HashMap::new();
+
+        match metric {
+            LeaderboardMetric::Streak => {
+                for user_id in self.daily_activity.keys() {
+                    let (mut longest, mut run, mut previous) = (0, 0, None::<NaiveDate>);
+                    for day in self.solve_days(*user_id, since_day).into_iter().map(|a| a.day) {
+                        run = if previous.and_then(|p| p.succ_opt()) == Some(day) { run + 1 } else { 1 };
+                        longest = longest.max(run);
+                        previous = Some(day);
+                    }
+                    scores.insert(*user_id, longest);
+                }
+            }
+            LeaderboardMetric::Solved => {
+                for user_id in self.daily_activity.keys() {
+                    let solved = self.solve_days(*user_id, since_day).iter().map(|a| a.problems_solved as i64).sum();
+                    scores.insert(*user_id, solved);
+                }
+            }
+            LeaderboardMetric::Accepted => {
+                for solution in &self.solutions {
+                    if solution.is_accepted && since.is_none_or(|since| solution.created_at >= since) {
+                        *scores.entry(solution.user_id).or_default() += 1;
+                    }
+                }
+            }
+            LeaderboardMetric::Helpful => {
+                for feedback in &self.feedback {
+                    let in_window = match (since, feedback.created_at) {
+                        (Some(since), Some(at)) => at.and_utc() >= since,
+                        (Some(_), None) => false,
+                        (None, _) => true,
+                    };
+                    if feedback.is_helpful != Some(true) || !in_window {
+                        continue;
+                    }
+                    let author = self.problems.iter().find(|p| p.id == feedback.problem_id).map(|p| p.user_id);
+                    if let Some(author) = author.filter(|author| *author != feedback.user_id) {
+                        *scores.entry(author).or_default() += 1;
+                    }
+                }
+            }
+        }
+
+        scores
+    }
+
+    fn are_friends(&self, a: Uuid, b: Uuid) -> bool {
+        self.friends.iter().any(|f| (f.user_id == a && f.friend_id == b) || (f.user_id == b && f.friend_id == a))
+    }
+}
+
+#[async_trait]
+impl LeaderboardRepository for InMemoryStore {
+    async fn rank(&self, filter: &LeaderboardFilter) -> RepoResult<Ranking> {
+        let state = self.state();
+        let mut ranked: Vec<LeaderboardEntry> = state
+            .leaderboard_scores(filter.metric, filter.since)
+            .into_iter()
+            .filter(|(user_id, value)| {
+                *value > 0
+                    && (filter.scope == LeaderboardScope::Global
+                        || *user_id == filter.caller
+                        || state.are_friends(filter.caller, *user_id))
+            })
+            .filter_map(|(user_id, value)| {
+                let user = &state.users.get(&user_id)?.user;
+                user.suspended_at
+                    .is_none()
+                    .then(|| LeaderboardEntry { rank: 0, user_id, username: user.username.clone(), value })
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.value.cmp(&a.value).then_with(|| a.username.cmp(&b.username)));
+
+        // RANK(): one more than the number of entries with a strictly higher value
+        for i in 0..ranked.len() {
+            let tied = i > 0 && ranked[i].value == ranked[i - 1].value;
+            ranked[i].rank = if tied { ranked[i - 1].rank } else { i as i64 + 1 };
+        }
+
+        let caller = ranked.iter().find(|entry| entry.user_id == filter.caller).cloned();
+        ranked.truncate(filter.limit.max(0) as usize);
+
+        Ok(Ranking { entries: ranked, caller })
+    }
+}
@@ -0,0 +1,295 @@
+// Filtering, sorting and keyset pagination shared by every problem listing. Postgres runs
+// it as one query (`build_query`); the in-memory backend applies the same rules to its
+// rows (`matches` and `page`), so both hand out identical pages and cursors.
+
+use chrono::{DateTime, Utc};
+use data_encoding::BASE64URL_NOPAD;
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::problem::{Difficulty, ProblemListItem, ProblemListParams, ProblemPage, ProblemSort};
+use crate::models::tag::normalize_tag;
+
+use super::problems::visible_to;
+
+const DEFAULT_LIMIT: i64 = 20;
+const MAX_LIMIT: i64 = 100;
+
+// A ProblemListItem per problem. Callers append the conditions after WHERE, then
+// LISTED_GROUP_BY.
+pub(super) const LISTED_SELECT: &str = "
+    SELECT p.id, p.title, p.description, p.category, p.user_id, u.username AS created_by,
+           u.avatar_url AS author_avatar_url, p.difficulty_level, COALESCE(p.tags, '{}') AS tags, p.solved,
+           p.visibility, p.created_at, p.updated_at,
+           COALESCE(AVG(f.rating), 0)::float8 AS average_rating,
+           COUNT(f.id) AS feedback_count,
+           COUNT(f.id) FILTER (WHERE f.is_helpful) AS helpful_count
+    FROM problems p
+    JOIN users u ON u.id = p.user_id
+    LEFT JOIN problem_feedback f ON f.problem_id = p.id
+    WHERE ";
+
+pub(super) const LISTED_GROUP_BY: &str = " GROUP BY p.id, u.username, u.avatar_url";
+
+// Whose problems a listing covers
+#[derive(Debug, Clone, Copy)]
+pub enum ListingScope {
+    // Problems created by this user
+    Owner(Uuid),
+    // Everyone's problems except this user's
+    Community(Uuid),
+}
+
+impl ListingScope {
+    // Who is looking. Listings only include problems this user may see.
+    pub fn viewer(self) -> Uuid {
+        match self {
+            ListingScope::Owner(user_id) | ListingScope::Community(user_id) => user_id,
+        }
+    }
+}
+
+// The sort key of the last item on a page. Items are ordered by `value` (see
+// `sort_value`), then newest first, then by id, so the key is unique.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Cursor {
+    sort: ProblemSort,
+    value: f64,
+    created_at: DateTime<Utc>,
+    id: Uuid,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        BASE64URL_NOPAD.encode(&serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    fn decode(cursor: &str) -> Option<Cursor> {
+        let bytes = BASE64URL_NOPAD.decode(cursor.as_bytes()).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProblemListing {
+    pub scope: ListingScope,
+    pub category: Option<String>,
+    pub category_id: Option<Uuid>,
+    pub difficulty: Option<Difficulty>,
+    pub tags: Vec<String>,
+    pub solved: Option<bool>,
+    pub author: Option<Uuid>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub sort: ProblemSort,
+    pub limit: i64,
+    after: Option<Cursor>,
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+impl ProblemListing {
+    pub fn from_params(scope: ListingScope, params: &ProblemListParams) -> Result<Self, AppError> {
+        let sort = params.sort.unwrap_or_default();
+
+        let after = match params.cursor.as_deref() {
+            None | Some("") => None,
+            Some(cursor) => {
+                let cursor = Cursor::decode(cursor).ok_or_else(|| AppError::BadRequest("Invalid cursor".into()))?;
+                if cursor.sort != sort {
+                    return Err(AppError::BadRequest("Cursor belongs to a different sort order".into()));
+                }
+                Some(cursor)
+            }
+        };
+
+        if params.from.zip(params.to).is_some_and(|(from, to)| from > to) {
+            return Err(AppError::validation(
+                "Invalid filter",
+                serde_json::json!({ "from": "must not be after to" }),
+            ));
+        }
+
+        let tags = params
+            .tags
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(normalize_tag)
+            .collect();
+
+        Ok(ProblemListing {
+            scope,
+            category: non_empty(&params.category),
+            category_id: params.category_id,
+            difficulty: params.difficulty,
+            tags,
+            solved: params.solved,
+            author: params.author,
+            from: params.from,
+            to: params.to,
+            sort,
+            limit: params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+            after,
+        })
+    }
+
+    // Primary sort key. Rating and helpfulness sort high to low, unsolved-first low to high.
+    fn sort_value(&self, item: &ProblemListItem) -> f64 {
+        match self.sort {
+            ProblemSort::Newest => 0.0,
+            ProblemSort::Rating => item.average_rating,
+            ProblemSort::MostHelpful => item.helpful_count as f64,
+            ProblemSort::UnsolvedFirst => f64::from(u8::from(item.solved)),
+        }
+    }
+
+    fn ascending(&self) -> bool {
+        self.sort == ProblemSort::UnsolvedFirst
+    }
+
+    fn sort_value_sql(&self) -> &'static str {
+        match self.sort {
+            ProblemSort::Newest => "0::float8",
+            ProblemSort::Rating => "average_rating",
+            ProblemSort::MostHelpful => "helpful_count::float8",
+            ProblemSort::UnsolvedFirst => "(CASE WHEN solved THEN 1 ELSE 0 END)::float8",
+        }
+    }
+
+    // One row more than the page size is fetched to tell whether there is a next page
+    pub fn build_query(&self) -> QueryBuilder<'_, Postgres> {
+        let mut query = QueryBuilder::new("WITH viewer AS (SELECT ");
+        query.push_bind(self.scope.viewer()).push("::uuid AS id), listed AS (");
+        query.push(LISTED_SELECT);
+        query.push(visible_to("(SELECT id FROM viewer)")).push(" AND ");
+
+        match self.scope {
+            ListingScope::Owner(user_id) => query.push("p.user_id = ").push_bind(user_id),
+            ListingScope::Community(user_id) => query.push("p.user_id != ").push_bind(user_id),
+        };
+        if let Some(category) = &self.category {
+            query.push(" AND LOWER(p.category) = LOWER(").push_bind(category).push(")");
+        }
+        if let Some(category_id) = self.category_id {
+            query
+                .push(" AND EXISTS (SELECT 1 FROM problem_category_mappings m WHERE m.problem_id = p.id AND m.category_id = ")
+                .push_bind(category_id)
+                .push(")");
+        }
+        if let Some(difficulty) = self.difficulty {
+            query.push(" AND p.difficulty_level = ").push_bind(difficulty);
+        }
+        // Filter tags may be aliases, problems.tags only has canonical names
+        if !self.tags.is_empty() {
+            query
+                .push(
+                    " AND p.tags @> ARRAY(SELECT COALESCE(
+                        (SELECT t.name::text FROM tag_aliases a JOIN tags t ON t.id = a.tag_id WHERE a.alias = n), n)
+                     FROM UNNEST(",
+                )
+                .push_bind(&self.tags)
+                .push("::text[]) AS n)");
+        }
+        if let Some(solved) = self.solved {
+            query.push(" AND p.solved = ").push_bind(solved);
+        }
+        if let Some(author) = self.author {
+            query.push(" AND p.user_id = ").push_bind(author);
+        }
+        if let Some(from) = self.from {
+            query.push(" AND p.created_at >= ").push_bind(from);
+        }
+        if let Some(to) = self.to {
+            query.push(" AND p.created_at <= ").push_bind(to);
+        }
+
+        query.push(LISTED_GROUP_BY).push("), ranked AS (SELECT *, ");
+        query.push(self.sort_value_sql()).push(" AS sort_value FROM listed) SELECT * FROM ranked");
+
+        if let Some(after) = &self.after {
+            query
+                .push(" WHERE sort_value ")
+                .push(if self.ascending() { "> " } else { "< " })
+                .push_bind(after.value)
+                .push(" OR (sort_value = ")
+                .push_bind(after.value)
+                .push(" AND (created_at, id) < (")
+                .push_bind(after.created_at)
+                .push(", ")
+                .push_bind(after.id)
+                .push("))");
+        }
+
+        query
+            .push(" ORDER BY sort_value ")
+            .push(if self.ascending() { "ASC" } else { "DESC" })
+            .push(", created_at DESC, id DESC LIMIT ")
+            .push_bind(self.limit + 1);
+
+        query
+    }
+
+    // The filters of `build_query`, for backends that filter rows themselves. Visibility
+    // and category_id are left to the caller since they depend on other tables, and `tags`
+    // must already be resolved from aliases to tag names.
+    pub fn matches(&self, item: &ProblemListItem) -> bool {
+        let in_scope = match self.scope {
+            ListingScope::Owner(user_id) => item.user_id == user_id,
+            ListingScope::Community(user_id) => item.user_id != user_id,
+        };
+        let same = |wanted: &Option<String>, actual: Option<&str>| {
+            wanted.as_ref().is_none_or(|w| actual.is_some_and(|a| a.eq_ignore_ascii_case(w)))
+        };
+
+        in_scope
+            && same(&self.category, Some(&item.category))
+            && self.difficulty.is_none_or(|difficulty| item.difficulty_level == Some(difficulty))
+            && self.tags.iter().all(|t| item.tags.contains(t))
+            && self.solved.is_none_or(|solved| item.solved == solved)
+            && self.author.is_none_or(|author| item.user_id == author)
+            && self.from.is_none_or(|from| item.created_at >= from)
+            && self.to.is_none_or(|to| item.created_at <= to)
+    }
+
+    fn compare(&self, a: &ProblemListItem, b: &ProblemListItem) -> std::cmp::Ordering {
+        let by_value = self.sort_value(a).total_cmp(&self.sort_value(b));
+        let by_value = if self.ascending() { by_value } else { by_value.reverse() };
+        by_value
+            .then_with(|| b.created_at.cmp(&a.created_at))
+            .then_with(|| b.id.cmp(&a.id))
+    }
+
+    // Sort and paginate rows that already passed `matches`
+    pub fn page(&self, mut items: Vec<ProblemListItem>) -> ProblemPage {
+        items.sort_by(|a, b| self.compare(a, b));
+
+        if let Some(after) = &self.after {
+            let ascending = self.ascending();
+            items.retain(|item| {
+                let value = self.sort_value(item);
+                let past_value = if ascending { value > after.value } else { value < after.value };
+                past_value || (value == after.value && (item.created_at, item.id) < (after.created_at, after.id))
+            });
+        }
+
+        items.truncate(self.limit as usize + 1);
+        self.finish(items)
+    }
+
+    // Turn up to limit + 1 sorted rows into a page
+    pub fn finish(&self, mut items: Vec<ProblemListItem>) -> ProblemPage {
+        let has_more = items.len() as i64 > self.limit;
+        items.truncate(self.limit as usize);
+
+        let next_cursor = items.last().filter(|_| has_more).map(|last| {
+            Cursor { sort: self.sort, value: self.sort_value(last), created_at: last.created_at, id: last.id }.encode()
+        });
+
+        ProblemPage { problems: items, next_cursor }
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use uuid::Uuid;
+
+use crate::models::access_token::AccessToken;
+use crate::models::activity::DailyActivity;
+use crate::models::character::Character;
+use crate::models::chat::Chat;
+use crate::models::friend::{Friend, FriendRequest};
+use crate::models::message::Message;
+use crate::models::mfa::UserMfa;
+use crate::models::problem::{Problem, ProblemFeedback, ProblemRevision, Visibility};
+use crate::models::resource::{ProblemCategory, ProblemResource};
+use crate::models::session::Session;
+use crate::models::solution::ProblemSolution;
+use crate::models::streak::{Streak, StreakFreezeEvent};
+use crate::models::tag::{Tag, TagAlias};
+
+use super::sessions::RefreshTokenRecord;
+use super::users::{UserRecord, UserTokenRecord};
+
+// Every table the in-memory backend keeps, behind a single lock. Handlers never hold it
+// across an await, so one lock is simpler than per-table ones and keeps joins consistent.
+#[derive(Default)]
+pub(super) struct State {
+    pub users: HashMap<Uuid, UserRecord>,
+    pub user_tokens: Vec<UserTokenRecord>,
+    pub sessions: HashMap<Uuid, Session>,
+    // Keyed by token hash
+    pub refresh_tokens: HashMap<String, RefreshTokenRecord>,
+    pub mfa: HashMap<Uuid, UserMfa>,
+    // (user_id, code hash, used)
+    pub recovery_codes: Vec<(Uuid, String, bool)>,
+    // Keyed by token hash
+    pub access_tokens: HashMap<String, AccessToken>,
+    pub problems: Vec<Problem>,
+    pub feedback: Vec<ProblemFeedback>,
+    pub revisions: Vec<ProblemRevision>,
+    pub solutions: Vec<ProblemSolution>,
+    // (solution_id, user_id) -> 1 or -1
+    pub solution_votes: HashMap<(Uuid, Uuid), i16>,
+    pub resources: Vec<ProblemResource>,
+    // Filled on first use, see resources.rs
+    pub categories: Vec<ProblemCategory>,
+    // (problem_id, category_id) set explicitly, on top of the one matching the problem's category
+    pub category_mappings: Vec<(Uuid, Uuid)>,
+    pub tags: Vec<Tag>,
+    pub tag_aliases: Vec<TagAlias>,
+    pub streaks: HashMap<Uuid, Streak>,
+    pub streak_freezes: Vec<StreakFreezeEvent>,
+    pub daily_activity: HashMap<Uuid, BTreeMap<NaiveDate, DailyActivity>>,
+    // (user_id, problem_id) of every counted solve
+    pub problem_solves: HashSet<(Uuid, Uuid)>,
+    pub friend_requests: Vec<FriendRequest>,
+    pub friends: Vec<Friend>,
+    pub messages: Vec<Message>,
+    pub chats: Vec<Chat>,
+    pub characters: HashMap<Uuid, Character>,
+}
+
+#[derive(Default)]
+pub struct InMemoryStore {
+    state: Mutex<State>,
+}
+
+impl InMemoryStore {
+    pub(super) fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl State {
+    pub fn username(&self, user_id: Uuid) -> Option<String> {
+        self.users.get(&user_id).map(|record| record.user.username.clone())
+    }
+
+    // The zone the user's streak days are counted in
+    pub fn timezone(&self, user_id: Uuid) -> Tz {
+        self.users.get(&user_id).map(|record| record.user.tz()).unwrap_or(Tz::UTC)
+    }
+
+    // The in-memory counterpart of problems::visible_to
+    pub fn can_view(&self, viewer: Uuid, problem: &Problem) -> bool {
+        problem.user_id == viewer
+            || match problem.visibility {
+                Visibility::Public => true,
+                Visibility::Friends => self.friends.iter().any(|f| {
+                    (f.user_id == problem.user_id && f.friend_id == viewer)
+                        || (f.user_id == viewer && f.friend_id == problem.user_id)
+                }),
+                Visibility::Private => false,
+            }
+    }
+
+    // What ON DELETE CASCADE removes along with a problem in Postgres
+    pub fn remove_problem_children(&mut self, problem_id: Uuid) {
+        self.feedback.retain(|f| f.problem_id != problem_id);
+        self.resources.retain(|r| r.problem_id != problem_id);
+        self.revisions.retain(|r| r.problem_id != problem_id);
+        self.category_mappings.retain(|(mapped, _)| *mapped != problem_id);
+        self.problem_solves.retain(|(_, solved)| *solved != problem_id);
+
+        let solution_ids: Vec<Uuid> =
+            self.solutions.iter().filter(|s| s.problem_id == problem_id).map(|s| s.id).collect();
+        self.solutions.retain(|s| s.problem_id != problem_id);
+        self.solution_votes.retain(|(solution_id, _), _| !solution_ids.contains(solution_id));
+    }
+}
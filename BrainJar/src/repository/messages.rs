@@ -0,0 +1,305 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::chat::{Chat, ChatConversation, CreateMessage};
+use crate::models::message::{Message, MessageWithSender};
+
+use super::{InMemoryStore, RepoResult};
+
+// Direct messages between friends (`messages`) and the older open chat (`chats`)
+#[async_trait]
+pub trait MessageRepository: Send + Sync {
+    async fn send(&self, sender_id: Uuid, receiver_id: Uuid, message: &str) -> RepoResult<Message>;
+    // Messages between two users, newest first
+    async fn conversation(
+        &self,
+        user_id: Uuid,
+        other_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<MessageWithSender>>;
+    async fn count_conversation(&self, user_id: Uuid, other_id: Uuid) -> RepoResult<i64>;
+    // Mark messages addressed to `user_id` as read, returning how many were updated
+    async fn mark_read(&self, user_id: Uuid, message_ids: &[Uuid]) -> RepoResult<u64>;
+    // Delete regardless of sender, for moderation
+    async fn delete(&self, message_id: Uuid) -> RepoResult<bool>;
+
+    async fn chat_messages(&self, user_id: Uuid, other_id: Uuid) -> RepoResult<Vec<Chat>>;
+    async fn create_chat(&self, sender_id: Uuid, message: CreateMessage) -> RepoResult<Chat>;
+    // Everyone `user_id` has chatted with, most recent conversation first
+    async fn chat_conversations(&self, user_id: Uuid) -> RepoResult<Vec<ChatConversation>>;
+}
+
+#[async_trait]
+impl MessageRepository for PgPool {
+    async fn send(&self, sender_id: Uuid, receiver_id: Uuid, message: &str) -> RepoResult<Message> {
+        Ok(sqlx::query_as::<_, Message>(
+            "INSERT INTO messages (sender_id, receiver_id, message) VALUES ($1, $2, $3) RETURNING *"
+        )
+        .bind(sender_id)
+        .bind(receiver_id)
+        .bind(message)
+        .fetch_one(self)
+        .await?)
+    }
+
+    async fn conversation(
+        &self,
+        user_id: Uuid,
+        other_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<MessageWithSender>> {
+        Ok(sqlx::query_as::<_, MessageWithSender>(
+            "SELECT m.id, m.sender_id, m.receiver_id, m.message, m.is_read, m.created_at,
+                    u.username AS sender_username, u.avatar_url AS sender_avatar_url
+             FROM messages m
+             JOIN users u ON m.sender_id = u.id
+             WHERE (m.sender_id = $1 AND m.receiver_id = $2)
+                OR (m.sender_id = $2 AND m.receiver_id = $1)
+             ORDER BY m.created_at DESC
+             LIMIT $3 OFFSET $4"
+        )
+        .bind(user_id)
+        .bind(other_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn count_conversation(&self, user_id: Uuid, other_id: Uuid) -> RepoResult<i64> {
+        Ok(sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM messages
+             WHERE (sender_id = $1 AND receiver_id = $2)
+                OR (sender_id = $2 AND receiver_id = $1)"
+        )
+        .bind(user_id)
+        .bind(other_id)
+        .fetch_one(self)
+        .await?)
+    }
+
+    async fn mark_read(&self, user_id: Uuid, message_ids: &[Uuid]) -> RepoResult<u64> {
+        let result = sqlx::query(
+            "UPDATE messages SET is_read = true WHERE id = ANY($1) AND receiver_id = $2"
+        )
+        .bind(message_ids)
+        .bind(user_id)
+        .execute(self)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn delete(&self, message_id: Uuid) -> RepoResult<bool> {
+        let result = sqlx::query("DELETE FROM messages WHERE id = $1")
+            .bind(message_id)
+            .execute(self)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn chat_messages(&self, user_id: Uuid, other_id: Uuid) -> RepoResult<Vec<Chat>> {
+        Ok(Chat::get_messages(self, user_id, other_id).await?)
+    }
+
+    async fn create_chat(&self, sender_id: Uuid, message: CreateMessage) -> RepoResult<Chat> {
+        Ok(Chat::create_message(self, sender_id, message).await?)
+    }
+
+    async fn chat_conversations(&self, user_id: Uuid) -> RepoResult<Vec<ChatConversation>> {
+        Ok(sqlx::query_as::<_, ChatConversation>(
+            r#"
+            WITH conversation_partners AS (
+                SELECT DISTINCT
+                    CASE
+                        WHEN sender_id = $1 THEN receiver_id
+                        ELSE sender_id
+                    END as partner_id
+                FROM chats
+                WHERE sender_id = $1 OR receiver_id = $1
+            ),
+            latest_messages AS (
+                SELECT
+                    cp.partner_id,
+                    c.message,
+                    c.created_at,
+                    ROW_NUMBER() OVER (PARTITION BY cp.partner_id ORDER BY c.created_at DESC) as rn
+                FROM conversation_partners cp
+                LEFT JOIN chats c ON
+                    (c.sender_id = $1 AND c.receiver_id = cp.partner_id) OR
+                    (c.sender_id = cp.partner_id AND c.receiver_id = $1)
+            )
+            SELECT
+                cp.partner_id as user_id,
+                u.username,
+                lm.message as last_message,
+                lm.created_at as last_message_time,
+                COUNT(unread.id) as unread_count
+            FROM conversation_partners cp
+            JOIN users u ON u.id = cp.partner_id
+            LEFT JOIN latest_messages lm ON lm.partner_id = cp.partner_id AND lm.rn = 1
+            LEFT JOIN chats unread ON unread.sender_id = cp.partner_id AND unread.receiver_id = $1
+            GROUP BY cp.partner_id, u.username, lm.message, lm.created_at
+            ORDER BY lm.created_at DESC NULLS LAST
+            "#
+        )
+        .bind(user_id)
+        .fetch_all(self)
+        .await?)
+    }
+}
+
+fn between(sender_id: Uuid, receiver_id: Uuid, user_id: Uuid, other_id: Uuid) -> bool {
+    (sender_id == user_id && receiver_id == other_id) || (sender_id == other_id && receiver_id == user_id)
+}
+
+#[async_trait]
+impl MessageRepository for InMemoryStore {
+    async fn send(&self, sender_id: Uuid, receiver_id: Uuid, message: &str) -> RepoResult<Message> {
+        let mut state = self.state();
+        if !state.users.contains_key(&receiver_id) {
+            return Err(AppError::NotFound("User not found".into()));
+        }
+
+        let message = Message {
+            id: Uuid::new_v4(),
+            sender_id,
+            receiver_id,
+            message: message.to_string(),
+            is_read: false,
+            created_at: Utc::now(),
+        };
+
+        state.messages.push(message.clone());
+        Ok(message)
+    }
+
+    async fn conversation(
+        &self,
+        user_id: Uuid,
+        other_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<MessageWithSender>> {
+        let state = self.state();
+        let mut messages: Vec<&Message> = state
+            .messages
+            .iter()
+            .filter(|m| between(m.sender_id, m.receiver_id, user_id, other_id))
+            .collect();
+        messages.sort_by_key(|x| Reverse(x.created_at));
+
+        Ok(messages
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .filter_map(|m| {
+                Some(MessageWithSender {
+                    id: m.id,
+                    sender_id: m.sender_id,
+                    receiver_id: m.receiver_id,
+                    message: m.message.clone(),
+                    is_read: m.is_read,
+                    created_at: m.created_at,
+                    sender_username: state.username(m.sender_id)?,
+                    sender_avatar_url: None,
+                })
+            })
+            .collect())
+    }
+
+    async fn count_conversation(&self, user_id: Uuid, other_id: Uuid) -> RepoResult<i64> {
+        Ok(self
+            .state()
+            .messages
+            .iter()
+            .filter(|m| between(m.sender_id, m.receiver_id, user_id, other_id))
+            .count() as i64)
+    }
+
+    async fn mark_read(&self, user_id: Uuid, message_ids: &[Uuid]) -> RepoResult<u64> {
+        let mut updated = 0;
+        for message in self.state().messages.iter_mut() {
+            if message.receiver_id == user_id && message_ids.contains(&message.id) {
+                message.is_read = true;
+                updated += 1;
+            }
+        }
+        Ok(updated)
+    }
+
+    async fn delete(&self, message_id: Uuid) -> RepoResult<bool> {
+        let mut state = self.state();
+        let before = state.messages.len();
+        state.messages.retain(|m| m.id != message_id);
+        Ok(state.messages.len() < before)
+    }
+
+    async fn chat_messages(&self, user_id: Uuid, other_id: Uuid) -> RepoResult<Vec<Chat>> {
+        let mut chats: Vec<Chat> = self
+            .state()
+            .chats
+            .iter()
+            .filter(|c| between(c.sender_id, c.receiver_id, user_id, other_id))
+            .cloned()
+            .collect();
+
+        chats.sort_by_key(|x| Reverse(x.created_at));
+        Ok(chats)
+    }
+
+    async fn create_chat(&self, sender_id: Uuid, message: CreateMessage) -> RepoResult<Chat> {
+        let chat = Chat {
+            id: Uuid::new_v4(),
+            sender_id,
+            receiver_id: message.receiver_id,
+            message: message.message,
+            created_at: Utc::now(),
+        };
+
+        self.state().chats.push(chat.clone());
+        Ok(chat)
+    }
+
+    async fn chat_conversations(&self, user_id: Uuid) -> RepoResult<Vec<ChatConversation>> {
+        let state = self.state();
+        let mut conversations: HashMap<Uuid, ChatConversation> = HashMap::new();
+
+        for chat in state.chats.iter().filter(|c| c.sender_id == user_id || c.receiver_id == user_id) {
+            let partner_id = if chat.sender_id == user_id { chat.receiver_id } else { chat.sender_id };
+            let Some(username) = state.username(partner_id) else {
+                continue;
+            };
+
+            let conversation = conversations.entry(partner_id).or_insert(ChatConversation {
+                user_id: partner_id,
+                username,
+                last_message: None,
+                last_message_time: None,
+                unread_count: 0,
+            });
+
+            if conversation.last_message_time.is_none_or(|time| chat.created_at > time) {
+                conversation.last_message = Some(chat.message.clone());
+                conversation.last_message_time = Some(chat.created_at);
+            }
+
+            if chat.sender_id == partner_id {
+                conversation.unread_count += 1;
+            }
+        }
+
+        let mut conversations: Vec<ChatConversation> = conversations.into_values().collect();
+        conversations.sort_by_key(|x| Reverse(x.last_message_time));
+        Ok(conversations)
+    }
+}
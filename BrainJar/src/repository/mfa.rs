@@ -0,0 +1,161 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::crypto::hash_token;
+use crate::models::mfa::{generate_recovery_codes, normalize_recovery_code, UserMfa};
+
+use super::{InMemoryStore, RepoResult};
+
+// TOTP secrets and recovery codes
+#[async_trait]
+pub trait MfaRepository: Send + Sync {
+    async fn get(&self, user_id: Uuid) -> RepoResult<Option<UserMfa>>;
+    async fn is_enabled(&self, user_id: Uuid) -> RepoResult<bool>;
+    // Store a fresh secret for a pending enrollment, replacing any unconfirmed one.
+    // Returns None if two-factor is already enabled.
+    async fn start_enrollment(&self, user_id: Uuid, secret: &str) -> RepoResult<Option<UserMfa>>;
+    // Record the step of an accepted code. Returns false if a code for this or a later
+    // step was already used.
+    async fn record_step(&self, user_id: Uuid, step: i64) -> RepoResult<bool>;
+    async fn enable(&self, user_id: Uuid) -> RepoResult<()>;
+    async fn disable(&self, user_id: Uuid) -> RepoResult<()>;
+    // Replace all recovery codes and return the new plaintext codes
+    async fn regenerate_recovery_codes(&self, user_id: Uuid) -> RepoResult<Vec<String>>;
+    async fn consume_recovery_code(&self, user_id: Uuid, code: &str) -> RepoResult<bool>;
+    async fn remaining_recovery_codes(&self, user_id: Uuid) -> RepoResult<i64>;
+}
+
+#[async_trait]
+impl MfaRepository for PgPool {
+    async fn get(&self, user_id: Uuid) -> RepoResult<Option<UserMfa>> {
+        Ok(UserMfa::get(self, user_id).await?)
+    }
+
+    async fn is_enabled(&self, user_id: Uuid) -> RepoResult<bool> {
+        Ok(UserMfa::is_enabled(self, user_id).await?)
+    }
+
+    async fn start_enrollment(&self, user_id: Uuid, secret: &str) -> RepoResult<Option<UserMfa>> {
+        Ok(UserMfa::start_enrollment(self, user_id, secret).await?)
+    }
+
+    async fn record_step(&self, user_id: Uuid, step: i64) -> RepoResult<bool> {
+        Ok(UserMfa::record_step(self, user_id, step).await?)
+    }
+
+    async fn enable(&self, user_id: Uuid) -> RepoResult<()> {
+        Ok(UserMfa::enable(self, user_id).await?)
+    }
+
+    async fn disable(&self, user_id: Uuid) -> RepoResult<()> {
+        Ok(UserMfa::disable(self, user_id).await?)
+    }
+
+    async fn regenerate_recovery_codes(&self, user_id: Uuid) -> RepoResult<Vec<String>> {
+        Ok(UserMfa::regenerate_recovery_codes(self, user_id).await?)
+    }
+
+    async fn consume_recovery_code(&self, user_id: Uuid, code: &str) -> RepoResult<bool> {
+        Ok(UserMfa::consume_recovery_code(self, user_id, code).await?)
+    }
+
+    async fn remaining_recovery_codes(&self, user_id: Uuid) -> RepoResult<i64> {
+        Ok(UserMfa::remaining_recovery_codes(self, user_id).await?)
+    }
+}
+
+#[async_trait]
+impl MfaRepository for InMemoryStore {
+    async fn get(&self, user_id: Uuid) -> RepoResult<Option<UserMfa>> {
+        Ok(self.state().mfa.get(&user_id).cloned())
+    }
+
+    async fn is_enabled(&self, user_id: Uuid) -> RepoResult<bool> {
+        Ok(self.state().mfa.get(&user_id).is_some_and(|mfa| mfa.enabled_at.is_some()))
+    }
+
+    async fn start_enrollment(&self, user_id: Uuid, secret: &str) -> RepoResult<Option<UserMfa>> {
+        let mut state = self.state();
+        if state.mfa.get(&user_id).is_some_and(|mfa| mfa.enabled_at.is_some()) {
+            return Ok(None);
+        }
+
+        let mfa = UserMfa {
+            user_id,
+            totp_secret: secret.to_string(),
+            enabled_at: None,
+            last_used_step: None,
+        };
+        state.mfa.insert(user_id, mfa.clone());
+        Ok(Some(mfa))
+    }
+
+    async fn record_step(&self, user_id: Uuid, step: i64) -> RepoResult<bool> {
+        let mut state = self.state();
+        let Some(mfa) = state.mfa.get_mut(&user_id) else {
+            return Ok(false);
+        };
+
+        if mfa.last_used_step.is_some_and(|last| last >= step) {
+            return Ok(false);
+        }
+
+        mfa.last_used_step = Some(step);
+        Ok(true)
+    }
+
+    async fn enable(&self, user_id: Uuid) -> RepoResult<()> {
+        if let Some(mfa) = self.state().mfa.get_mut(&user_id) {
+            mfa.enabled_at = Some(Utc::now());
+        }
+        Ok(())
+    }
+
+    async fn disable(&self, user_id: Uuid) -> RepoResult<()> {
+        let mut state = self.state();
+        state.recovery_codes.retain(|(owner, _, _)| *owner != user_id);
+        state.mfa.remove(&user_id);
+        Ok(())
+    }
+
+    async fn regenerate_recovery_codes(&self, user_id: Uuid) -> RepoResult<Vec<String>> {
+        let codes = generate_recovery_codes();
+
+        let mut state = self.state();
+        state.recovery_codes.retain(|(owner, _, _)| *owner != user_id);
+        state.recovery_codes.extend(
+            codes.iter().map(|code| (user_id, hash_token(&normalize_recovery_code(code)), false)),
+        );
+
+        Ok(codes)
+    }
+
+    async fn consume_recovery_code(&self, user_id: Uuid, code: &str) -> RepoResult<bool> {
+        let code_hash = hash_token(&normalize_recovery_code(code));
+        let mut state = self.state();
+
+        let unused = state
+            .recovery_codes
+            .iter_mut()
+            .find(|(owner, hash, used)| *owner == user_id && *hash == code_hash && !*used);
+
+        Ok(match unused {
+            Some((_, _, used)) => {
+                *used = true;
+                true
+            }
+            None => false,
+        })
+    }
+
+    async fn remaining_recovery_codes(&self, user_id: Uuid) -> RepoResult<i64> {
+        Ok(self
+            .state()
+            .recovery_codes
+            .iter()
+            .filter(|(owner, _, used)| *owner == user_id && !*used)
+            .count() as i64)
+    }
+}
@@ -0,0 +1,160 @@
+// Storage behind the handlers. Each domain has a trait implemented for `PgPool` and for
+// `InMemoryStore`, and `database.backend` picks one at startup. Handlers take the trait
+// object they need, e.g. `problems: web::Data<dyn ProblemRepository>`.
+//
+// The in-memory backend keeps everything in process and loses it on restart. It exists
+// for demos and for exercising handlers without Postgres, not for production.
+
+use std::sync::Arc;
+
+use actix_web::web;
+use sqlx::PgPool;
+
+use crate::error::AppError;
+
+mod memory;
+
+pub mod access_tokens;
+pub mod activity;
+pub mod characters;
+pub mod friends;
+pub mod leaderboards;
+pub mod listing;
+pub mod messages;
+pub mod mfa;
+pub mod problems;
+pub mod resources;
+pub mod search;
+pub mod sessions;
+pub mod solutions;
+pub mod streaks;
+pub mod tags;
+pub mod users;
+
+pub use access_tokens::AccessTokenRepository;
+pub use activity::ActivityRepository;
+pub use characters::CharacterRepository;
+pub use friends::FriendRepository;
+pub use leaderboards::LeaderboardRepository;
+pub use memory::InMemoryStore;
+pub use messages::MessageRepository;
+pub use mfa::MfaRepository;
+pub use problems::ProblemRepository;
+pub use resources::ResourceRepository;
+pub use search::SearchRepository;
+pub use sessions::SessionRepository;
+pub use solutions::SolutionRepository;
+pub use streaks::StreakRepository;
+pub use tags::TagRepository;
+pub use users::UserRepository;
+
+pub type RepoResult<T> = Result<T, AppError>;
+
+// Implemented by anything that provides every repository, i.e. each backend
+pub trait Backend:
+    UserRepository
+    + SessionRepository
+    + MfaRepository
+    + AccessTokenRepository
+    + ProblemRepository
+    + ResourceRepository
+    + SolutionRepository
+    + SearchRepository
+    + StreakRepository
+    + ActivityRepository
+    + LeaderboardRepository
+    + TagRepository
+    + FriendRepository
+    + MessageRepository
+    + CharacterRepository
+    + 'static
+{
+}
+
+impl<T> Backend for T where
+    T: UserRepository
+        + SessionRepository
+        + MfaRepository
+        + AccessTokenRepository
+        + ProblemRepository
+        + ResourceRepository
+        + SolutionRepository
+        + SearchRepository
+        + StreakRepository
+        + ActivityRepository
+        + LeaderboardRepository
+        + TagRepository
+        + FriendRepository
+        + MessageRepository
+        + CharacterRepository
+        + 'static
+{
+}
+
+#[derive(Clone)]
+pub struct Repositories {
+    pub users: Arc<dyn UserRepository>,
+    pub sessions: Arc<dyn SessionRepository>,
+    pub mfa: Arc<dyn MfaRepository>,
+    pub access_tokens: Arc<dyn AccessTokenRepository>,
+    pub problems: Arc<dyn ProblemRepository>,
+    pub resources: Arc<dyn ResourceRepository>,
+    pub solutions: Arc<dyn SolutionRepository>,
+    pub search: Arc<dyn SearchRepository>,
+    pub streaks: Arc<dyn StreakRepository>,
+    pub activity: Arc<dyn ActivityRepository>,
+    pub leaderboards: Arc<dyn LeaderboardRepository>,
+    pub tags: Arc<dyn TagRepository>,
+    pub friends: Arc<dyn FriendRepository>,
+    pub messages: Arc<dyn MessageRepository>,
+    pub characters: Arc<dyn CharacterRepository>,
+}
+
+impl Repositories {
+    pub fn postgres(pool: PgPool) -> Self {
+        Self::from_backend(Arc::new(pool))
+    }
+
+    pub fn in_memory() -> Self {
+        Self::from_backend(Arc::new(InMemoryStore::default()))
+    }
+
+    fn from_backend<B: Backend>(backend: Arc<B>) -> Self {
+        Repositories {
+            users: backend.clone(),
+            sessions: backend.clone(),
+            mfa: backend.clone(),
+            access_tokens: backend.clone(),
+            problems: backend.clone(),
+            resources: backend.clone(),
+            solutions: backend.clone(),
+            search: backend.clone(),
+            streaks: backend.clone(),
+            activity: backend.clone(),
+            leaderboards: backend.clone(),
+            tags: backend.clone(),
+            friends: backend.clone(),
+            messages: backend.clone(),
+            characters: backend,
+        }
+    }
+
+    // Register every repository as app data so handlers can extract `web::Data<dyn ...>`
+    pub fn configure(&self, cfg: &mut web::ServiceConfig) {
+        cfg.app_data(web::Data::from(self.users.clone()))
+            .app_data(web::Data::from(self.sessions.clone()))
+            .app_data(web::Data::from(self.mfa.clone()))
+            .app_data(web::Data::from(self.access_tokens.clone()))
+            .app_data(web::Data::from(self.problems.clone()))
+            .app_data(web::Data::from(self.resources.clone()))
+            .app_data(web::Data::from(self.solutions.clone()))
+            .app_data(web::Data::from(self.search.clone()))
+            .app_data(web::Data::from(self.streaks.clone()))
+            .app_data(web::Data::from(self.activity.clone()))
+            .app_data(web::Data::from(self.leaderboards.clone()))
+            .app_data(web::Data::from(self.tags.clone()))
+            .app_data(web::Data::from(self.friends.clone()))
+            .app_data(web::Data::from(self.messages.clone()))
+            .app_data(web::Data::from(self.characters.clone()));
+    }
+}
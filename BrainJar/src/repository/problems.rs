@@ -0,0 +1,848 @@
+use std::cmp::Reverse;
+
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::problem::{
+    CategoryCount, CreateProblem, Difficulty, FeedbackWithAuthor, Problem, ProblemFeedback, ProblemFeedbackInput,
+    ProblemListItem, ProblemPage, ProblemRevision, ProblemStats, Visibility,
+};
+
+use super::listing::{ProblemListing, LISTED_GROUP_BY, LISTED_SELECT};
+use super::memory::State;
+use super::tags::link_tags;
+use super::{InMemoryStore, RepoResult};
+
+// Problems and the feedback left on them
+#[async_trait]
+pub trait ProblemRepository: Send + Sync {
+    async fn create(&self, user_id: Uuid, problem: &CreateProblem) -> RepoResult<Problem>;
+    // Create all of them with their tags (normalized), or none
+    async fn import(&self, user_id: Uuid, problems: &[CreateProblem]) -> RepoResult<Vec<Problem>>;
+    // None if the problem doesn't exist or `viewer` may not see it
+    async fn find_visible(&self, id: Uuid, viewer: Uuid) -> RepoResult<Option<Problem>>;
+    async fn find_for_owner(&self, id: Uuid, user_id: Uuid) -> RepoResult<Option<Problem>>;
+    // Every problem of the user, oldest first
+    async fn owned_by(&self, user_id: Uuid) -> RepoResult<Vec<Problem>>;
+    // Other users' problems the user posted a solution on and can still see, oldest first
+    async fn answered_by(&self, user_id: Uuid) -> RepoResult<Vec<Problem>>;
+    // Edit the owner's problem, recording a revision when anything changed. `restored_from`
+    // notes the revision whose content is being restored.
+    async fn update(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        problem: &CreateProblem,
+        restored_from: Option<i32>,
+    ) -> RepoResult<Option<Problem>>;
+    // Newest first
+    async fn revisions(&self, problem_id: Uuid) -> RepoResult<Vec<ProblemRevision>>;
+    async fn find_revision(&self, problem_id: Uuid, revision: i32) -> RepoResult<Option<ProblemRevision>>;
+    async fn delete(&self, id: Uuid, user_id: Uuid) -> RepoResult<bool>;
+    // Delete regardless of owner, for moderation
+    async fn delete_any(&self, id: Uuid) -> RepoResult<bool>;
+    async fn set_solved(&self, id: Uuid, user_id: Uuid, solved: bool) -> RepoResult<Option<Problem>>;
+    async fn set_visibility(&self, id: Uuid, user_id: Uuid, visibility: Visibility) -> RepoResult<Option<Problem>>;
+    // Every category in use among the problems `viewer` may see, with their count, most used first
+    async fn categories(&self, viewer: Uuid) -> RepoResult<Vec<CategoryCount>>;
+    async fn stats(&self, user_id: Uuid) -> RepoResult<ProblemStats>;
+    // One page of a filtered, sorted listing (see listing.rs)
+    async fn list(&self, listing: &ProblemListing) -> RepoResult<ProblemPage>;
+    // A single problem as it appears in listings, if `viewer` may see it
+    async fn find_listed(&self, id: Uuid, viewer: Uuid) -> RepoResult<Option<ProblemListItem>>;
+    // Create or replace the caller's feedback on a problem. The flag is true if it was created.
+    async fn upsert_feedback(
+        &self,
+        problem_id: Uuid,
+        user_id: Uuid,
+        input: &ProblemFeedbackInput,
+    ) -> RepoResult<(ProblemFeedback, bool)>;
+    async fn list_feedback(&self, problem_id: Uuid) -> RepoResult<Vec<FeedbackWithAuthor>>;
+    // Delete regardless of author, for moderation
+    async fn delete_feedback(&self, feedback_id: Uuid) -> RepoResult<bool>;
+}
+
+// SQL condition on `p` (problems) true when `viewer`, an SQL expression for a user id, may
+// see the problem. State::can_view is the in-memory counterpart.
+pub(super) fn visible_to(viewer: &str) -> String {
+    format!(
+        "(p.user_id = {viewer}
+          OR p.visibility = 'public'
+          OR (p.visibility = 'friends' AND EXISTS (
+                SELECT 1 FROM friends fr
+                WHERE (fr.user_id = p.user_id AND fr.friend_id = {viewer})
+                   OR (fr.user_id = {viewer} AND fr.friend_id = p.user_id))))",
+        viewer = viewer
+    )
+}
+
+const EDITABLE_FIELDS: [&str; 3] = ["title", "description", "category"];
+
+// Names of the editable fields `edit` would change
+fn changed_fields(current: &Problem, edit: &CreateProblem) -> Vec<String> {
+    [
+        current.title != edit.title,
+        current.description != edit.description,
+        current.category != edit.category,
+    ]
+    .into_iter()
+    .zip(EDITABLE_FIELDS)
+    .filter(|(changed, _)| *changed)
+    .map(|(_, field)| field.to_string())
+    .collect()
+}
+
+// A new problem with its first revision. Tags are left to the caller.
+async fn insert_problem(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    problem: &CreateProblem,
+) -> RepoResult<Problem> {
+    let created = sqlx::query_as::<_, Problem>(
+        "INSERT INTO problems (id, title, description, category, user_id, created_at, documentation_links, video_references, difficulty_level, tags, solved, updated_at, visibility)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $6, $12)
+         RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(&problem.title)
+    .bind(&problem.description)
+    .bind(&problem.category)
+    .bind(user_id)
+    .bind(Utc::now())
+    .bind(&problem.documentation_links)
+    .bind(None::<Vec<String>>) // video_references as empty
+    .bind(problem.difficulty_level)
+    .bind(None::<Vec<String>>) // tags as empty
+    .bind(false) // solved defaults to false
+    .bind(problem.visibility.unwrap_or_default())
+    .fetch_one(&mut **tx)
+    .await?;
+
+    insert_revision(tx, &created, user_id, &EDITABLE_FIELDS.map(String::from), None).await?;
+    Ok(created)
+}
+
+// Snapshot `problem` as its next revision
+async fn insert_revision(
+    tx: &mut Transaction<'_, Postgres>,
+    problem: &Problem,
+    user_id: Uuid,
+    changed: &[String],
+    restored_from: Option<i32>,
+) -> RepoResult<()> {
+    sqlx::query(
+        "INSERT INTO problem_revisions
+            (problem_id, revision, user_id, title, description, category, changed_fields, restored_from)
+         SELECT $1, COALESCE(MAX(revision), 0) + 1, $2, $3, $4, $5, $6, $7
+         FROM problem_revisions WHERE problem_id = $1"
+    )
+    .bind(problem.id)
+    .bind(user_id)
+    .bind(&problem.title)
+    .bind(&problem.description)
+    .bind(&problem.category)
+    .bind(changed)
+    .bind(restored_from)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+#[async_trait]
+impl ProblemRepository for PgPool {
+    async fn create(&self, user_id: Uuid, problem: &CreateProblem) -> RepoResult<Problem> {
+        let mut tx = self.begin().await?;
+        let created = insert_problem(&mut tx, user_id, problem).await?;
+        tx.commit().await?;
+
+        Ok(created)
+    }
+
+    async fn import(&self, user_id: Uuid, problems: &[CreateProblem]) -> RepoResult<Vec<Problem>> {
+        let mut tx = self.begin().await?;
+
+        let mut created = Vec::with_capacity(problems.len());
+        for problem in problems {
+            let mut row = insert_problem(&mut tx, user_id, problem).await?;
+            if let Some(tags) = problem.tags.as_deref().filter(|tags| !tags.is_empty()) {
+                row.tags = Some(link_tags(&mut tx, row.id, tags).await?);
+            }
+            created.push(row);
+        }
+        tx.commit().await?;
+
+        Ok(created)
+    }
+
+    async fn find_visible(&self, id: Uuid, viewer: Uuid) -> RepoResult<Option<Problem>> {
+        Ok(sqlx::query_as::<_, Problem>(&format!(
+            "SELECT p.* FROM problems p WHERE p.id = $1 AND {}",
+            visible_to("$2")
+        ))
+        .bind(id)
+        .bind(viewer)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn find_for_owner(&self, id: Uuid, user_id: Uuid) -> RepoResult<Option<Problem>> {
+        Ok(sqlx::query_as::<_, Problem>(
+            "SELECT * FROM problems WHERE id = $1 AND user_id = $2"
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn owned_by(&self, user_id: Uuid) -> RepoResult<Vec<Problem>> {
+        Ok(sqlx::query_as::<_, Problem>(
+            "SELECT * FROM problems WHERE user_id = $1 ORDER BY created_at, id"
+        )
+        .bind(user_id)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn answered_by(&self, user_id: Uuid) -> RepoResult<Vec<Problem>> {
+        Ok(sqlx::query_as::<_, Problem>(&format!(
+            "SELECT p.* FROM problems p
+             WHERE p.user_id <> $1
+               AND EXISTS (SELECT 1 FROM problem_solutions s WHERE s.problem_id = p.id AND s.user_id = $1)
+               AND {}
+             ORDER BY p.created_at, p.id",
+            visible_to("$1")
+        ))
+        .bind(user_id)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        problem: &CreateProblem,
+        restored_from: Option<i32>,
+    ) -> RepoResult<Option<Problem>> {
+        let mut tx = self.begin().await?;
+
+        // The row lock also serializes revision numbers
+        let Some(current) = sqlx::query_as::<_, Problem>(
+            "SELECT * FROM problems WHERE id = $1 AND user_id = $2 FOR UPDATE"
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let changed = changed_fields(&current, problem);
+        let visibility = problem.visibility.unwrap_or(current.visibility);
+        let difficulty = problem.difficulty_level.or(current.difficulty_level);
+        let links = problem.documentation_links.clone().or_else(|| current.documentation_links.clone());
+        if changed.is_empty()
+            && visibility == current.visibility
+            && difficulty == current.difficulty_level
+            && links == current.documentation_links
+        {
+            return Ok(Some(current));
+        }
+
+        let updated = sqlx::query_as::<_, Problem>(
+            "UPDATE problems
+             SET title = $1, description = $2, category = $3, visibility = $4, difficulty_level = $5,
+                 documentation_links = $6
+             WHERE id = $7
+             RETURNING *"
+        )
+        .bind(&problem.title)
+        .bind(&problem.description)
+        .bind(&problem.category)
+        .bind(visibility)
+        .bind(difficulty)
+        .bind(&links)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // Revisions track title, description and category, other changes alone don't make one
+        if !changed.is_empty() {
+            insert_revision(&mut tx, &updated, user_id, &changed, restored_from).await?;
+        }
+        tx.commit().await?;
+
+        Ok(Some(updated))
+    }
+
+    async fn revisions(&self, problem_id: Uuid) -> RepoResult<Vec<ProblemRevision>> {
+        Ok(sqlx::query_as::<_, ProblemRevision>(
+            "SELECT r.*, u.username
+             FROM problem_revisions r
+             JOIN users u ON u.id = r.user_id
+             WHERE r.problem_id = $1
+             ORDER BY r.revision DESC"
+        )
+        .bind(problem_id)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn find_revision(&self, problem_id: Uuid, revision: i32) -> RepoResult<Option<ProblemRevision>> {
+        Ok(sqlx::query_as::<_, ProblemRevision>(
+            "SELECT r.*, u.username
+             FROM problem_revisions r
+             JOIN users u ON u.id = r.user_id
+             WHERE r.problem_id = $1 AND r.revision = $2"
+        )
+        .bind(problem_id)
+        .bind(revision)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn delete(&self, id: Uuid, user_id: Uuid) -> RepoResult<bool> {
+        let result = sqlx::query("DELETE FROM problems WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(self)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_any(&self, id: Uuid) -> RepoResult<bool> {
+        let result = sqlx::query("DELETE FROM problems WHERE id = $1")
+            .bind(id)
+            .execute(self)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn set_solved(&self, id: Uuid, user_id: Uuid, solved: bool) -> RepoResult<Option<Problem>> {
+        Ok(sqlx::query_as::<_, Problem>(
+            "UPDATE problems
+             SET solved = $1
+             WHERE id = $2 AND user_id = $3
+             RETURNING *"
+        )
+        .bind(solved)
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn set_visibility(&self, id: Uuid, user_id: Uuid, visibility: Visibility) -> RepoResult<Option<Problem>> {
+        Ok(sqlx::query_as::<_, Problem>(
+            "UPDATE problems
+             SET visibility = $1
+             WHERE id = $2 AND user_id = $3
+             RETURNING *"
+        )
+        .bind(visibility)
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn categories(&self, viewer: Uuid) -> RepoResult<Vec<CategoryCount>> {
+        Ok(sqlx::query_as::<_, CategoryCount>(&format!(
+            "SELECT p.category AS name, COUNT(*) AS count
+             FROM problems p
+             WHERE {}
+             GROUP BY p.category
+             ORDER BY count DESC, p.category ASC",
+            visible_to("$1")
+        ))
+        .bind(viewer)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn stats(&self, user_id: Uuid) -> RepoResult<ProblemStats> {
+        Ok(sqlx::query_as::<_, ProblemStats>(
+            "SELECT
+                COUNT(*) AS total_problems,
+                COUNT(CASE WHEN solved = true THEN 1 END) AS solved_problems,
+                COUNT(CASE WHEN difficulty_level = 'easy' THEN 1 END) AS easy_problems,
+                COUNT(CASE WHEN difficulty_level = 'medium' THEN 1 END) AS medium_problems,
+                COUNT(CASE WHEN difficulty_level = 'hard' THEN 1 END) AS hard_problems,
+                COUNT(DISTINCT category) AS categories_explored
+             FROM problems
+             WHERE user_id = $1"
+        )
+        .bind(user_id)
+        .fetch_one(self)
+        .await?)
+    }
+
+    async fn list(&self, listing: &ProblemListing) -> RepoResult<ProblemPage> {
+        let rows = listing.build_query().build_query_as::<ProblemListItem>().fetch_all(self).await?;
+        Ok(listing.finish(rows))
+    }
+
+    async fn find_listed(&self, id: Uuid, viewer: Uuid) -> RepoResult<Option<ProblemListItem>> {
+        Ok(sqlx::query_as::<_, ProblemListItem>(&format!(
+            "{} p.id = $1 AND {} {}",
+            LISTED_SELECT,
+            visible_to("$2"),
+            LISTED_GROUP_BY
+        ))
+        .bind(id)
+        .bind(viewer)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn upsert_feedback(
+        &self,
+        problem_id: Uuid,
+        user_id: Uuid,
+        input: &ProblemFeedbackInput,
+    ) -> RepoResult<(ProblemFeedback, bool)> {
+        // xmax is 0 only for rows the statement inserted rather than updated
+        let (id, created_at, created) = sqlx::query_as::<_, (Uuid, Option<NaiveDateTime>, bool)>(
+            "INSERT INTO problem_feedback (problem_id, user_id, rating, feedback, is_helpful)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (problem_id, user_id) DO UPDATE SET
+                rating = EXCLUDED.rating,
+                feedback = EXCLUDED.feedback,
+                is_helpful = EXCLUDED.is_helpful,
+                created_at = NOW()
+             RETURNING id, created_at, (xmax = 0) AS created"
+        )
+        .bind(problem_id)
+        .bind(user_id)
+        .bind(input.rating)
+        .bind(input.feedback.as_deref())
+        .bind(input.is_helpful)
+        .fetch_one(self)
+        .await?;
+
+        let feedback = ProblemFeedback {
+            id,
+            problem_id,
+            user_id,
+            rating: input.rating,
+            feedback: input.feedback.clone(),
+            is_helpful: input.is_helpful,
+            created_at,
+        };
+
+        Ok((feedback, created))
+    }
+
+    async fn list_feedback(&self, problem_id: Uuid) -> RepoResult<Vec<FeedbackWithAuthor>> {
+        Ok(sqlx::query_as::<_, FeedbackWithAuthor>(
+            "SELECT pf.id, pf.user_id, u.username AS user_name, u.avatar_url AS user_avatar,
+                    pf.rating, pf.feedback, pf.is_helpful, pf.created_at
+             FROM problem_feedback pf
+             JOIN users u ON pf.user_id = u.id
+             WHERE pf.problem_id = $1
+             ORDER BY pf.created_at DESC"
+        )
+        .bind(problem_id)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn delete_feedback(&self, feedback_id: Uuid) -> RepoResult<bool> {
+        let result = sqlx::query("DELETE FROM problem_feedback WHERE id = $1")
+            .bind(feedback_id)
+            .execute(self)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+impl State {
+    // The in-memory insert_problem
+    fn insert_problem(&mut self, user_id: Uuid, problem: &CreateProblem) -> Problem {
+        let now = Utc::now();
+        let problem = Problem {
+            id: Uuid::new_v4(),
+            title: problem.title.clone(),
+            description: problem.description.clone(),
+            category: problem.category.clone(),
+            user_id,
+            created_at: now,
+            documentation_links: problem.documentation_links.clone(),
+            video_references: None,
+            difficulty_level: problem.difficulty_level,
+            tags: None,
+            solved: false,
+            updated_at: now,
+            visibility: problem.visibility.unwrap_or_default(),
+        };
+
+        self.record_revision(&problem, user_id, EDITABLE_FIELDS.map(String::from).to_vec(), None);
+        self.problems.push(problem.clone());
+        problem
+    }
+
+    fn record_revision(&mut self, problem: &Problem, user_id: Uuid, changed: Vec<String>, restored_from: Option<i32>) {
+        let revision = self
+            .revisions
+            .iter()
+            .filter(|r| r.problem_id == problem.id)
+            .map(|r| r.revision)
+            .max()
+            .unwrap_or(0)
+            + 1;
+
+        self.revisions.push(ProblemRevision {
+            id: Uuid::new_v4(),
+            problem_id: problem.id,
+            revision,
+            user_id,
+            username: String::new(),
+            title: problem.title.clone(),
+            description: problem.description.clone(),
+            category: problem.category.clone(),
+            changed_fields: changed,
+            restored_from,
+            created_at: Utc::now(),
+        });
+    }
+
+    // Usernames can change, so stored revisions leave theirs blank and it's filled on read
+    fn with_username(&self, revision: &ProblemRevision) -> ProblemRevision {
+        ProblemRevision { username: self.username(revision.user_id).unwrap_or_default(), ..revision.clone() }
+    }
+}
+
+// What LISTED_SELECT returns for a problem. None if its author is gone.
+fn list_item(state: &State, p: &Problem) -> Option<ProblemListItem> {
+    let feedback: Vec<&ProblemFeedback> = state.feedback.iter().filter(|f| f.problem_id == p.id).collect();
+    let ratings: Vec<i32> = feedback.iter().filter_map(|f| f.rating).collect();
+    Some(ProblemListItem {
+        id: p.id,
+        title: p.title.clone(),
+        description: p.description.clone(),
+        category: p.category.clone(),
+        user_id: p.user_id,
+        created_by: state.username(p.user_id)?,
+        author_avatar_url: None,
+        difficulty_level: p.difficulty_level,
+        tags: p.tags.clone().unwrap_or_default(),
+        solved: p.solved,
+        visibility: p.visibility,
+        created_at: p.created_at,
+        updated_at: p.updated_at,
+        average_rating: if ratings.is_empty() {
+            0.0
+        } else {
+            ratings.iter().map(|r| f64::from(*r)).sum::<f64>() / ratings.len() as f64
+        },
+        feedback_count: feedback.len() as i64,
+        helpful_count: feedback.iter().filter(|f| f.is_helpful == Some(true)).count() as i64,
+    })
+}
+
+#[async_trait]
+impl ProblemRepository for InMemoryStore {
+    async fn create(&self, user_id: Uuid, problem: &CreateProblem) -> RepoResult<Problem> {
+        let mut state = self.state();
+        if !state.users.contains_key(&user_id) {
+            return Err(AppError::NotFound("User not found".into()));
+        }
+
+        Ok(state.insert_problem(user_id, problem))
+    }
+
+    async fn import(&self, user_id: Uuid, problems: &[CreateProblem]) -> RepoResult<Vec<Problem>> {
+        let mut state = self.state();
+        if !state.users.contains_key(&user_id) {
+            return Err(AppError::NotFound("User not found".into()));
+        }
+
+        let mut created = Vec::with_capacity(problems.len());
+        for problem in problems {
+            let mut row = state.insert_problem(user_id, problem);
+            if let Some(tags) = problem.tags.as_deref().filter(|tags| !tags.is_empty()) {
+                row.tags = Some(state.tag_problem(row.id, tags));
+            }
+            created.push(row);
+        }
+        Ok(created)
+    }
+
+    async fn find_visible(&self, id: Uuid, viewer: Uuid) -> RepoResult<Option<Problem>> {
+        let state = self.state();
+        Ok(state.problems.iter().find(|p| p.id == id && state.can_view(viewer, p)).cloned())
+    }
+
+    async fn find_for_owner(&self, id: Uuid, user_id: Uuid) -> RepoResult<Option<Problem>> {
+        Ok(self.state().problems.iter().find(|p| p.id == id && p.user_id == user_id).cloned())
+    }
+
+    async fn owned_by(&self, user_id: Uuid) -> RepoResult<Vec<Problem>> {
+        let mut problems: Vec<Problem> =
+            self.state().problems.iter().filter(|p| p.user_id == user_id).cloned().collect();
+        problems.sort_by_key(|p| (p.created_at, p.id));
+        Ok(problems)
+    }
+
+    async fn answered_by(&self, user_id: Uuid) -> RepoResult<Vec<Problem>> {
+        let state = self.state();
+        let mut problems: Vec<Problem> = state
+            .problems
+            .iter()
+            .filter(|p| p.user_id != user_id && state.can_view(user_id, p))
+            .filter(|p| state.solutions.iter().any(|s| s.problem_id == p.id && s.user_id == user_id))
+            .cloned()
+            .collect();
+        problems.sort_by_key(|p| (p.created_at, p.id));
+        Ok(problems)
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        problem: &CreateProblem,
+        restored_from: Option<i32>,
+    ) -> RepoResult<Option<Problem>> {
+        let mut state = self.state();
+        let Some(existing) = state.problems.iter_mut().find(|p| p.id == id && p.user_id == user_id) else {
+            return Ok(None);
+        };
+
+        let changed = changed_fields(existing, problem);
+        let visibility = problem.visibility.unwrap_or(existing.visibility);
+        let difficulty = problem.difficulty_level.or(existing.difficulty_level);
+        let links = problem.documentation_links.clone().or_else(|| existing.documentation_links.clone());
+        if changed.is_empty()
+            && visibility == existing.visibility
+            && difficulty == existing.difficulty_level
+            && links == existing.documentation_links
+        {
+            return Ok(Some(existing.clone()));
+        }
+
+        existing.title = problem.title.clone();
+        existing.description = problem.description.clone();
+        existing.category = problem.category.clone();
+        existing.visibility = visibility;
+        existing.difficulty_level = difficulty;
+        existing.documentation_links = links;
+        existing.updated_at = Utc::now();
+        let updated = existing.clone();
+
+        if !changed.is_empty() {
+            state.record_revision(&updated, user_id, changed, restored_from);
+        }
+        Ok(Some(updated))
+    }
+
+    async fn revisions(&self, problem_id: Uuid) -> RepoResult<Vec<ProblemRevision>> {
+        let state = self.state();
+        let mut revisions: Vec<ProblemRevision> = state
+            .revisions
+            .iter()
+            .filter(|r| r.problem_id == problem_id)
+            .map(|r| state.with_username(r))
+            .collect();
+
+        revisions.sort_by_key(|r| Reverse(r.revision));
+        Ok(revisions)
+    }
+
+    async fn find_revision(&self, problem_id: Uuid, revision: i32) -> RepoResult<Option<ProblemRevision>> {
+        let state = self.state();
+        Ok(state
+            .revisions
+            .iter()
+            .find(|r| r.problem_id == problem_id && r.revision == revision)
+            .map(|r| state.with_username(r)))
+    }
+
+    async fn delete(&self, id: Uuid, user_id: Uuid) -> RepoResult<bool> {
+        let mut state = self.state();
+        let before = state.problems.len();
+        state.problems.retain(|p| !(p.id == id && p.user_id == user_id));
+
+        let deleted = state.problems.len() < before;
+        if deleted {
+            state.remove_problem_children(id);
+        }
+        Ok(deleted)
+    }
+
+    async fn delete_any(&self, id: Uuid) -> RepoResult<bool> {
+        let mut state = self.state();
+        let before = state.problems.len();
+        state.problems.retain(|p| p.id != id);
+
+        let deleted = state.problems.len() < before;
+        if deleted {
+            state.remove_problem_children(id);
+        }
+        Ok(deleted)
+    }
+
+    async fn set_solved(&self, id: Uuid, user_id: Uuid, solved: bool) -> RepoResult<Option<Problem>> {
+        Ok(self
+            .state()
+            .problems
+            .iter_mut()
+            .find(|p| p.id == id && p.user_id == user_id)
+            .map(|problem| {
+                problem.solved = solved;
+                problem.updated_at = Utc::now();
+                problem.clone()
+            }))
+    }
+
+    async fn set_visibility(&self, id: Uuid, user_id: Uuid, visibility: Visibility) -> RepoResult<Option<Problem>> {
+        Ok(self
+            .state()
+            .problems
+            .iter_mut()
+            .find(|p| p.id == id && p.user_id == user_id)
+            .map(|problem| {
+                problem.visibility = visibility;
+                problem.updated_at = Utc::now();
+                problem.clone()
+            }))
+    }
+
+    async fn categories(&self, viewer: Uuid) -> RepoResult<Vec<CategoryCount>> {
+        let state = self.state();
+        let mut counts = std::collections::HashMap::<String, i64>::new();
+        for problem in state.problems.iter().filter(|p| state.can_view(viewer, p)) {
+            *counts.entry(problem.category.clone()).or_default() += 1;
+        }
+
+        let mut categories: Vec<CategoryCount> =
+            counts.into_iter().map(|(name, count)| CategoryCount { name, count }).collect();
+        categories.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+        Ok(categories)
+    }
+
+    async fn stats(&self, user_id: Uuid) -> RepoResult<ProblemStats> {
+        let state = self.state();
+        let problems: Vec<&Problem> = state.problems.iter().filter(|p| p.user_id == user_id).collect();
+        let difficulty = |level: Difficulty| {
+            problems.iter().filter(|p| p.difficulty_level == Some(level)).count() as i64
+        };
+
+        let mut categories: Vec<&str> = problems.iter().map(|p| p.category.as_str()).collect();
+        categories.sort_unstable();
+        categories.dedup();
+
+        Ok(ProblemStats {
+            total_problems: problems.len() as i64,
+            solved_problems: problems.iter().filter(|p| p.solved).count() as i64,
+            easy_problems: difficulty(Difficulty::Easy),
+            medium_problems: difficulty(Difficulty::Medium),
+            hard_problems: difficulty(Difficulty::Hard),
+            categories_explored: categories.len() as i64,
+        })
+    }
+
+    async fn list(&self, listing: &ProblemListing) -> RepoResult<ProblemPage> {
+        let state = self.state();
+        let mut listing = listing.clone();
+        listing.tags = listing.tags.iter().map(|name| state.tag_name(name).to_string()).collect();
+
+        let items = state
+            .problems
+            .iter()
+            .filter(|p| state.can_view(listing.scope.viewer(), p))
+            .filter(|p| listing.category_id.is_none_or(|category_id| state.maps_to(p.id, category_id)))
+            .filter_map(|p| list_item(&state, p))
+            .filter(|item| listing.matches(item))
+            .collect();
+
+        Ok(listing.page(items))
+    }
+
+    async fn find_listed(&self, id: Uuid, viewer: Uuid) -> RepoResult<Option<ProblemListItem>> {
+        let state = self.state();
+        Ok(state
+            .problems
+            .iter()
+            .find(|p| p.id == id && state.can_view(viewer, p))
+            .and_then(|p| list_item(&state, p)))
+    }
+
+    async fn upsert_feedback(
+        &self,
+        problem_id: Uuid,
+        user_id: Uuid,
+        input: &ProblemFeedbackInput,
+    ) -> RepoResult<(ProblemFeedback, bool)> {
+        let mut state = self.state();
+        if !state.problems.iter().any(|p| p.id == problem_id) {
+            return Err(AppError::NotFound("Problem not found".into()));
+        }
+
+        let now = Some(Utc::now().naive_utc());
+        if let Some(existing) = state
+            .feedback
+            .iter_mut()
+            .find(|f| f.problem_id == problem_id && f.user_id == user_id)
+        {
+            existing.rating = input.rating;
+            existing.feedback = input.feedback.clone();
+            existing.is_helpful = input.is_helpful;
+            existing.created_at = now;
+            return Ok((existing.clone(), false));
+        }
+
+        let feedback = ProblemFeedback {
+            id: Uuid::new_v4(),
+            problem_id,
+            user_id,
+            rating: input.rating,
+            feedback: input.feedback.clone(),
+            is_helpful: input.is_helpful,
+            created_at: now,
+        };
+
+        state.feedback.push(feedback.clone());
+        Ok((feedback, true))
+    }
+
+    async fn list_feedback(&self, problem_id: Uuid) -> RepoResult<Vec<FeedbackWithAuthor>> {
+        let state = self.state();
+        let mut feedback: Vec<FeedbackWithAuthor> = state
+            .feedback
+            .iter()
+            .filter(|f| f.problem_id == problem_id)
+            .filter_map(|f| {
+                Some(FeedbackWithAuthor {
+                    id: f.id,
+                    user_id: f.user_id,
+                    user_name: state.username(f.user_id)?,
+                    user_avatar: None,
+                    rating: f.rating,
+                    feedback: f.feedback.clone(),
+                    is_helpful: f.is_helpful,
+                    created_at: f.created_at,
+                })
+            })
+            .collect();
+
+        feedback.sort_by_key(|x| Reverse(x.created_at));
+        Ok(feedback)
+    }
+
+    async fn delete_feedback(&self, feedback_id: Uuid) -> RepoResult<bool> {
+        let mut state = self.state();
+        let before = state.feedback.len();
+        state.feedback.retain(|f| f.id != feedback_id);
+        Ok(state.feedback.len() < before)
+    }
+}
@@ -0,0 +1,288 @@
+use std::cmp::Reverse;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::resource::{CreateResourceRequest, ProblemCategory, ProblemResource};
+
+use super::memory::State;
+use super::problems::visible_to;
+use super::{InMemoryStore, RepoResult};
+
+// Learning resources attached to problems, and the category catalog
+#[async_trait]
+pub trait ResourceRepository: Send + Sync {
+    // Recommended first, then newest
+    async fn list_for_problem(&self, problem_id: Uuid) -> RepoResult<Vec<ProblemResource>>;
+    async fn create(&self, problem_id: Uuid, resource: &CreateResourceRequest) -> RepoResult<ProblemResource>;
+    // The whole catalog, by name
+    async fn list_categories(&self) -> RepoResult<Vec<ProblemCategory>>;
+    async fn find_category(&self, id: Uuid) -> RepoResult<Option<ProblemCategory>>;
+    async fn categories_for_problem(&self, problem_id: Uuid) -> RepoResult<Vec<ProblemCategory>>;
+    // Map a problem to exactly these categories plus the one matching its own category.
+    // NotFound if any of them isn't in the catalog. Returns the resulting mapping.
+    async fn set_problem_categories(&self, problem_id: Uuid, category_ids: &[Uuid]) -> RepoResult<Vec<ProblemCategory>>;
+    // Resources of the problems in a category that `viewer` may see, recommended first
+    async fn list_for_category(&self, category_id: Uuid, viewer: Uuid, limit: i64) -> RepoResult<Vec<ProblemResource>>;
+}
+
+const RESOURCE_COLUMNS: &str = "r.id, r.problem_id, r.resource_type, r.title, r.url, r.description, r.thumbnail_url,
+                                r.provider, r.duration, r.difficulty_level, r.is_recommended, r.created_at";
+
+#[async_trait]
+impl ResourceRepository for PgPool {
+    async fn list_for_problem(&self, problem_id: Uuid) -> RepoResult<Vec<ProblemResource>> {
+        Ok(sqlx::query_as::<_, ProblemResource>(&format!(
+            "SELECT {} FROM problem_resources r
+             WHERE r.problem_id = $1
+             ORDER BY r.is_recommended DESC, r.created_at DESC",
+            RESOURCE_COLUMNS
+        ))
+        .bind(problem_id)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn create(&self, problem_id: Uuid, resource: &CreateResourceRequest) -> RepoResult<ProblemResource> {
+        Ok(sqlx::query_as::<_, ProblemResource>(&format!(
+            "INSERT INTO problem_resources AS r
+                (problem_id, resource_type, title, url, description, thumbnail_url, provider, duration, difficulty_level)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             RETURNING {}",
+            RESOURCE_COLUMNS
+        ))
+        .bind(problem_id)
+        .bind(resource.resource_type)
+        .bind(&resource.title)
+        .bind(&resource.url)
+        .bind(&resource.description)
+        .bind(&resource.thumbnail_url)
+        .bind(&resource.provider)
+        .bind(&resource.duration)
+        .bind(resource.difficulty_level)
+        .fetch_one(self)
+        .await?)
+    }
+
+    async fn list_categories(&self) -> RepoResult<Vec<ProblemCategory>> {
+        Ok(sqlx::query_as::<_, ProblemCategory>("SELECT * FROM problem_categories ORDER BY name")
+            .fetch_all(self)
+            .await?)
+    }
+
+    async fn find_category(&self, id: Uuid) -> RepoResult<Option<ProblemCategory>> {
+        Ok(sqlx::query_as::<_, ProblemCategory>("SELECT * FROM problem_categories WHERE id = $1")
+            .bind(id)
+            .fetch_optional(self)
+            .await?)
+    }
+
+    async fn categories_for_problem(&self, problem_id: Uuid) -> RepoResult<Vec<ProblemCategory>> {
+        Ok(sqlx::query_as::<_, ProblemCategory>(
+            "SELECT c.* FROM problem_categories c
+             JOIN problem_category_mappings m ON m.category_id = c.id
+             WHERE m.problem_id = $1
+             ORDER BY c.name"
+        )
+        .bind(problem_id)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn set_problem_categories(&self, problem_id: Uuid, category_ids: &[Uuid]) -> RepoResult<Vec<ProblemCategory>> {
+        let mut tx = self.begin().await?;
+
+        let category: String = sqlx::query_scalar("SELECT category FROM problems WHERE id = $1 FOR UPDATE")
+            .bind(problem_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Problem not found".into()))?;
+
+        let known: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM problem_categories WHERE id = ANY($1)")
+            .bind(category_ids)
+            .fetch_one(&mut *tx)
+            .await?;
+        if known as usize != category_ids.len() {
+            return Err(AppError::NotFound("Category not found".into()));
+        }
+
+        sqlx::query("DELETE FROM problem_category_mappings WHERE problem_id = $1")
+            .bind(problem_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO problem_category_mappings (problem_id, category_id)
+             SELECT $1, id FROM problem_categories WHERE id = ANY($2) OR LOWER(name) = LOWER($3)"
+        )
+        .bind(problem_id)
+        .bind(category_ids)
+        .bind(&category)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        self.categories_for_problem(problem_id).await
+    }
+
+    async fn list_for_category(&self, category_id: Uuid, viewer: Uuid, limit: i64) -> RepoResult<Vec<ProblemResource>> {
+        Ok(sqlx::query_as::<_, ProblemResource>(&format!(
+            "SELECT {} FROM problem_resources r
+             JOIN problem_category_mappings m ON m.problem_id = r.problem_id
+             JOIN problems p ON p.id = r.problem_id
+             WHERE m.category_id = $1 AND {}
+             ORDER BY r.is_recommended DESC, r.created_at DESC
+             LIMIT $3",
+            RESOURCE_COLUMNS,
+            visible_to("$2")
+        ))
+        .bind(category_id)
+        .bind(viewer)
+        .bind(limit)
+        .fetch_all(self)
+        .await?)
+    }
+}
+
+// Seeded into the catalog by the enhanced schema migration
+const DEFAULT_CATEGORIES: [(&str, &str, &str, &str); 8] = [
+    ("Algorithms", "Algorithm design and analysis problems", "cpu", "#FF6B6B"),
+    ("Data Structures", "Arrays, trees, graphs, and other data structures", "database", "#4ECDC4"),
+    ("Dynamic Programming", "Problems involving dynamic programming techniques", "zap", "#45B7D1"),
+    ("Math", "Mathematical and number theory problems", "calculator", "#96CEB4"),
+    ("Strings", "String manipulation and pattern matching", "type", "#FECA57"),
+    ("Graphs", "Graph theory and traversal problems", "share-2", "#FF9FF3"),
+    ("Trees", "Binary trees, BST, and tree algorithms", "git-branch", "#54A0FF"),
+    ("Sorting", "Sorting algorithms and related problems", "arrow-up-down", "#5F27CD"),
+];
+
+impl State {
+    // The catalog, seeded on first use like the migration seeds Postgres
+    fn category_catalog(&mut self) -> &[ProblemCategory] {
+        if self.categories.is_empty() {
+            let now = Some(Utc::now().naive_utc());
+            self.categories = DEFAULT_CATEGORIES
+                .iter()
+                .map(|(name, description, icon, color)| ProblemCategory {
+                    id: Uuid::new_v4(),
+                    name: name.to_string(),
+                    description: Some(description.to_string()),
+                    icon: Some(icon.to_string()),
+                    color: Some(color.to_string()),
+                    created_at: now,
+                })
+                .collect();
+            self.categories.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        &self.categories
+    }
+
+    // Problems link to the catalog entry matching their category name, as the
+    // problems_map_category trigger does in Postgres, and to any set explicitly
+    fn in_category(&self, problem_id: Uuid, category: &ProblemCategory) -> bool {
+        self.category_mappings.contains(&(problem_id, category.id))
+            || self
+                .problems
+                .iter()
+                .any(|p| p.id == problem_id && p.category.eq_ignore_ascii_case(&category.name))
+    }
+
+    // Whether the problem is mapped to the catalog entry `category_id`
+    pub(super) fn maps_to(&self, problem_id: Uuid, category_id: Uuid) -> bool {
+        self.categories
+            .iter()
+            .find(|c| c.id == category_id)
+            .is_some_and(|c| self.in_category(problem_id, c))
+    }
+
+    fn visible_problem(&self, problem_id: Uuid, viewer: Uuid) -> bool {
+        self.problems.iter().any(|p| p.id == problem_id && self.can_view(viewer, p))
+    }
+}
+
+fn recommended_first(resources: &mut [ProblemResource]) {
+    resources.sort_by_key(|r| (Reverse(r.is_recommended), Reverse(r.created_at)));
+}
+
+#[async_trait]
+impl ResourceRepository for InMemoryStore {
+    async fn list_for_problem(&self, problem_id: Uuid) -> RepoResult<Vec<ProblemResource>> {
+        let mut resources: Vec<ProblemResource> =
+            self.state().resources.iter().filter(|r| r.problem_id == problem_id).cloned().collect();
+        recommended_first(&mut resources);
+        Ok(resources)
+    }
+
+    async fn create(&self, problem_id: Uuid, resource: &CreateResourceRequest) -> RepoResult<ProblemResource> {
+        let mut state = self.state();
+        if !state.problems.iter().any(|p| p.id == problem_id) {
+            return Err(AppError::NotFound("Problem not found".into()));
+        }
+
+        let resource = ProblemResource {
+            id: Uuid::new_v4(),
+            problem_id,
+            resource_type: resource.resource_type,
+            title: resource.title.clone(),
+            url: resource.url.clone(),
+            description: resource.description.clone(),
+            thumbnail_url: resource.thumbnail_url.clone(),
+            provider: resource.provider.clone(),
+            duration: resource.duration.clone(),
+            difficulty_level: resource.difficulty_level,
+            is_recommended: false,
+            created_at: Some(Utc::now().naive_utc()),
+        };
+
+        state.resources.push(resource.clone());
+        Ok(resource)
+    }
+
+    async fn list_categories(&self) -> RepoResult<Vec<ProblemCategory>> {
+        Ok(self.state().category_catalog().to_vec())
+    }
+
+    async fn find_category(&self, id: Uuid) -> RepoResult<Option<ProblemCategory>> {
+        Ok(self.state().category_catalog().iter().find(|c| c.id == id).cloned())
+    }
+
+    async fn categories_for_problem(&self, problem_id: Uuid) -> RepoResult<Vec<ProblemCategory>> {
+        let mut state = self.state();
+        let catalog = state.category_catalog().to_vec();
+        Ok(catalog.into_iter().filter(|c| state.in_category(problem_id, c)).collect())
+    }
+
+    async fn set_problem_categories(&self, problem_id: Uuid, category_ids: &[Uuid]) -> RepoResult<Vec<ProblemCategory>> {
+        let mut state = self.state();
+        if !state.problems.iter().any(|p| p.id == problem_id) {
+            return Err(AppError::NotFound("Problem not found".into()));
+        }
+        let catalog = state.category_catalog().to_vec();
+        if !category_ids.iter().all(|id| catalog.iter().any(|c| c.id == *id)) {
+            return Err(AppError::NotFound("Category not found".into()));
+        }
+
+        state.category_mappings.retain(|(mapped, _)| *mapped != problem_id);
+        state.category_mappings.extend(category_ids.iter().map(|id| (problem_id, *id)));
+        Ok(catalog.into_iter().filter(|c| state.in_category(problem_id, c)).collect())
+    }
+
+    async fn list_for_category(&self, category_id: Uuid, viewer: Uuid, limit: i64) -> RepoResult<Vec<ProblemResource>> {
+        let mut state = self.state();
+        let Some(category) = state.category_catalog().iter().find(|c| c.id == category_id).cloned() else {
+            return Ok(Vec::new());
+        };
+
+        let mut resources: Vec<ProblemResource> = state
+            .resources
+            .iter()
+            .filter(|r| state.in_category(r.problem_id, &category) && state.visible_problem(r.problem_id, viewer))
+            .cloned()
+            .collect();
+        recommended_first(&mut resources);
+        resources.truncate(limit as usize);
+        Ok(resources)
+    }
+}
@@ -0,0 +1,277 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+use crate::models::search::{SearchQuery, SearchResult, SearchResultType};
+
+use super::problems::visible_to;
+use super::{InMemoryStore, RepoResult};
+
+// Keyword search across problems, solutions and resources
+#[async_trait]
+pub trait SearchRepository: Send + Sync {
+    // Best matches first, with the total number of hits for pagination
+    async fn search(&self, query: &SearchQuery) -> RepoResult<(Vec<SearchResult>, i64)>;
+}
+
+const HEADLINE_OPTIONS: &str = "StartSel=<mark>, StopSel=</mark>, MaxWords=35, MinWords=15, MaxFragments=2";
+
+// SQL for `expr` with HTML special characters escaped. Snippets are rendered as HTML for the
+// <mark> tags, so the text around them mustn't be. The parser reads entities as single
+// tokens, so ts_headline never cuts one in half.
+fn html_escaped(expr: &str) -> String {
+    format!(
+        r#"replace(replace(replace(replace(replace({expr}, '&', '&amp;'), '<', '&lt;'), '>', '&gt;'), '"', '&quot;'), '''', '&#39;')"#
+    )
+}
+
+// One SELECT per requested type, combined into `hits`. Bodies are only turned into snippets
+// for the page that's returned. $1 is the search text and $2 the viewer.
+fn hits_sql(types: &[SearchResultType]) -> String {
+    let visible = visible_to("$2");
+    let sources: Vec<String> = types
+        .iter()
+        .map(|t| match t {
+            SearchResultType::Problem => {
+                "SELECT 'problem', p.id, p.id, p.title,
+                        p.description, ts_rank_cd(p.search_vector, q.query), p.created_at
+                 FROM problems p, q
+                 WHERE p.search_vector @@ q.query AND {visible}"
+            }
+            SearchResultType::Solution => {
+                "SELECT 'solution', s.id, s.problem_id, p.title,
+                        s.solution_text, ts_rank_cd(s.search_vector, q.query), s.created_at
+                 FROM problem_solutions s
+                 JOIN problems p ON p.id = s.problem_id, q
+                 WHERE s.search_vector @@ q.query AND {visible}"
+            }
+            SearchResultType::Resource => {
+                "SELECT 'resource', r.id, r.problem_id, r.title,
+                        COALESCE(r.description, r.title), ts_rank_cd(r.search_vector, q.query),
+                        r.created_at AT TIME ZONE 'UTC'
+                 FROM problem_resources r
+                 JOIN problems p ON p.id = r.problem_id, q
+                 WHERE r.search_vector @@ q.query AND {visible}"
+            }
+        })
+        .map(|source| source.replace("{visible}", &visible))
+        .collect();
+
+    format!(
+        "WITH q AS (SELECT websearch_to_tsquery('english', $1) AS query),
+         hits (result_type, id, problem_id, title, body, rank, created_at) AS ({})",
+        sources.join(" UNION ALL ")
+    )
+}
+
+#[async_trait]
+impl SearchRepository for PgPool {
+    async fn search(&self, query: &SearchQuery) -> RepoResult<(Vec<SearchResult>, i64)> {
+        let hits = hits_sql(&query.types);
+
+        let total = sqlx::query_scalar::<_, i64>(&format!("{} SELECT COUNT(*) FROM hits", hits))
+            .bind(&query.text)
+            .bind(query.viewer)
+            .fetch_one(self)
+            .await?;
+
+        let results = sqlx::query_as::<_, SearchResult>(&format!(
+            "{}
+             SELECT h.result_type, h.id, h.problem_id, h.title,
+                    ts_headline('english', {}, q.query, '{}') AS snippet,
+                    h.rank, h.created_at
+             FROM hits h, q
+             ORDER BY h.rank DESC, h.created_at DESC NULLS LAST
+             LIMIT $3 OFFSET $4",
+            hits,
+            html_escaped("h.body"),
+            HEADLINE_OPTIONS
+        ))
+        .bind(&query.text)
+        .bind(query.viewer)
+        .bind(query.limit)
+        .bind(query.offset)
+        .fetch_all(self)
+        .await?;
+
+        Ok((results, total))
+    }
+}
+
+const SNIPPET_CONTEXT: usize = 60;
+
+fn push_escaped(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+// Rough stand-in for ts_headline: the text around the first matching term, HTML-escaped,
+// with every match wrapped in <mark></mark>
+fn snippet(body: &str, terms: &[String]) -> String {
+    let first = {
+        let lower = body.to_lowercase();
+        terms.iter().filter_map(|t| lower.find(t.as_str())).min().unwrap_or(0)
+    };
+
+    let mut start = first.saturating_sub(SNIPPET_CONTEXT).min(body.len());
+    while !body.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (first + SNIPPET_CONTEXT * 2).min(body.len());
+    while !body.is_char_boundary(end) {
+        end += 1;
+    }
+    let window = &body[start..end];
+
+    // Lowercasing can shift byte offsets outside ASCII, skip highlighting then
+    let lower = window.to_lowercase();
+    let mut marked = String::with_capacity(window.len());
+    if lower.len() != window.len() {
+        push_escaped(&mut marked, window);
+        return marked;
+    }
+
+    let mut matches: Vec<(usize, usize)> = terms
+        .iter()
+        .flat_map(|t| lower.match_indices(t.as_str()).map(|(i, m)| (i, i + m.len())))
+        .filter(|(from, to)| window.is_char_boundary(*from) && window.is_char_boundary(*to))
+        .collect();
+    matches.sort_unstable();
+
+    let mut last = 0;
+    for (from, to) in matches {
+        if from < last {
+            continue;
+        }
+        push_escaped(&mut marked, &window[last..from]);
+        marked.push_str("<mark>");
+        push_escaped(&mut marked, &window[from..to]);
+        marked.push_str("</mark>");
+        last = to;
+    }
+    push_escaped(&mut marked, &window[last..]);
+    marked
+}
+
+// Words to find and words to exclude. Quotes are dropped, so phrases match word by word.
+fn parse_terms(text: &str) -> (Vec<String>, Vec<String>) {
+    let mut terms = Vec::new();
+    let mut excluded = Vec::new();
+    for word in text.split_whitespace() {
+        let word = word.trim_matches('"').to_lowercase();
+        match word.strip_prefix('-') {
+            Some(rest) if !rest.is_empty() => excluded.push(rest.to_string()),
+            _ if word.is_empty() || word == "or" => {}
+            _ => terms.push(word),
+        }
+    }
+    (terms, excluded)
+}
+
+// Every term has to appear somewhere and no excluded one may; the rank is how often the
+// terms appear, titles counting double
+fn rank(title: &str, body: &str, terms: &[String], excluded: &[String]) -> Option<f32> {
+    let title = title.to_lowercase();
+    let body = body.to_lowercase();
+    if excluded.iter().any(|e| title.contains(e.as_str()) || body.contains(e.as_str())) {
+        return None;
+    }
+
+    let mut rank = 0;
+    for term in terms {
+        let hits = title.matches(term.as_str()).count() * 2 + body.matches(term.as_str()).count();
+        if hits == 0 {
+            return None;
+        }
+        rank += hits;
+    }
+    Some(rank as f32)
+}
+
+// Plain substring matching, enough to exercise the endpoint
+#[async_trait]
+impl SearchRepository for InMemoryStore {
+    async fn search(&self, query: &SearchQuery) -> RepoResult<(Vec<SearchResult>, i64)> {
+        let (terms, excluded) = parse_terms(&query.text);
+        let state = self.state();
+        let mut results = Vec::new();
+
+        if query.types.contains(&SearchResultType::Problem) {
+            for problem in state.problems.iter().filter(|p| state.can_view(query.viewer, p)) {
+                let text = format!("{} {}", problem.description, problem.tags.as_deref().unwrap_or_default().join(" "));
+                if let Some(rank) = rank(&problem.title, &text, &terms, &excluded) {
+                    results.push(SearchResult {
+                        result_type: SearchResultType::Problem,
+                        id: problem.id,
+                        problem_id: problem.id,
+                        title: problem.title.clone(),
+                        snippet: snippet(&problem.description, &terms),
+                        rank,
+                        created_at: Some(problem.created_at),
+                    });
+                }
+            }
+        }
+
+        if query.types.contains(&SearchResultType::Solution) {
+            for solution in &state.solutions {
+                let Some(problem) = state
+                    .problems
+                    .iter()
+                    .find(|p| p.id == solution.problem_id && state.can_view(query.viewer, p))
+                else {
+                    continue;
+                };
+                if let Some(rank) = rank("", &solution.solution_text, &terms, &excluded) {
+                    results.push(SearchResult {
+                        result_type: SearchResultType::Solution,
+                        id: solution.id,
+                        problem_id: solution.problem_id,
+                        title: problem.title.clone(),
+                        snippet: snippet(&solution.solution_text, &terms),
+                        rank,
+                        created_at: Some(solution.created_at),
+                    });
+                }
+            }
+        }
+
+        if query.types.contains(&SearchResultType::Resource) {
+            for resource in &state.resources {
+                if !state.problems.iter().any(|p| p.id == resource.problem_id && state.can_view(query.viewer, p)) {
+                    continue;
+                }
+                let body = resource.description.as_deref().unwrap_or(&resource.title);
+                if let Some(rank) = rank(&resource.title, body, &terms, &excluded) {
+                    results.push(SearchResult {
+                        result_type: SearchResultType::Resource,
+                        id: resource.id,
+                        problem_id: resource.problem_id,
+                        title: resource.title.clone(),
+                        snippet: snippet(body, &terms),
+                        rank,
+                        created_at: resource.created_at.map(|at| at.and_utc()),
+                    });
+                }
+            }
+        }
+
+        results.sort_by(|a, b| b.rank.total_cmp(&a.rank).then_with(|| b.created_at.cmp(&a.created_at)));
+
+        let total = results.len() as i64;
+        let page = results
+            .into_iter()
+            .skip(query.offset as usize)
+            .take(query.limit as usize)
+            .collect();
+
+        Ok((page, total))
+    }
+}
@@ -0,0 +1,207 @@
+use std::cmp::Reverse;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::crypto::{generate_token, hash_token};
+use crate::models::session::{RefreshOutcome, Session};
+use crate::models::user::Role;
+
+use super::{InMemoryStore, RepoResult};
+
+// Login sessions and their single-use refresh tokens
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
+    // Start a new session and return it together with its first (plaintext) refresh token
+    async fn create(
+        &self,
+        user_id: Uuid,
+        user_agent: Option<String>,
+        ip_address: Option<String>,
+        ttl: Duration,
+    ) -> RepoResult<(Session, String)>;
+    // Exchange a refresh token for a new one; replaying a spent token revokes its session
+    async fn rotate(&self, refresh_token: &str) -> RepoResult<RefreshOutcome>;
+    // Current role of the session's user, or None if the session was revoked, has expired
+    // or the account is suspended
+    async fn active_role(&self, session_id: Uuid, user_id: Uuid) -> RepoResult<Option<Role>>;
+    async fn list_active_for_user(&self, user_id: Uuid) -> RepoResult<Vec<Session>>;
+    async fn revoke(&self, session_id: Uuid, user_id: Uuid, reason: &str) -> RepoResult<bool>;
+    async fn revoke_all_for_user(&self, user_id: Uuid, reason: &str) -> RepoResult<u64>;
+}
+
+#[async_trait]
+impl SessionRepository for PgPool {
+    async fn create(
+        &self,
+        user_id: Uuid,
+        user_agent: Option<String>,
+        ip_address: Option<String>,
+        ttl: Duration,
+    ) -> RepoResult<(Session, String)> {
+        Ok(Session::create(self, user_id, user_agent, ip_address, ttl).await?)
+    }
+
+    async fn rotate(&self, refresh_token: &str) -> RepoResult<RefreshOutcome> {
+        Ok(Session::rotate(self, refresh_token).await?)
+    }
+
+    async fn active_role(&self, session_id: Uuid, user_id: Uuid) -> RepoResult<Option<Role>> {
+        Ok(Session::active_role(self, session_id, user_id).await?)
+    }
+
+    async fn list_active_for_user(&self, user_id: Uuid) -> RepoResult<Vec<Session>> {
+        Ok(Session::list_active_for_user(self, user_id).await?)
+    }
+
+    async fn revoke(&self, session_id: Uuid, user_id: Uuid, reason: &str) -> RepoResult<bool> {
+        Ok(Session::revoke(self, session_id, user_id, reason).await?)
+    }
+
+    async fn revoke_all_for_user(&self, user_id: Uuid, reason: &str) -> RepoResult<u64> {
+        Ok(Session::revoke_all_for_user(self, user_id, reason).await?)
+    }
+}
+
+pub(super) struct RefreshTokenRecord {
+    pub session_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+    pub used: bool,
+}
+
+fn revoke_session(session: &mut Session, reason: &str) -> bool {
+    if session.revoked_at.is_some() {
+        return false;
+    }
+
+    session.revoked_at = Some(Utc::now());
+    session.revoked_reason = Some(reason.to_string());
+    true
+}
+
+#[async_trait]
+impl SessionRepository for InMemoryStore {
+    async fn create(
+        &self,
+        user_id: Uuid,
+        user_agent: Option<String>,
+        ip_address: Option<String>,
+        ttl: Duration,
+    ) -> RepoResult<(Session, String)> {
+        let now = Utc::now();
+        let session = Session {
+            id: Uuid::new_v4(),
+            user_id,
+            user_agent,
+            ip_address,
+            created_at: now,
+            last_used_at: now,
+            expires_at: now + ttl,
+            revoked_at: None,
+            revoked_reason: None,
+        };
+
+        let refresh_token = generate_token(32);
+        let mut state = self.state();
+        state.refresh_tokens.insert(hash_token(&refresh_token), RefreshTokenRecord {
+            session_id: session.id,
+            expires_at: session.expires_at,
+            used: false,
+        });
+        state.sessions.insert(session.id, session.clone());
+
+        Ok((session, refresh_token))
+    }
+
+    async fn rotate(&self, refresh_token: &str) -> RepoResult<RefreshOutcome> {
+        let now = Utc::now();
+        let mut state = self.state();
+
+        let Some(token) = state.refresh_tokens.get_mut(&hash_token(refresh_token)) else {
+            return Ok(RefreshOutcome::Invalid);
+        };
+
+        let session_id = token.session_id;
+        if token.used {
+            if let Some(session) = state.sessions.get_mut(&session_id) {
+                revoke_session(session, "refresh_token_reuse");
+            }
+            tracing::warn!("Refresh token reuse detected, revoked session {}", session_id);
+            return Ok(RefreshOutcome::ReuseDetected);
+        }
+
+        let token_expired = token.expires_at <= now;
+        let session = match state.sessions.get_mut(&session_id) {
+            Some(s) if s.revoked_at.is_none() && s.expires_at > now && !token_expired => s,
+            _ => return Ok(RefreshOutcome::Invalid),
+        };
+
+        session.last_used_at = now;
+        let session = session.clone();
+
+        if let Some(token) = state.refresh_tokens.get_mut(&hash_token(refresh_token)) {
+            token.used = true;
+        }
+
+        let new_token = generate_token(32);
+        state.refresh_tokens.insert(hash_token(&new_token), RefreshTokenRecord {
+            session_id,
+            expires_at: session.expires_at,
+            used: false,
+        });
+
+        Ok(RefreshOutcome::Rotated { session, refresh_token: new_token })
+    }
+
+    async fn active_role(&self, session_id: Uuid, user_id: Uuid) -> RepoResult<Option<Role>> {
+        let state = self.state();
+        let now = Utc::now();
+
+        let active = state
+            .sessions
+            .get(&session_id)
+            .is_some_and(|s| s.user_id == user_id && s.revoked_at.is_none() && s.expires_at > now);
+
+        Ok(state
+            .users
+            .get(&user_id)
+            .filter(|record| active && record.user.suspended_at.is_none())
+            .map(|record| record.user.role))
+    }
+
+    async fn list_active_for_user(&self, user_id: Uuid) -> RepoResult<Vec<Session>> {
+        let now = Utc::now();
+        let mut sessions: Vec<Session> = self
+            .state()
+            .sessions
+            .values()
+            .filter(|s| s.user_id == user_id && s.revoked_at.is_none() && s.expires_at > now)
+            .cloned()
+            .collect();
+
+        sessions.sort_by_key(|x| Reverse(x.last_used_at));
+        Ok(sessions)
+    }
+
+    async fn revoke(&self, session_id: Uuid, user_id: Uuid, reason: &str) -> RepoResult<bool> {
+        Ok(self
+            .state()
+            .sessions
+            .get_mut(&session_id)
+            .filter(|s| s.user_id == user_id)
+            .is_some_and(|s| revoke_session(s, reason)))
+    }
+
+    async fn revoke_all_for_user(&self, user_id: Uuid, reason: &str) -> RepoResult<u64> {
+        Ok(self
+            .state()
+            .sessions
+            .values_mut()
+            .filter(|s| s.user_id == user_id)
+            .map(|s| revoke_session(s, reason))
+            .filter(|revoked| *revoked)
+            .count() as u64)
+    }
+}
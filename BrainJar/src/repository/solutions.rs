@@ -0,0 +1,428 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::solution::{
+    CreateSolution, ProblemSolution, SolutionResponse, SolutionSort, SolutionVoteTotals, SolvedProblemResponse,
+};
+
+use super::problems::visible_to;
+use super::{InMemoryStore, RepoResult};
+
+// Solutions users submit to problems, one per user per problem
+#[async_trait]
+pub trait SolutionRepository: Send + Sync {
+    // Conflict if the user already has a solution for the problem
+    async fn create(&self, problem_id: Uuid, user_id: Uuid, solution: &CreateSolution) -> RepoResult<ProblemSolution>;
+    async fn find_by_id(&self, id: Uuid) -> RepoResult<Option<ProblemSolution>>;
+    async fn list_for_problem(&self, problem_id: Uuid, sort: SolutionSort) -> RepoResult<Vec<SolutionResponse>>;
+    // The solutions on each of the problems, fetched together. Problems without any are left out.
+    async fn list_for_problems(
+        &self,
+        problem_ids: &[Uuid],
+        sort: SolutionSort,
+    ) -> RepoResult<HashMap<Uuid, Vec<SolutionResponse>>>;
+    // Every problem the user has solved and can still see, most recent first
+    async fn solved_by_user(&self, user_id: Uuid) -> RepoResult<Vec<SolvedProblemResponse>>;
+    // Only the author can edit or delete. None / false if the solution isn't theirs.
+    async fn update(&self, id: Uuid, user_id: Uuid, solution: &CreateSolution) -> RepoResult<Option<ProblemSolution>>;
+    async fn delete(&self, id: Uuid, user_id: Uuid) -> RepoResult<bool>;
+    // Record the user's vote (1 or -1), or clear it with 0
+    async fn vote(&self, id: Uuid, user_id: Uuid, value: i16) -> RepoResult<SolutionVoteTotals>;
+    // Make this the problem's only accepted solution and mark the problem solved
+    async fn accept(&self, problem_id: Uuid, id: Uuid) -> RepoResult<Option<ProblemSolution>>;
+    // Clear the problem's accepted solution, returning false if there was none
+    async fn unaccept(&self, problem_id: Uuid) -> RepoResult<bool>;
+}
+
+fn order_by(sort: SolutionSort) -> &'static str {
+    match sort {
+        SolutionSort::Top => "s.is_accepted DESC, score DESC, s.created_at DESC",
+        SolutionSort::Newest => "s.created_at DESC",
+        SolutionSort::Oldest => "s.created_at ASC",
+    }
+}
+
+// Solutions with their author and vote totals, for the problems matching `condition`
+fn solutions_query(condition: &str, sort: SolutionSort) -> String {
+    format!(
+        "SELECT s.id AS solution_id, s.problem_id, s.user_id, u.username AS user_name, u.avatar_url AS avatar,
+                s.solution_text, s.created_at, s.is_accepted,
+                COUNT(v.user_id) FILTER (WHERE v.value > 0) AS upvotes,
+                COUNT(v.user_id) FILTER (WHERE v.value < 0) AS downvotes,
+                COALESCE(SUM(v.value), 0) AS score
+         FROM problem_solutions s
+         JOIN users u ON s.user_id = u.id
+         LEFT JOIN solution_votes v ON v.solution_id = s.id
+         WHERE {}
+         GROUP BY s.id, u.id
+         ORDER BY {}",
+        condition,
+        order_by(sort)
+    )
+}
+
+#[derive(sqlx::FromRow)]
+struct ProblemSolutionRow {
+    problem_id: Uuid,
+    #[sqlx(flatten)]
+    solution: SolutionResponse,
+}
+
+#[async_trait]
+impl SolutionRepository for PgPool {
+    async fn create(&self, problem_id: Uuid, user_id: Uuid, solution: &CreateSolution) -> RepoResult<ProblemSolution> {
+        Ok(sqlx::query_as::<_, ProblemSolution>(
+            "INSERT INTO problem_solutions (problem_id, user_id, solution_text, metadata)
+             VALUES ($1, $2, $3, $4)
+             RETURNING *"
+        )
+        .bind(problem_id)
+        .bind(user_id)
+        .bind(&solution.solution_text)
+        .bind(&solution.metadata)
+        .fetch_one(self)
+        .await?)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> RepoResult<Option<ProblemSolution>> {
+        Ok(sqlx::query_as::<_, ProblemSolution>("SELECT * FROM problem_solutions WHERE id = $1")
+            .bind(id)
+            .fetch_optional(self)
+            .await?)
+    }
+
+    async fn list_for_problem(&self, problem_id: Uuid, sort: SolutionSort) -> RepoResult<Vec<SolutionResponse>> {
+        Ok(sqlx::query_as::<_, SolutionResponse>(&solutions_query("s.problem_id = $1", sort))
+            .bind(problem_id)
+            .fetch_all(self)
+            .await?)
+    }
+
+    async fn list_for_problems(
+        &self,
+        problem_ids: &[Uuid],
+        sort: SolutionSort,
+    ) -> RepoResult<HashMap<Uuid, Vec<SolutionResponse>>> {
+        let rows = sqlx::query_as::<_, ProblemSolutionRow>(&solutions_query("s.problem_id = ANY($1)", sort))
+            .bind(problem_ids)
+            .fetch_all(self)
+            .await?;
+
+        let mut by_problem: HashMap<Uuid, Vec<SolutionResponse>> = HashMap::new();
+        for row in rows {
+            by_problem.entry(row.problem_id).or_default().push(row.solution);
+        }
+        Ok(by_problem)
+    }
+
+    async fn solved_by_user(&self, user_id: Uuid) -> RepoResult<Vec<SolvedProblemResponse>> {
+        Ok(sqlx::query_as::<_, SolvedProblemResponse>(&format!(
+            "SELECT p.id AS problem_id, p.title, p.description, p.category,
+                    s.created_at AS solved_at, s.id AS solution_id, s.solution_text,
+                    p.user_id AS problem_creator_id, u.username AS problem_creator_username,
+                    u.avatar_url AS problem_creator_avatar
+             FROM problem_solutions s
+             JOIN problems p ON s.problem_id = p.id
+             JOIN users u ON p.user_id = u.id
+             WHERE s.user_id = $1 AND {}
+             ORDER BY s.created_at DESC",
+            visible_to("$1")
+        ))
+        .bind(user_id)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn update(&self, id: Uuid, user_id: Uuid, solution: &CreateSolution) -> RepoResult<Option<ProblemSolution>> {
+        Ok(sqlx::query_as::<_, ProblemSolution>(
+            "UPDATE problem_solutions
+             SET solution_text = $3, metadata = $4
+             WHERE id = $1 AND user_id = $2
+             RETURNING *"
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(&solution.solution_text)
+        .bind(&solution.metadata)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn delete(&self, id: Uuid, user_id: Uuid) -> RepoResult<bool> {
+        let result = sqlx::query("DELETE FROM problem_solutions WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(self)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn vote(&self, id: Uuid, user_id: Uuid, value: i16) -> RepoResult<SolutionVoteTotals> {
+        if value == 0 {
+            sqlx::query("DELETE FROM solution_votes WHERE solution_id = $1 AND user_id = $2")
+                .bind(id)
+                .bind(user_id)
+                .execute(self)
+                .await?;
+        } else {
+            sqlx::query(
+                "INSERT INTO solution_votes (solution_id, user_id, value)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (solution_id, user_id) DO UPDATE SET value = EXCLUDED.value, created_at = NOW()"
+            )
+            .bind(id)
+            .bind(user_id)
+            .bind(value)
+            .execute(self)
+            .await?;
+        }
+
+        Ok(sqlx::query_as::<_, SolutionVoteTotals>(
+            "SELECT $1::uuid AS solution_id,
+                    COUNT(*) FILTER (WHERE value > 0) AS upvotes,
+                    COUNT(*) FILTER (WHERE value < 0) AS downvotes,
+                    COALESCE(SUM(value), 0) AS score
+             FROM solution_votes
+             WHERE solution_id = $1"
+        )
+        .bind(id)
+        .fetch_one(self)
+        .await?)
+    }
+
+    async fn accept(&self, problem_id: Uuid, id: Uuid) -> RepoResult<Option<ProblemSolution>> {
+        let mut tx = self.begin().await?;
+
+        // Clear first, the partial unique index allows only one accepted row at a time
+        sqlx::query("UPDATE problem_solutions SET is_accepted = FALSE WHERE problem_id = $1 AND is_accepted")
+            .bind(problem_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let solution = sqlx::query_as::<_, ProblemSolution>(
+            "UPDATE problem_solutions SET is_accepted = TRUE
+             WHERE id = $1 AND problem_id = $2
+             RETURNING *"
+        )
+        .bind(id)
+        .bind(problem_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(solution) = solution else {
+            return Ok(None);
+        };
+
+        sqlx::query("UPDATE problems SET solved = TRUE WHERE id = $1")
+            .bind(problem_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(Some(solution))
+    }
+
+    async fn unaccept(&self, problem_id: Uuid) -> RepoResult<bool> {
+        let result = sqlx::query(
+            "UPDATE problem_solutions SET is_accepted = FALSE WHERE problem_id = $1 AND is_accepted"
+        )
+        .bind(problem_id)
+        .execute(self)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+fn vote_totals(votes: &HashMap<(Uuid, Uuid), i16>, solution_id: Uuid) -> SolutionVoteTotals {
+    let values: Vec<i16> = votes
+        .iter()
+        .filter(|((id, _), _)| *id == solution_id)
+        .map(|(_, value)| *value)
+        .collect();
+
+    SolutionVoteTotals {
+        solution_id,
+        upvotes: values.iter().filter(|v| **v > 0).count() as i64,
+        downvotes: values.iter().filter(|v| **v < 0).count() as i64,
+        score: values.iter().map(|v| *v as i64).sum(),
+    }
+}
+
+#[async_trait]
+impl SolutionRepository for InMemoryStore {
+    async fn create(&self, problem_id: Uuid, user_id: Uuid, solution: &CreateSolution) -> RepoResult<ProblemSolution> {
+        let mut state = self.state();
+        if !state.problems.iter().any(|p| p.id == problem_id) {
+            return Err(AppError::NotFound("Problem not found".into()));
+        }
+
+        if state.solutions.iter().any(|s| s.problem_id == problem_id && s.user_id == user_id) {
+            return Err(AppError::Conflict("You have already submitted a solution to this problem".into()));
+        }
+
+        let solution = ProblemSolution {
+            id: Uuid::new_v4(),
+            problem_id,
+            user_id,
+            solution_text: solution.solution_text.clone(),
+            created_at: Utc::now(),
+            metadata: solution.metadata.clone(),
+            is_accepted: false,
+        };
+
+        state.solutions.push(solution.clone());
+        Ok(solution)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> RepoResult<Option<ProblemSolution>> {
+        Ok(self.state().solutions.iter().find(|s| s.id == id).cloned())
+    }
+
+    async fn list_for_problem(&self, problem_id: Uuid, sort: SolutionSort) -> RepoResult<Vec<SolutionResponse>> {
+        Ok(self.list_for_problems(&[problem_id], sort).await?.remove(&problem_id).unwrap_or_default())
+    }
+
+    async fn list_for_problems(
+        &self,
+        problem_ids: &[Uuid],
+        sort: SolutionSort,
+    ) -> RepoResult<HashMap<Uuid, Vec<SolutionResponse>>> {
+        let state = self.state();
+        let mut by_problem: HashMap<Uuid, Vec<SolutionResponse>> = HashMap::new();
+        for s in state.solutions.iter().filter(|s| problem_ids.contains(&s.problem_id)) {
+            let Some(user_name) = state.username(s.user_id) else {
+                continue;
+            };
+            let totals = vote_totals(&state.solution_votes, s.id);
+            by_problem.entry(s.problem_id).or_default().push(SolutionResponse {
+                solution_id: s.id,
+                user_id: s.user_id,
+                user_name,
+                avatar: None,
+                solution_text: s.solution_text.clone(),
+                created_at: s.created_at,
+                is_accepted: s.is_accepted,
+                upvotes: totals.upvotes,
+                downvotes: totals.downvotes,
+                score: totals.score,
+            });
+        }
+
+        for solutions in by_problem.values_mut() {
+            match sort {
+                SolutionSort::Top => solutions.sort_by_key(|s| Reverse((s.is_accepted, s.score, s.created_at))),
+                SolutionSort::Newest => solutions.sort_by_key(|s| Reverse(s.created_at)),
+                SolutionSort::Oldest => solutions.sort_by_key(|s| s.created_at),
+            }
+        }
+        Ok(by_problem)
+    }
+
+    async fn solved_by_user(&self, user_id: Uuid) -> RepoResult<Vec<SolvedProblemResponse>> {
+        let state = self.state();
+        let mut solved: Vec<SolvedProblemResponse> = state
+            .solutions
+            .iter()
+            .filter(|s| s.user_id == user_id)
+            .filter_map(|s| {
+                let problem = state.problems.iter().find(|p| p.id == s.problem_id && state.can_view(user_id, p))?;
+                Some(SolvedProblemResponse {
+                    problem_id: problem.id,
+                    title: problem.title.clone(),
+                    description: problem.description.clone(),
+                    category: problem.category.clone(),
+                    solved_at: s.created_at,
+                    solution_id: s.id,
+                    solution_text: s.solution_text.clone(),
+                    problem_creator_id: problem.user_id,
+                    problem_creator_username: state.username(problem.user_id)?,
+                    problem_creator_avatar: None,
+                })
+            })
+            .collect();
+
+        solved.sort_by_key(|s| Reverse(s.solved_at));
+        Ok(solved)
+    }
+
+    async fn update(&self, id: Uuid, user_id: Uuid, solution: &CreateSolution) -> RepoResult<Option<ProblemSolution>> {
+        Ok(self
+            .state()
+            .solutions
+            .iter_mut()
+            .find(|s| s.id == id && s.user_id == user_id)
+            .map(|existing| {
+                existing.solution_text = solution.solution_text.clone();
+                existing.metadata = solution.metadata.clone();
+                existing.clone()
+            }))
+    }
+
+    async fn delete(&self, id: Uuid, user_id: Uuid) -> RepoResult<bool> {
+        let mut state = self.state();
+        let before = state.solutions.len();
+        state.solutions.retain(|s| !(s.id == id && s.user_id == user_id));
+
+        let deleted = state.solutions.len() < before;
+        if deleted {
+            state.solution_votes.retain(|(solution_id, _), _| *solution_id != id);
+        }
+        Ok(deleted)
+    }
+
+    async fn vote(&self, id: Uuid, user_id: Uuid, value: i16) -> RepoResult<SolutionVoteTotals> {
+        let mut state = self.state();
+        if !state.solutions.iter().any(|s| s.id == id) {
+            return Err(AppError::NotFound("Solution not found".into()));
+        }
+
+        if value == 0 {
+            state.solution_votes.remove(&(id, user_id));
+        } else {
+            state.solution_votes.insert((id, user_id), value);
+        }
+
+        Ok(vote_totals(&state.solution_votes, id))
+    }
+
+    async fn accept(&self, problem_id: Uuid, id: Uuid) -> RepoResult<Option<ProblemSolution>> {
+        let mut state = self.state();
+        if !state.solutions.iter().any(|s| s.id == id && s.problem_id == problem_id) {
+            return Ok(None);
+        }
+
+        let mut accepted = None;
+        for solution in state.solutions.iter_mut().filter(|s| s.problem_id == problem_id) {
+            solution.is_accepted = solution.id == id;
+            if solution.is_accepted {
+                accepted = Some(solution.clone());
+            }
+        }
+
+        if let Some(problem) = state.problems.iter_mut().find(|p| p.id == problem_id) {
+            problem.solved = true;
+            problem.updated_at = Utc::now();
+        }
+
+        Ok(accepted)
+    }
+
+    async fn unaccept(&self, problem_id: Uuid) -> RepoResult<bool> {
+        let mut cleared = false;
+        for solution in self.state().solutions.iter_mut() {
+            if solution.problem_id == problem_id && solution.is_accepted {
+                solution.is_accepted = false;
+                cleared = true;
+            }
+        }
+        Ok(cleared)
+    }
+}
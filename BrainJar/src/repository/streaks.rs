@@ -0,0 +1,299 @@
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::StreakConfig;
+use crate::models::activity::{ActivityKind, DailyActivity};
+use crate::models::streak::{streak_day, DerivedStreak, FreezeKind, Streak, StreakFreezeEvent, StreakLeaderboardEntry};
+
+use super::memory::State;
+use super::{InMemoryStore, RepoResult};
+
+// Daily solving streaks
+#[async_trait]
+pub trait StreakRepository: Send + Sync {
+    async fn find_for_user(&self, user_id: Uuid) -> RepoResult<Option<Streak>>;
+    async fn create(&self, user_id: Uuid) -> RepoResult<Streak>;
+    // The only way a streak grows: the first time one of the user's solutions to someone
+    // else's `problem_id` is accepted, it's counted in the activity ledger and advances (or
+    // restarts) their streak, with days in their own timezone. None if it was counted before.
+    async fn record_solve(&self, user_id: Uuid, problem_id: Uuid, rules: &StreakConfig) -> RepoResult<Option<Streak>>;
+    // Credit purchased freezes. None if the user has no streak yet.
+    async fn grant_freezes(&self, user_id: Uuid, count: i32) -> RepoResult<Option<Streak>>;
+    // Freezes spent on missed days, newest first
+    async fn freezes_used(&self, user_id: Uuid, limit: i64) -> RepoResult<Vec<StreakFreezeEvent>>;
+    // Every stored streak, oldest first
+    async fn all(&self) -> RepoResult<Vec<Streak>>;
+    // The user's streak rebuilt from the activity ledger and the freezes used
+    async fn derive(&self, user_id: Uuid) -> RepoResult<DerivedStreak>;
+    // Overwrite the stored counts with derived ones. None if the user has no streak.
+    async fn apply_derived(&self, user_id: Uuid, derived: &DerivedStreak) -> RepoResult<Option<Streak>>;
+    // Live streaks as of now in each user's timezone; lapsed streaks count as 0
+    async fn leaderboard(&self, limit: i64, rules: &StreakConfig) -> RepoResult<Vec<StreakLeaderboardEntry>>;
+}
+
+#[async_trait]
+impl StreakRepository for PgPool {
+    async fn find_for_user(&self, user_id: Uuid) -> RepoResult<Option<Streak>> {
+        Ok(Streak::get_by_user_id(self, user_id).await?)
+    }
+
+    async fn create(&self, user_id: Uuid) -> RepoResult<Streak> {
+        Ok(Streak::create_new(self, user_id).await?)
+    }
+
+    async fn record_solve(&self, user_id: Uuid, problem_id: Uuid, rules: &StreakConfig) -> RepoResult<Option<Streak>> {
+        Ok(Streak::update_for_problem_solve(self, user_id, problem_id, rules).await?)
+    }
+
+    async fn grant_freezes(&self, user_id: Uuid, count: i32) -> RepoResult<Option<Streak>> {
+        let mut tx = self.begin().await?;
+        let streak = sqlx::query_as::<_, Streak>(
+            "UPDATE streaks
+             SET freezes_available = freezes_available + $1
+             WHERE user_id = $2
+             RETURNING *"
+        )
+        .bind(count)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if streak.is_some() {
+            for _ in 0..count {
+                Streak::record_freeze_event(&mut tx, user_id, FreezeKind::Purchased, None).await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(streak)
+    }
+
+    async fn freezes_used(&self, user_id: Uuid, limit: i64) -> RepoResult<Vec<StreakFreezeEvent>> {
+        Ok(sqlx::query_as::<_, StreakFreezeEvent>(
+            "SELECT * FROM streak_freeze_events
+             WHERE user_id = $1 AND kind = 'used'
+             ORDER BY day DESC, created_at DESC
+             LIMIT $2"
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn all(&self) -> RepoResult<Vec<Streak>> {
+        Ok(sqlx::query_as::<_, Streak>("SELECT * FROM streaks ORDER BY created_at")
+            .fetch_all(self)
+            .await?)
+    }
+
+    async fn derive(&self, user_id: Uuid) -> RepoResult<DerivedStreak> {
+        let solve_days = sqlx::query_as::<_, DailyActivity>(
+            "SELECT day, problems_created, problems_solved, solutions_posted, feedback_given
+             FROM daily_activity
+             WHERE user_id = $1 AND problems_solved > 0
+             ORDER BY day"
+        )
+        .bind(user_id)
+        .fetch_all(self)
+        .await?;
+        let frozen_days = sqlx::query_scalar::<_, NaiveDate>(
+            "SELECT day FROM streak_freeze_events
+             WHERE user_id = $1 AND kind = 'used' AND day IS NOT NULL
+             ORDER BY day"
+        )
+        .bind(user_id)
+        .fetch_all(self)
+        .await?;
+
+        Ok(Streak::derive(&solve_days, &frozen_days))
+    }
+
+    async fn apply_derived(&self, user_id: Uuid, derived: &DerivedStreak) -> RepoResult<Option<Streak>> {
+        Ok(sqlx::query_as::<_, Streak>(
+            "UPDATE streaks
+             SET count = $2, longest_streak = $3, problems_solved_today = $4
+             WHERE user_id = $1
+             RETURNING *"
+        )
+        .bind(user_id)
+        .bind(derived.count)
+        .bind(derived.longest_streak)
+        .bind(derived.problems_solved_today)
+        .fetch_optional(self)
+        .await?)
+    }
+
+    async fn leaderboard(&self, limit: i64, rules: &StreakConfig) -> RepoResult<Vec<StreakLeaderboardEntry>> {
+        // Streak days as in streak_day(): local dates, shifted back by the grace hours
+        Ok(sqlx::query_as::<_, StreakLeaderboardEntry>(
+            "WITH local AS (
+                SELECT u.username, s.count, s.longest_streak, s.problems_solved_today, s.freezes_available,
+                       DATE((s.last_active - make_interval(hours => $2)) AT TIME ZONE u.timezone) AS last_day,
+                       DATE((NOW() - make_interval(hours => $2)) AT TIME ZONE u.timezone) AS today
+                FROM streaks s
+                JOIN users u ON s.user_id = u.id
+             )
+             SELECT username,
+                    CASE WHEN today <= last_day + 1 + freezes_available THEN count ELSE 0 END AS current_streak,
+                    COALESCE(longest_streak, 1) AS longest_streak,
+                    CASE WHEN last_day = today THEN COALESCE(problems_solved_today, 0) ELSE 0 END
+                        AS problems_solved_today
+             FROM local
+             ORDER BY current_streak DESC, longest_streak DESC
+             LIMIT $1"
+        )
+        .bind(limit)
+        .bind(rules.grace_hours as i32)
+        .fetch_all(self)
+        .await?)
+    }
+}
+
+#[async_trait]
+impl StreakRepository for InMemoryStore {
+    async fn find_for_user(&self, user_id: Uuid) -> RepoResult<Option<Streak>> {
+        Ok(self.state().streaks.get(&user_id).cloned())
+    }
+
+    async fn create(&self, user_id: Uuid) -> RepoResult<Streak> {
+        let now = Utc::now();
+        let streak = Streak {
+            id: Uuid::new_v4(),
+            user_id,
+            count: 0,
+            last_active: now,
+            created_at: now,
+            longest_streak: 0,
+            problems_solved_today: 0,
+            freezes_available: 0,
+        };
+
+        self.state().streaks.insert(user_id, streak.clone());
+        Ok(streak)
+    }
+
+    async fn record_solve(&self, user_id: Uuid, problem_id: Uuid, rules: &StreakConfig) -> RepoResult<Option<Streak>> {
+        let now = Utc::now();
+        let mut state = self.state();
+        if !state.problem_solves.insert((user_id, problem_id)) {
+            return Ok(None);
+        }
+        let tz = state.timezone(user_id);
+        state.add_activity(user_id, streak_day(now, tz, rules), ActivityKind::ProblemSolved, 1);
+        let outcome = Streak::after_solve(state.streaks.get(&user_id), now, tz, rules);
+
+        for day in &outcome.frozen_days {
+            state.record_freeze_event(user_id, FreezeKind::Used, Some(*day));
+        }
+        if outcome.earned_freeze {
+            state.record_freeze_event(user_id, FreezeKind::Earned, None);
+        }
+
+        let streak = state.streaks.entry(user_id).or_insert_with(|| Streak {
+            id: Uuid::new_v4(),
+            user_id,
+            count: outcome.count,
+            last_active: now,
+            created_at: now,
+            longest_streak: outcome.longest_streak,
+            problems_solved_today: outcome.problems_solved_today,
+            freezes_available: outcome.freezes_available,
+        });
+
+        streak.count = outcome.count;
+        streak.last_active = now;
+        streak.longest_streak = outcome.longest_streak;
+        streak.problems_solved_today = outcome.problems_solved_today;
+        streak.freezes_available = outcome.freezes_available;
+        Ok(Some(streak.clone()))
+    }
+
+    async fn grant_freezes(&self, user_id: Uuid, count: i32) -> RepoResult<Option<Streak>> {
+        let mut state = self.state();
+        let Some(streak) = state.streaks.get_mut(&user_id) else {
+            return Ok(None);
+        };
+        streak.freezes_available += count;
+        let streak = streak.clone();
+
+        for _ in 0..count {
+            state.record_freeze_event(user_id, FreezeKind::Purchased, None);
+        }
+        Ok(Some(streak))
+    }
+
+    async fn freezes_used(&self, user_id: Uuid, limit: i64) -> RepoResult<Vec<StreakFreezeEvent>> {
+        let state = self.state();
+        let mut used: Vec<StreakFreezeEvent> = state
+            .streak_freezes
+            .iter()
+            .filter(|e| e.user_id == user_id && e.kind == FreezeKind::Used)
+            .cloned()
+            .collect();
+
+        used.sort_by(|a, b| b.day.cmp(&a.day).then_with(|| b.created_at.cmp(&a.created_at)));
+        used.truncate(limit.max(0) as usize);
+        Ok(used)
+    }
+
+    async fn all(&self) -> RepoResult<Vec<Streak>> {
+        let mut streaks: Vec<Streak> = self.state().streaks.values().cloned().collect();
+        streaks.sort_by_key(|s| s.created_at);
+        Ok(streaks)
+    }
+
+    async fn derive(&self, user_id: Uuid) -> RepoResult<DerivedStreak> {
+        let state = self.state();
+        let mut frozen_days: Vec<NaiveDate> = state
+            .streak_freezes
+            .iter()
+            .filter(|e| e.user_id == user_id && e.kind == FreezeKind::Used)
+            .filter_map(|e| e.day)
+            .collect();
+        frozen_days.sort_unstable();
+
+        Ok(Streak::derive(&state.solve_days(user_id, None), &frozen_days))
+    }
+
+    async fn apply_derived(&self, user_id: Uuid, derived: &DerivedStreak) -> RepoResult<Option<Streak>> {
+        Ok(self.state().streaks.get_mut(&user_id).map(|streak| {
+            streak.count = derived.count;
+            streak.longest_streak = derived.longest_streak;
+            streak.problems_solved_today = derived.problems_solved_today;
+            streak.clone()
+        }))
+    }
+
+    async fn leaderboard(&self, limit: i64, rules: &StreakConfig) -> RepoResult<Vec<StreakLeaderboardEntry>> {
+        let now = Utc::now();
+        let state = self.state();
+        let mut entries: Vec<StreakLeaderboardEntry> = state
+            .streaks
+            .values()
+            .filter_map(|s| {
+                let (current_streak, problems_solved_today) = s.current(now, state.timezone(s.user_id), rules);
+                Some(StreakLeaderboardEntry {
+                    username: state.username(s.user_id)?,
+                    current_streak,
+                    longest_streak: s.longest_streak,
+                    problems_solved_today,
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.current_streak.cmp(&a.current_streak).then_with(|| b.longest_streak.cmp(&a.longest_streak))
+        });
+        entries.truncate(limit.max(0) as usize);
+
+        Ok(entries)
+    }
+}
+
+impl State {
+    fn record_freeze_event(&mut self, user_id: Uuid, kind: FreezeKind, day: Option<NaiveDate>) {
+        self.streak_freezes.push(StreakFreezeEvent { id: Uuid::new_v4(), user_id, kind, day, created_at: Utc::now() });
+    }
+}
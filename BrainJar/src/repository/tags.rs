@@ -0,0 +1,364 @@
+use std::cmp::Reverse;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::tag::{Tag, TagAlias, TagUsage};
+
+use super::memory::State;
+use super::problems::visible_to;
+use super::{InMemoryStore, RepoResult};
+
+// The tag vocabulary. problem_tags links problems to tags; problems.tags keeps a sorted copy
+// of the names, rewritten here whenever the links change.
+#[async_trait]
+pub trait TagRepository: Send + Sync {
+    // Tags whose name or an alias starts with `prefix` (already normalized), most used
+    // first. Only problems `viewer` may see count, and tags nobody can see are left out.
+    async fn search(&self, prefix: Option<&str>, viewer: Uuid, limit: i64) -> RepoResult<Vec<TagUsage>>;
+    async fn find(&self, id: Uuid) -> RepoResult<Option<Tag>>;
+    // Replace a problem's tags with `names` (normalized), creating tags as needed and
+    // following aliases. Returns the canonical names, sorted.
+    async fn set_problem_tags(&self, problem_id: Uuid, names: &[String]) -> RepoResult<Vec<String>>;
+    // Move every use of `source` to `target`, delete `source` and keep its name as an alias
+    async fn merge(&self, source: Uuid, target: Uuid) -> RepoResult<Tag>;
+    async fn add_alias(&self, tag_id: Uuid, alias: &str) -> RepoResult<TagAlias>;
+    async fn remove_alias(&self, alias: &str) -> RepoResult<bool>;
+}
+
+// Rewrite the problems.tags copy of the given problems from problem_tags
+async fn sync_tag_names(tx: &mut Transaction<'_, Postgres>, problem_ids: &[Uuid]) -> RepoResult<()> {
+    sqlx::query(
+        "UPDATE problems p
+         SET tags = (
+             SELECT COALESCE(ARRAY_AGG(t.name ORDER BY t.name), '{}')
+             FROM problem_tags pt JOIN tags t ON t.id = pt.tag_id
+             WHERE pt.problem_id = p.id
+         )
+         WHERE p.id = ANY($1)"
+    )
+    .bind(problem_ids)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+// The body of set_problem_tags, for callers that already hold a transaction
+pub(super) async fn link_tags(
+    tx: &mut Transaction<'_, Postgres>,
+    problem_id: Uuid,
+    names: &[String],
+) -> RepoResult<Vec<String>> {
+    sqlx::query(
+        "INSERT INTO tags (name)
+         SELECT n FROM UNNEST($1::text[]) AS n
+         WHERE NOT EXISTS (SELECT 1 FROM tag_aliases a WHERE a.alias = n)
+         ON CONFLICT (name) DO NOTHING"
+    )
+    .bind(names)
+    .execute(&mut **tx)
+    .await?;
+
+    let tags = sqlx::query_as::<_, Tag>(
+        "SELECT DISTINCT t.* FROM UNNEST($1::text[]) AS n
+         LEFT JOIN tag_aliases a ON a.alias = n
+         JOIN tags t ON t.id = COALESCE(a.tag_id, (SELECT id FROM tags WHERE name = n))
+         ORDER BY t.name"
+    )
+    .bind(names)
+    .fetch_all(&mut **tx)
+    .await?;
+    let tag_ids: Vec<Uuid> = tags.iter().map(|t| t.id).collect();
+
+    sqlx::query("DELETE FROM problem_tags WHERE problem_id = $1")
+        .bind(problem_id)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query("INSERT INTO problem_tags (problem_id, tag_id) SELECT $1, UNNEST($2::uuid[])")
+        .bind(problem_id)
+        .bind(&tag_ids)
+        .execute(&mut **tx)
+        .await?;
+
+    sync_tag_names(tx, &[problem_id]).await?;
+    Ok(tags.into_iter().map(|t| t.name).collect())
+}
+
+async fn lock_tag(tx: &mut Transaction<'_, Postgres>, id: Uuid) -> RepoResult<Tag> {
+    sqlx::query_as::<_, Tag>("SELECT * FROM tags WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Tag not found".into()))
+}
+
+#[async_trait]
+impl TagRepository for PgPool {
+    async fn search(&self, prefix: Option<&str>, viewer: Uuid, limit: i64) -> RepoResult<Vec<TagUsage>> {
+        Ok(sqlx::query_as::<_, TagUsage>(&format!(
+            "SELECT t.id, t.name, COUNT(p.id) AS usage_count
+             FROM tags t
+             JOIN problem_tags pt ON pt.tag_id = t.id
+             JOIN problems p ON p.id = pt.problem_id AND {}
+             WHERE $1::text IS NULL
+                OR t.name LIKE $1 || '%'
+                OR EXISTS (SELECT 1 FROM tag_aliases a WHERE a.tag_id = t.id AND a.alias LIKE $1 || '%')
+             GROUP BY t.id
+             ORDER BY usage_count DESC, t.name
+             LIMIT $3",
+            visible_to("$2")
+        ))
+        .bind(prefix)
+        .bind(viewer)
+        .bind(limit)
+        .fetch_all(self)
+        .await?)
+    }
+
+    async fn find(&self, id: Uuid) -> RepoResult<Option<Tag>> {
+        Ok(sqlx::query_as::<_, Tag>("SELECT * FROM tags WHERE id = $1")
+            .bind(id)
+            .fetch_optional(self)
+            .await?)
+    }
+
+    async fn set_problem_tags(&self, problem_id: Uuid, names: &[String]) -> RepoResult<Vec<String>> {
+        let mut tx = self.begin().await?;
+
+        sqlx::query("SELECT id FROM problems WHERE id = $1 FOR UPDATE")
+            .bind(problem_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Problem not found".into()))?;
+
+        let names = link_tags(&mut tx, problem_id, names).await?;
+        tx.commit().await?;
+
+        Ok(names)
+    }
+
+    async fn merge(&self, source: Uuid, target: Uuid) -> RepoResult<Tag> {
+        let mut tx = self.begin().await?;
+        let source = lock_tag(&mut tx, source).await?;
+        let target = lock_tag(&mut tx, target).await?;
+
+        let affected: Vec<Uuid> = sqlx::query_scalar("SELECT problem_id FROM problem_tags WHERE tag_id = $1")
+            .bind(source.id)
+            .fetch_all(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            "INSERT INTO problem_tags (problem_id, tag_id)
+             SELECT problem_id, $2 FROM problem_tags WHERE tag_id = $1
+             ON CONFLICT DO NOTHING"
+        )
+        .bind(source.id)
+        .bind(target.id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE tag_aliases SET tag_id = $2 WHERE tag_id = $1")
+            .bind(source.id)
+            .bind(target.id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO tag_aliases (alias, tag_id) VALUES ($1, $2)
+             ON CONFLICT (alias) DO UPDATE SET tag_id = EXCLUDED.tag_id"
+        )
+        .bind(&source.name)
+        .bind(target.id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM tags WHERE id = $1")
+            .bind(source.id)
+            .execute(&mut *tx)
+            .await?;
+
+        sync_tag_names(&mut tx, &affected).await?;
+        tx.commit().await?;
+
+        Ok(target)
+    }
+
+    async fn add_alias(&self, tag_id: Uuid, alias: &str) -> RepoResult<TagAlias> {
+        if self.find(tag_id).await?.is_none() {
+            return Err(AppError::NotFound("Tag not found".into()));
+        }
+
+        let taken: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM tags WHERE name = $1)
+                 OR EXISTS (SELECT 1 FROM tag_aliases WHERE alias = $1)"
+        )
+        .bind(alias)
+        .fetch_one(self)
+        .await?;
+        if taken {
+            return Err(AppError::Conflict(format!("'{}' is already a tag or alias", alias)));
+        }
+
+        Ok(sqlx::query_as::<_, TagAlias>("INSERT INTO tag_aliases (alias, tag_id) VALUES ($1, $2) RETURNING *")
+            .bind(alias)
+            .bind(tag_id)
+            .fetch_one(self)
+            .await?)
+    }
+
+    async fn remove_alias(&self, alias: &str) -> RepoResult<bool> {
+        let result = sqlx::query("DELETE FROM tag_aliases WHERE alias = $1")
+            .bind(alias)
+            .execute(self)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+impl State {
+    // The tag `name` stands for, following aliases, created if it doesn't exist
+    fn resolve_tag(&mut self, name: &str) -> Tag {
+        let id = self.tag_aliases.iter().find(|a| a.alias == name).map(|a| a.tag_id);
+        let existing = self
+            .tags
+            .iter()
+            .find(|t| id.map_or(t.name == name, |id| t.id == id))
+            .cloned();
+
+        existing.unwrap_or_else(|| {
+            let tag = Tag { id: Uuid::new_v4(), name: name.to_string(), created_at: Utc::now() };
+            self.tags.push(tag.clone());
+            tag
+        })
+    }
+
+    // What `name` means as a tag: the tag an alias points to, otherwise the name itself
+    pub(super) fn tag_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.tag_aliases
+            .iter()
+            .find(|a| a.alias == name)
+            .and_then(|a| self.tags.iter().find(|t| t.id == a.tag_id))
+            .map_or(name, |t| t.name.as_str())
+    }
+
+    // The in-memory link_tags
+    pub(super) fn tag_problem(&mut self, problem_id: Uuid, names: &[String]) -> Vec<String> {
+        let mut canonical: Vec<String> = names.iter().map(|name| self.resolve_tag(name).name).collect();
+        canonical.sort();
+        canonical.dedup();
+
+        if let Some(problem) = self.problems.iter_mut().find(|p| p.id == problem_id) {
+            problem.tags = Some(canonical.clone());
+            problem.updated_at = Utc::now();
+        }
+        canonical
+    }
+}
+
+// In memory the problems' tag names are the links themselves
+#[async_trait]
+impl TagRepository for InMemoryStore {
+    async fn search(&self, prefix: Option<&str>, viewer: Uuid, limit: i64) -> RepoResult<Vec<TagUsage>> {
+        let state = self.state();
+        let visible: Vec<&Vec<String>> = state
+            .problems
+            .iter()
+            .filter(|p| state.can_view(viewer, p))
+            .filter_map(|p| p.tags.as_ref())
+            .collect();
+
+        let mut tags: Vec<TagUsage> = state
+            .tags
+            .iter()
+            .filter(|t| {
+                prefix.is_none_or(|prefix| {
+                    t.name.starts_with(prefix)
+                        || state.tag_aliases.iter().any(|a| a.tag_id == t.id && a.alias.starts_with(prefix))
+                })
+            })
+            .map(|t| TagUsage {
+                id: t.id,
+                name: t.name.clone(),
+                usage_count: visible.iter().filter(|tags| tags.contains(&t.name)).count() as i64,
+            })
+            .filter(|t| t.usage_count > 0)
+            .collect();
+
+        tags.sort_by(|a, b| a.name.cmp(&b.name));
+        tags.sort_by_key(|t| Reverse(t.usage_count));
+        tags.truncate(limit as usize);
+        Ok(tags)
+    }
+
+    async fn find(&self, id: Uuid) -> RepoResult<Option<Tag>> {
+        Ok(self.state().tags.iter().find(|t| t.id == id).cloned())
+    }
+
+    async fn set_problem_tags(&self, problem_id: Uuid, names: &[String]) -> RepoResult<Vec<String>> {
+        let mut state = self.state();
+        if !state.problems.iter().any(|p| p.id == problem_id) {
+            return Err(AppError::NotFound("Problem not found".into()));
+        }
+
+        Ok(state.tag_problem(problem_id, names))
+    }
+
+    async fn merge(&self, source: Uuid, target: Uuid) -> RepoResult<Tag> {
+        let mut state = self.state();
+        let find = |id: Uuid| {
+            state
+                .tags
+                .iter()
+                .find(|t| t.id == id)
+                .cloned()
+                .ok_or_else(|| AppError::NotFound("Tag not found".into()))
+        };
+        let (source, target) = (find(source)?, find(target)?);
+
+        let now = Utc::now();
+        for problem in state.problems.iter_mut() {
+            let Some(tags) = problem.tags.as_mut().filter(|tags| tags.contains(&source.name)) else {
+                continue;
+            };
+            tags.retain(|t| *t != source.name);
+            tags.push(target.name.clone());
+            tags.sort();
+            tags.dedup();
+            problem.updated_at = now;
+        }
+
+        state.tag_aliases.retain(|a| a.alias != source.name);
+        for alias in state.tag_aliases.iter_mut().filter(|a| a.tag_id == source.id) {
+            alias.tag_id = target.id;
+        }
+        state.tag_aliases.push(TagAlias { alias: source.name.clone(), tag_id: target.id, created_at: now });
+        state.tags.retain(|t| t.id != source.id);
+
+        Ok(target)
+    }
+
+    async fn add_alias(&self, tag_id: Uuid, alias: &str) -> RepoResult<TagAlias> {
+        let mut state = self.state();
+        if !state.tags.iter().any(|t| t.id == tag_id) {
+            return Err(AppError::NotFound("Tag not found".into()));
+        }
+        if state.tags.iter().any(|t| t.name == alias) || state.tag_aliases.iter().any(|a| a.alias == alias) {
+            return Err(AppError::Conflict(format!("'{}' is already a tag or alias", alias)));
+        }
+
+        let alias = TagAlias { alias: alias.to_string(), tag_id, created_at: Utc::now() };
+        state.tag_aliases.push(alias.clone());
+        Ok(alias)
+    }
+
+    async fn remove_alias(&self, alias: &str) -> RepoResult<bool> {
+        let mut state = self.state();
+        let before = state.tag_aliases.len();
+        state.tag_aliases.retain(|a| a.alias != alias);
+        Ok(state.tag_aliases.len() < before)
+    }
+}
@@ -0,0 +1,365 @@
+use std::cmp::Reverse;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::crypto::{generate_token, hash_token};
+use crate::error::AppError;
+use crate::models::user::{lockout_duration, Role, User};
+use crate::models::user_token::{TokenPurpose, UserToken};
+
+use super::{InMemoryStore, RepoResult};
+
+// Admin user listing; `search` matches username or email case-insensitively
+pub struct UserFilter {
+    pub search: Option<String>,
+    pub role: Option<Role>,
+    pub suspended: Option<bool>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+// Accounts, plus the one-time emailed tokens (verification, password reset) that belong to them
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn create(&self, username: &str, email: &str, password_hash: &str) -> RepoResult<User>;
+    async fn find_by_id(&self, id: Uuid) -> RepoResult<Option<User>>;
+    async fn find_by_email(&self, email: &str) -> RepoResult<Option<User>>;
+    async fn email_or_username_taken(&self, email: &str, username: &str) -> RepoResult<bool>;
+    async fn list(&self, filter: &UserFilter) -> RepoResult<Vec<User>>;
+
+    // Count a failed login and lock the account once the threshold is reached
+    async fn record_failed_login(&self, id: Uuid) -> RepoResult<()>;
+    async fn clear_failed_logins(&self, id: Uuid) -> RepoResult<()>;
+    async fn set_password(&self, id: Uuid, password_hash: &str) -> RepoResult<()>;
+    // Returns when the email was verified (the earlier time if it already was)
+    async fn mark_verified(&self, id: Uuid) -> RepoResult<Option<DateTime<Utc>>>;
+    // `timezone` must already be a valid IANA name
+    async fn set_timezone(&self, id: Uuid, timezone: &str) -> RepoResult<Option<User>>;
+
+    async fn set_role(&self, id: Uuid, role: Role) -> RepoResult<Option<User>>;
+    async fn suspend(&self, id: Uuid, reason: Option<&str>) -> RepoResult<Option<User>>;
+    async fn unsuspend(&self, id: Uuid) -> RepoResult<Option<User>>;
+
+    // Issue a new emailed token, invalidating any earlier unused token for the same purpose
+    async fn issue_token(&self, user_id: Uuid, purpose: TokenPurpose, ttl: Duration) -> RepoResult<String>;
+    // Mark a token as used and return its owner, or None if it is unknown, expired or already used
+    async fn consume_token(&self, token: &str, purpose: TokenPurpose) -> RepoResult<Option<Uuid>>;
+}
+
+#[async_trait]
+impl UserRepository for PgPool {
+    async fn create(&self, username: &str, email: &str, password_hash: &str) -> RepoResult<User> {
+        let user = sqlx::query_as!(
+            User,
+            r#"
+            INSERT INTO users (username, email, password_hash, created_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, username, email, password_hash, created_at, verified_at,
+                      role as "role: Role", suspended_at, locked_until, timezone
+            "#,
+            username,
+            email,
+            password_hash,
+            Utc::now()
+        )
+        .fetch_one(self)
+        .await?;
+
+        Ok(user)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> RepoResult<Option<User>> {
+        Ok(User::find_by_id(self, id).await?)
+    }
+
+    async fn find_by_email(&self, email: &str) -> RepoResult<Option<User>> {
+        Ok(User::find_by_email(self, email).await?)
+    }
+
+    async fn email_or_username_taken(&self, email: &str, username: &str) -> RepoResult<bool> {
+        let taken = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM users WHERE email = $1 OR username = $2)"
+        )
+        .bind(email)
+        .bind(username)
+        .fetch_one(self)
+        .await?;
+
+        Ok(taken)
+    }
+
+    async fn list(&self, filter: &UserFilter) -> RepoResult<Vec<User>> {
+        let users = sqlx::query_as::<_, User>(
+            "SELECT * FROM users
+             WHERE ($1::text IS NULL OR username ILIKE $1 OR email ILIKE $1)
+               AND ($2::user_role IS NULL OR role = $2)
+               AND ($3::boolean IS NULL OR (suspended_at IS NOT NULL) = $3)
+             ORDER BY created_at DESC
+             LIMIT $4 OFFSET $5"
+        )
+        .bind(filter.search.as_ref().map(|s| format!("%{}%", s)))
+        .bind(filter.role)
+        .bind(filter.suspended)
+        .bind(filter.limit)
+        .bind(filter.offset)
+        .fetch_all(self)
+        .await?;
+
+        Ok(users)
+    }
+
+    async fn record_failed_login(&self, id: Uuid) -> RepoResult<()> {
+        Ok(User::record_failed_login(self, id).await?)
+    }
+
+    async fn clear_failed_logins(&self, id: Uuid) -> RepoResult<()> {
+        Ok(User::clear_failed_logins(self, id).await?)
+    }
+
+    async fn set_password(&self, id: Uuid, password_hash: &str) -> RepoResult<()> {
+        sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+            .bind(password_hash)
+            .bind(id)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+
+    async fn mark_verified(&self, id: Uuid) -> RepoResult<Option<DateTime<Utc>>> {
+        let verified_at = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+            "UPDATE users SET verified_at = COALESCE(verified_at, NOW()) WHERE id = $1 RETURNING verified_at"
+        )
+        .bind(id)
+        .fetch_optional(self)
+        .await?;
+
+        Ok(verified_at.flatten())
+    }
+
+    async fn set_timezone(&self, id: Uuid, timezone: &str) -> RepoResult<Option<User>> {
+        let user = sqlx::query_as::<_, User>("UPDATE users SET timezone = $2 WHERE id = $1 RETURNING *")
+            .bind(id)
+            .bind(timezone)
+            .fetch_optional(self)
+            .await?;
+
+        Ok(user)
+    }
+
+    async fn set_role(&self, id: Uuid, role: Role) -> RepoResult<Option<User>> {
+        let user = sqlx::query_as::<_, User>("UPDATE users SET role = $2 WHERE id = $1 RETURNING *")
+            .bind(id)
+            .bind(role)
+            .fetch_optional(self)
+            .await?;
+
+        Ok(user)
+    }
+
+    async fn suspend(&self, id: Uuid, reason: Option<&str>) -> RepoResult<Option<User>> {
+        let user = sqlx::query_as::<_, User>(
+            "UPDATE users SET suspended_at = COALESCE(suspended_at, NOW()), suspension_reason = $2
+             WHERE id = $1
+             RETURNING *"
+        )
+        .bind(id)
+        .bind(reason)
+        .fetch_optional(self)
+        .await?;
+
+        Ok(user)
+    }
+
+    async fn unsuspend(&self, id: Uuid) -> RepoResult<Option<User>> {
+        let user = sqlx::query_as::<_, User>(
+            "UPDATE users SET suspended_at = NULL, suspension_reason = NULL
+             WHERE id = $1
+             RETURNING *"
+        )
+        .bind(id)
+        .fetch_optional(self)
+        .await?;
+
+        Ok(user)
+    }
+
+    async fn issue_token(&self, user_id: Uuid, purpose: TokenPurpose, ttl: Duration) -> RepoResult<String> {
+        Ok(UserToken::issue(self, user_id, purpose, ttl).await?)
+    }
+
+    async fn consume_token(&self, token: &str, purpose: TokenPurpose) -> RepoResult<Option<Uuid>> {
+        Ok(UserToken::consume(self, token, purpose).await?)
+    }
+}
+
+// In-memory row for a user, with the columns that User doesn't expose
+pub(super) struct UserRecord {
+    pub user: User,
+    pub failed_login_attempts: i32,
+    pub suspension_reason: Option<String>,
+}
+
+pub(super) struct UserTokenRecord {
+    pub user_id: Uuid,
+    pub purpose: TokenPurpose,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub used: bool,
+}
+
+#[async_trait]
+impl UserRepository for InMemoryStore {
+    async fn create(&self, username: &str, email: &str, password_hash: &str) -> RepoResult<User> {
+        let mut state = self.state();
+        if state.users.values().any(|r| r.user.email == email || r.user.username == username) {
+            return Err(AppError::Conflict("Username or email already exists".into()));
+        }
+
+        let user = User {
+            id: Uuid::new_v4(),
+            username: username.to_string(),
+            email: email.to_string(),
+            password_hash: password_hash.to_string(),
+            created_at: Utc::now(),
+            verified_at: None,
+            role: Role::User,
+            suspended_at: None,
+            locked_until: None,
+            timezone: "UTC".to_string(),
+        };
+
+        state.users.insert(user.id, UserRecord { user: user.clone(), failed_login_attempts: 0, suspension_reason: None });
+        Ok(user)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> RepoResult<Option<User>> {
+        Ok(self.state().users.get(&id).map(|r| r.user.clone()))
+    }
+
+    async fn find_by_email(&self, email: &str) -> RepoResult<Option<User>> {
+        Ok(self.state().users.values().find(|r| r.user.email == email).map(|r| r.user.clone()))
+    }
+
+    async fn email_or_username_taken(&self, email: &str, username: &str) -> RepoResult<bool> {
+        Ok(self.state().users.values().any(|r| r.user.email == email || r.user.username == username))
+    }
+
+    async fn list(&self, filter: &UserFilter) -> RepoResult<Vec<User>> {
+        let search = filter.search.as_ref().map(|s| s.to_lowercase());
+        let mut users: Vec<User> = self
+            .state()
+            .users
+            .values()
+            .map(|r| &r.user)
+            .filter(|u| {
+                search.as_ref().is_none_or(|s| u.username.to_lowercase().contains(s) || u.email.to_lowercase().contains(s))
+            })
+            .filter(|u| filter.role.is_none_or(|role| u.role == role))
+            .filter(|u| filter.suspended.is_none_or(|suspended| u.suspended_at.is_some() == suspended))
+            .cloned()
+            .collect();
+
+        users.sort_by_key(|x| Reverse(x.created_at));
+        Ok(users.into_iter().skip(filter.offset as usize).take(filter.limit as usize).collect())
+    }
+
+    async fn record_failed_login(&self, id: Uuid) -> RepoResult<()> {
+        if let Some(record) = self.state().users.get_mut(&id) {
+            record.failed_login_attempts += 1;
+            if let Some(duration) = lockout_duration(record.failed_login_attempts) {
+                record.user.locked_until = Some(Utc::now() + duration);
+            }
+        }
+        Ok(())
+    }
+
+    async fn clear_failed_logins(&self, id: Uuid) -> RepoResult<()> {
+        if let Some(record) = self.state().users.get_mut(&id) {
+            record.failed_login_attempts = 0;
+            record.user.locked_until = None;
+        }
+        Ok(())
+    }
+
+    async fn set_password(&self, id: Uuid, password_hash: &str) -> RepoResult<()> {
+        if let Some(record) = self.state().users.get_mut(&id) {
+            record.user.password_hash = password_hash.to_string();
+        }
+        Ok(())
+    }
+
+    async fn mark_verified(&self, id: Uuid) -> RepoResult<Option<DateTime<Utc>>> {
+        Ok(self
+            .state()
+            .users
+            .get_mut(&id)
+            .map(|record| *record.user.verified_at.get_or_insert_with(Utc::now)))
+    }
+
+    async fn set_timezone(&self, id: Uuid, timezone: &str) -> RepoResult<Option<User>> {
+        Ok(self.state().users.get_mut(&id).map(|record| {
+            record.user.timezone = timezone.to_string();
+            record.user.clone()
+        }))
+    }
+
+    async fn set_role(&self, id: Uuid, role: Role) -> RepoResult<Option<User>> {
+        Ok(self.state().users.get_mut(&id).map(|record| {
+            record.user.role = role;
+            record.user.clone()
+        }))
+    }
+
+    async fn suspend(&self, id: Uuid, reason: Option<&str>) -> RepoResult<Option<User>> {
+        Ok(self.state().users.get_mut(&id).map(|record| {
+            record.user.suspended_at.get_or_insert_with(Utc::now);
+            record.suspension_reason = reason.map(|r| r.to_string());
+            record.user.clone()
+        }))
+    }
+
+    async fn unsuspend(&self, id: Uuid) -> RepoResult<Option<User>> {
+        Ok(self.state().users.get_mut(&id).map(|record| {
+            record.user.suspended_at = None;
+            record.suspension_reason = None;
+            record.user.clone()
+        }))
+    }
+
+    async fn issue_token(&self, user_id: Uuid, purpose: TokenPurpose, ttl: Duration) -> RepoResult<String> {
+        let token = generate_token(32);
+        let mut state = self.state();
+
+        for record in state.user_tokens.iter_mut().filter(|t| t.user_id == user_id && t.purpose == purpose) {
+            record.used = true;
+        }
+
+        state.user_tokens.push(UserTokenRecord {
+            user_id,
+            purpose,
+            token_hash: hash_token(&token),
+            expires_at: Utc::now() + ttl,
+            used: false,
+        });
+
+        Ok(token)
+    }
+
+    async fn consume_token(&self, token: &str, purpose: TokenPurpose) -> RepoResult<Option<Uuid>> {
+        let token_hash = hash_token(token);
+        let now = Utc::now();
+
+        Ok(self
+            .state()
+            .user_tokens
+            .iter_mut()
+            .find(|t| t.token_hash == token_hash && t.purpose == purpose && !t.used && t.expires_at > now)
+            .map(|record| {
+                record.used = true;
+                record.user_id
+            }))
+    }
+}
@@ -0,0 +1,252 @@
+use actix_web::{web, HttpResponse};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::middleware::{Admin, Moderator, RequireRole};
+use crate::models::streak::{GrantFreezesRequest, RecomputeStreaksParams};
+use crate::models::tag::{normalize_tag, CreateTagAliasRequest, MergeTagRequest};
+use crate::models::user::{AdminUserQuery, SuspendUserRequest, UpdateRoleRequest};
+use crate::repository::users::UserFilter;
+use crate::repository::{
+    MessageRepository, ProblemRepository, SessionRepository, StreakRepository, TagRepository, UserRepository,
+};
+use crate::streak_audit;
+
+const MAX_FREEZE_GRANT: i32 = 30;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/admin")
+            .route("/users", web::get().to(list_users))
+            .route("/users/{id}/role", web::put().to(update_role))
+            .route("/users/{id}/suspend", web::post().to(suspend_user))
+            .route("/users/{id}/unsuspend", web::post().to(unsuspend_user))
+            .route("/users/{id}/streak-freezes", web::post().to(grant_streak_freezes))
+            .route("/streaks/recompute", web::post().to(recompute_streaks))
+            .route("/problems/{id}", web::delete().to(delete_problem))
+            .route("/feedback/{id}", web::delete().to(delete_feedback))
+            .route("/messages/{id}", web::delete().to(delete_message))
+            .route("/tags/{id}/merge", web::post().to(merge_tag))
+            .route("/tags/{id}/aliases", web::post().to(add_tag_alias))
+            .route("/tags/aliases/{alias}", web::delete().to(remove_tag_alias))
+    );
+}
+
+async fn list_users(
+    users: web::Data<dyn UserRepository>,
+    _admin: RequireRole<Admin>,
+    query: web::Query<AdminUserQuery>,
+) -> Result<HttpResponse, AppError> {
+    let filter = UserFilter {
+        search: query.search.as_ref().map(|s| s.trim().to_string()),
+        role: query.role,
+        suspended: query.suspended,
+        limit: query.limit.unwrap_or(50).clamp(1, 200),
+        offset: query.offset.unwrap_or(0).max(0),
+    };
+
+    let users = users.list(&filter).await?;
+
+    Ok(HttpResponse::Ok().json(users))
+}
+
+async fn update_role(
+    users: web::Data<dyn UserRepository>,
+    admin: RequireRole<Admin>,
+    path: web::Path<Uuid>,
+    payload: web::Json<UpdateRoleRequest>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = path.into_inner();
+
+    // Stops the last admin from locking everyone out by demoting themselves
+    if user_id == admin.user.id {
+        return Err(AppError::BadRequest("You cannot change your own role".into()));
+    }
+
+    let user = users
+        .set_role(user_id, payload.role)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+    tracing::info!("Admin {} set role of user {} to {:?}", admin.user.id, user.id, user.role);
+    Ok(HttpResponse::Ok().json(user))
+}
+
+async fn suspend_user(
+    users: web::Data<dyn UserRepository>,
+    sessions: web::Data<dyn SessionRepository>,
+    admin: RequireRole<Admin>,
+    path: web::Path<Uuid>,
+    payload: web::Json<SuspendUserRequest>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = path.into_inner();
+
+    if user_id == admin.user.id {
+        return Err(AppError::BadRequest("You cannot suspend yourself".into()));
+    }
+
+    let user = users
+        .suspend(user_id, payload.reason.as_deref())
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+    // Suspension already blocks every request, revoking also kills outstanding refresh tokens
+    let revoked = sessions.revoke_all_for_user(user_id, "suspended").await?;
+
+    tracing::info!("Admin {} suspended user {} ({} sessions revoked)", admin.user.id, user_id, revoked);
+    Ok(HttpResponse::Ok().json(user))
+}
+
+async fn unsuspend_user(
+    users: web::Data<dyn UserRepository>,
+    admin: RequireRole<Admin>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let user = users
+        .unsuspend(path.into_inner())
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+    tracing::info!("Admin {} unsuspended user {}", admin.user.id, user.id);
+    Ok(HttpResponse::Ok().json(user))
+}
+
+// Purchases are taken outside BrainJar; once one clears, the freezes are credited here
+async fn grant_streak_freezes(
+    streaks: web::Data<dyn StreakRepository>,
+    admin: RequireRole<Admin>,
+    path: web::Path<Uuid>,
+    payload: web::Json<GrantFreezesRequest>,
+) -> Result<HttpResponse, AppError> {
+    if !(1..=MAX_FREEZE_GRANT).contains(&payload.count) {
+        return Err(AppError::validation(
+            "Invalid freeze grant",
+            serde_json::json!({ "count": format!("must be between 1 and {}", MAX_FREEZE_GRANT) }),
+        ));
+    }
+
+    let user_id = path.into_inner();
+    let streak = streaks
+        .grant_freezes(user_id, payload.count)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User has no streak yet".into()))?;
+
+    tracing::info!("Admin {} granted {} streak freezes to user {}", admin.user.id, payload.count, user_id);
+    Ok(HttpResponse::Ok().json(streak))
+}
+
+// Rebuild every streak from its history; ?dry_run=true only reports what would change
+async fn recompute_streaks(
+    streaks: web::Data<dyn StreakRepository>,
+    admin: RequireRole<Admin>,
+    params: web::Query<RecomputeStreaksParams>,
+) -> Result<HttpResponse, AppError> {
+    let audit = streak_audit::recompute_streaks(streaks.get_ref(), params.dry_run).await?;
+
+    tracing::info!(
+        "Admin {} recomputed {} streaks: {} discrepancies, {} repaired",
+        admin.user.id,
+        audit.checked,
+        audit.discrepancies.len(),
+        audit.repaired
+    );
+    Ok(HttpResponse::Ok().json(audit))
+}
+
+// Content moderation is open to moderators as well as admins
+
+async fn delete_problem(
+    problems: web::Data<dyn ProblemRepository>,
+    moderator: RequireRole<Moderator>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let problem_id = path.into_inner();
+
+    if !problems.delete_any(problem_id).await? {
+        return Err(AppError::NotFound("Problem not found".into()));
+    }
+
+    tracing::info!("Moderator {} deleted problem {}", moderator.user.id, problem_id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn delete_feedback(
+    problems: web::Data<dyn ProblemRepository>,
+    moderator: RequireRole<Moderator>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let feedback_id = path.into_inner();
+
+    if !problems.delete_feedback(feedback_id).await? {
+        return Err(AppError::NotFound("Feedback not found".into()));
+    }
+
+    tracing::info!("Moderator {} deleted feedback {}", moderator.user.id, feedback_id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn delete_message(
+    messages: web::Data<dyn MessageRepository>,
+    moderator: RequireRole<Moderator>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let message_id = path.into_inner();
+
+    if !messages.delete(message_id).await? {
+        return Err(AppError::NotFound("Message not found".into()));
+    }
+
+    tracing::info!("Moderator {} deleted message {}", moderator.user.id, message_id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Folds tag {id} into `into`. Problems tagged with it get `into` instead, and its name
+// becomes an alias so new uses land on `into` too.
+async fn merge_tag(
+    tags: web::Data<dyn TagRepository>,
+    moderator: RequireRole<Moderator>,
+    path: web::Path<Uuid>,
+    payload: web::Json<MergeTagRequest>,
+) -> Result<HttpResponse, AppError> {
+    let source = path.into_inner();
+
+    if source == payload.into {
+        return Err(AppError::BadRequest("A tag cannot be merged into itself".into()));
+    }
+
+    let tag = tags.merge(source, payload.into).await?;
+
+    tracing::info!("Moderator {} merged tag {} into {}", moderator.user.id, source, tag.id);
+    Ok(HttpResponse::Ok().json(tag))
+}
+
+async fn add_tag_alias(
+    tags: web::Data<dyn TagRepository>,
+    moderator: RequireRole<Moderator>,
+    path: web::Path<Uuid>,
+    payload: web::Json<CreateTagAliasRequest>,
+) -> Result<HttpResponse, AppError> {
+    let alias = normalize_tag(&payload.alias).ok_or_else(|| {
+        AppError::validation("Invalid alias", serde_json::json!({ "alias": "must contain letters or digits" }))
+    })?;
+
+    let alias = tags.add_alias(path.into_inner(), &alias).await?;
+
+    tracing::info!("Moderator {} added alias {} for tag {}", moderator.user.id, alias.alias, alias.tag_id);
+    Ok(HttpResponse::Created().json(alias))
+}
+
+async fn remove_tag_alias(
+    tags: web::Data<dyn TagRepository>,
+    moderator: RequireRole<Moderator>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let alias = normalize_tag(&path.into_inner()).unwrap_or_default();
+
+    if !tags.remove_alias(&alias).await? {
+        return Err(AppError::NotFound("Alias not found".into()));
+    }
+
+    tracing::info!("Moderator {} removed tag alias {}", moderator.user.id, alias);
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -1,74 +1,102 @@
-use actix_web::{web, HttpResponse, Error, web::ServiceConfig};
+use actix_web::{web, HttpRequest, HttpResponse, web::ServiceConfig};
 use bcrypt::{hash, verify, DEFAULT_COST};
-use jsonwebtoken::{encode, EncodingKey, Header};
 use serde::Serialize;
-use sqlx::PgPool;
-use chrono::{Utc, Duration};
+use uuid::Uuid;
 
-use crate::models::user::{CreateUser, LoginUser, User};
-use crate::middleware::{Claims, AuthenticatedUser};
+use crate::mailer::{Email, Mailer};
+use crate::models::user::{
+    parse_timezone, CreateUser, ForgotPasswordRequest, LoginUser, ResetPasswordRequest, Role, UpdateTimezoneRequest, User,
+    VerifyEmailRequest,
+};
+use crate::models::session::{RefreshOutcome, RefreshTokenRequest, Session, TokenPair};
+use crate::models::user_token::TokenPurpose;
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::jwt::JwtKeys;
+use crate::middleware::{create_access_token, create_mfa_token, AuthenticatedUser};
+use crate::repository::{AccessTokenRepository, FriendRepository, MfaRepository, SessionRepository, UserRepository};
+use crate::routes::mfa;
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+// Applied wherever a password is chosen: signup and reset
+fn check_password_length(password: &str) -> Result<(), AppError> {
+    if password.len() < MIN_PASSWORD_LENGTH {
+        return Err(AppError::BadRequest(format!(
+            "Password must be at least {} characters",
+            MIN_PASSWORD_LENGTH
+        )));
+    }
+    Ok(())
+}
 
 pub fn config(cfg: &mut ServiceConfig) {
     cfg.service(
         web::scope("/api/auth")
             .route("/register", web::post().to(signup))
             .route("/login", web::post().to(login))
+            .route("/refresh", web::post().to(refresh))
+            .route("/logout", web::post().to(logout))
+            .route("/logout-all", web::post().to(logout_all))
+            .route("/sessions", web::get().to(list_sessions))
+            .route("/sessions/{id}", web::delete().to(revoke_session))
+            .route("/forgot-password", web::post().to(forgot_password))
+            .route("/reset-password", web::post().to(reset_password))
+            .route("/verify-email", web::post().to(verify_email))
+            .route("/resend-verification", web::post().to(resend_verification))
+            .service(web::scope("/mfa").configure(mfa::config))
     )
     .service(
         web::scope("/api/users")
             .route("/suggestions", web::get().to(get_user_suggestions))
+            .route("/me/timezone", web::put().to(set_timezone))
     );
 }
 
+// Issue a verification token and email it. Delivery failures are logged, not surfaced,
+// so a flaky mail server never blocks signup.
+async fn send_verification_email(users: &dyn UserRepository, config: &AppConfig, mailer: &dyn Mailer, user: &User) -> Result<(), AppError> {
+    let ttl = TokenPurpose::EmailVerification.ttl(&config.tokens);
+    let token = users.issue_token(user.id, TokenPurpose::EmailVerification, ttl).await?;
+
+    let body = format!(
+        "Hi {},\n\nPlease confirm your email address by opening the link below:\n\n{}/verify-email?token={}\n\nThe link expires in {} hours.",
+        user.username,
+        config.app_base_url,
+        token,
+        ttl.num_hours()
+    );
+
+    if let Err(e) = mailer.send(Email::new(&user.email, "Confirm your BrainJar email", body)).await {
+        tracing::error!("Failed to send verification email to {}: {}", user.email, e);
+    }
+
+    Ok(())
+}
+
 async fn signup(
-    pool: web::Data<PgPool>,
+    users: web::Data<dyn UserRepository>,
+    config: web::Data<AppConfig>,
+    mailer: web::Data<dyn Mailer>,
     payload: web::Json<CreateUser>,
-) -> Result<HttpResponse, Error> {
+) -> Result<HttpResponse, AppError> {
     tracing::debug!("Received signup request for user: {}", payload.username);
 
-    // Check if user already exists in database
-    let existing_user = sqlx::query!(
-        "SELECT id FROM users WHERE email = $1 OR username = $2",
-        payload.email,
-        payload.username
-    )
-    .fetch_optional(pool.get_ref())
-    .await
-    .map_err(|e| {
-        tracing::error!("Database error checking existing user: {}", e);
-        actix_web::error::ErrorInternalServerError("Database error")
-    })?;
+    check_password_length(&payload.password)?;
 
-    if existing_user.is_some() {
-        return Err(actix_web::error::ErrorConflict("Username or email already exists"));
+    // Check if user already exists in database
+    if users.email_or_username_taken(&payload.email, &payload.username).await? {
+        return Err(AppError::Conflict("Username or email already exists".into()));
     }
 
     // Hash password
     let password_hash = hash(payload.password.as_bytes(), DEFAULT_COST)
-        .map_err(|e| {
-            tracing::error!("Password hashing failed: {}", e);
-            actix_web::error::ErrorInternalServerError("Internal server error")
-        })?;
+        .map_err(|e| AppError::internal("Password hashing failed", e))?;
 
     // Insert user into database
-    let user = sqlx::query_as!(
-        User,
-        r#"
-        INSERT INTO users (username, email, password_hash, created_at)
-        VALUES ($1, $2, $3, $4)
-        RETURNING id, username, email, password_hash, created_at
-        "#,
-        payload.username,
-        payload.email,
-        password_hash,
-        Utc::now()
-    )
-    .fetch_one(pool.get_ref())
-    .await
-    .map_err(|e| {
-        tracing::error!("Database error creating user: {}", e);
-        actix_web::error::ErrorInternalServerError("Failed to create user")
-    })?;
+    let user = users.create(&payload.username, &payload.email, &password_hash).await?;
+
+    send_verification_email(users.get_ref(), &config, mailer.get_ref(), &user).await?;
 
     // Return user without password hash
     let response_user = User {
@@ -77,6 +105,11 @@ async fn signup(
         email: user.email,
         password_hash: String::from("***"),
         created_at: user.created_at,
+        verified_at: user.verified_at,
+        role: user.role,
+        suspended_at: user.suspended_at,
+        locked_until: None,
+        timezone: user.timezone,
     };
 
     tracing::info!("Successfully created user in database: {}", response_user.username);
@@ -84,63 +117,107 @@ async fn signup(
 }
 
 #[derive(Debug, Serialize)]
-struct LoginResponse {
-    token: String,
-    user: User,
+pub(crate) struct LoginResponse {
+    #[serde(flatten)]
+    pub tokens: TokenPair,
+    pub user: User,
+}
+
+// Start a session for the user and issue its first access/refresh token pair
+pub(crate) async fn start_session(
+    sessions: &dyn SessionRepository,
+    users: &dyn UserRepository,
+    config: &AppConfig,
+    keys: &JwtKeys,
+    req: &HttpRequest,
+    user: &User,
+) -> Result<TokenPair, AppError> {
+    if user.suspended_at.is_some() {
+        tracing::warn!("Login attempt for suspended user: {}", user.username);
+        return Err(AppError::Forbidden("Account suspended".into()));
+    }
+
+    let user_agent = req
+        .headers()
+        .get("User-Agent")
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.to_string());
+    let ip_address = req.connection_info().realip_remote_addr().map(|s| s.to_string());
+
+    let (session, refresh_token) = sessions
+        .create(user.id, user_agent, ip_address, config.tokens.refresh_token_ttl())
+        .await?;
+
+    // Only a completed login (including any second factor) resets the lockout counter
+    users.clear_failed_logins(user.id).await?;
+
+    issue_tokens(config, keys, &session, refresh_token, user.role)
+}
+
+fn issue_tokens(config: &AppConfig, keys: &JwtKeys, session: &Session, refresh_token: String, role: Role) -> Result<TokenPair, AppError> {
+    let token = create_access_token(config, keys, session.user_id, session.id, role).map_err(|e| AppError::internal("JWT generation error", e))?;
+
+    Ok(TokenPair {
+        token,
+        refresh_token,
+        token_type: "Bearer",
+        expires_in: config.tokens.access_token_ttl().num_seconds(),
+    })
 }
 
 async fn login(
-    pool: web::Data<PgPool>,
+    users: web::Data<dyn UserRepository>,
+    sessions: web::Data<dyn SessionRepository>,
+    mfa: web::Data<dyn MfaRepository>,
+    config: web::Data<AppConfig>,
+    keys: web::Data<JwtKeys>,
+    req: HttpRequest,
     payload: web::Json<LoginUser>,
-) -> Result<HttpResponse, Error> {
+) -> Result<HttpResponse, AppError> {
     tracing::debug!("Received login request for email: {}", payload.email);
 
     // Find user by email in database
-    let user = sqlx::query_as!(
-        User,
-        "SELECT id, username, email, password_hash, created_at FROM users WHERE email = $1",
-        payload.email
-    )
-    .fetch_optional(pool.get_ref())
-    .await
-    .map_err(|e| {
-        tracing::error!("Database error finding user: {}", e);
-        actix_web::error::ErrorInternalServerError("Database error")
-    })?
-    .ok_or_else(|| {
+    let user = users
+        .find_by_email(&payload.email)
+        .await?
+        .ok_or_else(|| {
         tracing::warn!("Login attempt with invalid email: {}", payload.email);
-        actix_web::error::ErrorUnauthorized("Invalid credentials")
+        AppError::Unauthorized("Invalid credentials".into())
     })?;
 
+    // Refuse before checking the password so a locked account can't be brute-forced
+    if let Some(retry_after) = user.lockout_remaining() {
+        tracing::warn!("Login attempt for locked account: {}", payload.email);
+        return Err(AppError::RateLimited {
+            message: "Too many failed login attempts, try again later".to_string(),
+            retry_after: retry_after as u64,
+        });
+    }
+
     // Verify password
     if !verify(&payload.password, &user.password_hash)
-        .map_err(|e| {
-            tracing::error!("Password verification error: {}", e);
-            actix_web::error::ErrorInternalServerError("Authentication error")
-        })? 
+        .map_err(|e| AppError::internal("Password verification error", e))? 
     {
         tracing::warn!("Login attempt with invalid password for user: {}", payload.email);
-        return Err(actix_web::error::ErrorUnauthorized("Invalid credentials"));
+        users.record_failed_login(user.id).await?;
+        return Err(AppError::Unauthorized("Invalid credentials".into()));
     }
 
-    // Generate JWT
-    let claims = Claims {
-        sub: user.id.to_string(),
-        exp: (Utc::now() + Duration::days(7)).timestamp(),
-    };
+    // With two-factor enabled the password only earns a short-lived token for /mfa/verify
+    let mfa_enabled = mfa.is_enabled(user.id).await?;
 
-    let jwt_secret = std::env::var("JWT_SECRET")
-        .unwrap_or_else(|_| "brainjar_secret_key_2025".to_string());
+    if mfa_enabled {
+        let mfa_token = create_mfa_token(&config, &keys, user.id).map_err(|e| AppError::internal("JWT generation error", e))?;
 
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(jwt_secret.as_bytes()),
-    )
-    .map_err(|e| {
-        tracing::error!("JWT generation error: {}", e);
-        actix_web::error::ErrorInternalServerError("Token generation failed")
-    })?;
+        tracing::info!("Password accepted, awaiting second factor for user: {}", user.username);
+        return Ok(HttpResponse::Ok().json(serde_json::json!({
+            "mfa_required": true,
+            "mfa_token": mfa_token,
+            "expires_in": config.tokens.mfa_token_ttl().num_seconds()
+        })));
+    }
+
+    let tokens = start_session(sessions.get_ref(), users.get_ref(), &config, &keys, &req, &user).await?;
 
     // Return user without password hash
     let response_user = User {
@@ -149,33 +226,210 @@ async fn login(
         email: user.email,
         password_hash: String::from("***"),
         created_at: user.created_at,
+        verified_at: user.verified_at,
+        role: user.role,
+        suspended_at: user.suspended_at,
+        locked_until: None,
+        timezone: user.timezone,
     };
 
     tracing::info!("Successful login for user: {}", response_user.username);
-    Ok(HttpResponse::Ok().json(LoginResponse { token, user: response_user }))
+    Ok(HttpResponse::Ok().json(LoginResponse { tokens, user: response_user }))
+}
+
+async fn refresh(
+    users: web::Data<dyn UserRepository>,
+    sessions: web::Data<dyn SessionRepository>,
+    config: web::Data<AppConfig>,
+    keys: web::Data<JwtKeys>,
+    payload: web::Json<RefreshTokenRequest>,
+) -> Result<HttpResponse, AppError> {
+    let outcome = sessions.rotate(&payload.refresh_token).await?;
+
+    match outcome {
+        RefreshOutcome::Rotated { session, refresh_token } => {
+            let user = users.find_by_id(session.user_id).await?
+                .ok_or_else(|| AppError::Unauthorized("Invalid or expired refresh token".into()))?;
+
+            if user.suspended_at.is_some() {
+                return Err(AppError::Forbidden("Account suspended".into()));
+            }
+
+            Ok(HttpResponse::Ok().json(issue_tokens(&config, &keys, &session, refresh_token, user.role)?))
+        }
+        RefreshOutcome::ReuseDetected => Err(AppError::Unauthorized(
+            "Refresh token reuse detected, session revoked".into(),
+        )),
+        RefreshOutcome::Invalid => Err(AppError::Unauthorized("Invalid or expired refresh token".into())),
+    }
+}
+
+async fn logout(
+    sessions: web::Data<dyn SessionRepository>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let session_id = user
+        .session_id()
+        .ok_or_else(|| AppError::BadRequest("Not authenticated with a session".into()))?;
+
+    sessions.revoke(session_id, user.id, "logout").await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn logout_all(
+    sessions: web::Data<dyn SessionRepository>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let revoked = sessions.revoke_all_for_user(user.id, "logout_all").await?;
+
+    tracing::info!("Revoked {} sessions for user {}", revoked, user.id);
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "revoked_sessions": revoked
+    })))
+}
+
+async fn list_sessions(
+    sessions: web::Data<dyn SessionRepository>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let sessions_json: Vec<serde_json::Value> = sessions
+        .list_active_for_user(user.id)
+        .await?
+        .into_iter()
+        .map(|s| serde_json::json!({
+            "id": s.id,
+            "user_agent": s.user_agent,
+            "ip_address": s.ip_address,
+            "created_at": s.created_at,
+            "last_used_at": s.last_used_at,
+            "expires_at": s.expires_at,
+            "current": Some(s.id) == user.session_id()
+        }))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(sessions_json))
+}
+
+async fn revoke_session(
+    sessions: web::Data<dyn SessionRepository>,
+    path: web::Path<Uuid>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let revoked = sessions.revoke(path.into_inner(), user.id, "revoked_by_user").await?;
+
+    if !revoked {
+        return Err(AppError::NotFound("Session not found".into()));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn forgot_password(
+    users: web::Data<dyn UserRepository>,
+    config: web::Data<AppConfig>,
+    mailer: web::Data<dyn Mailer>,
+    payload: web::Json<ForgotPasswordRequest>,
+) -> Result<HttpResponse, AppError> {
+    let user = users.find_by_email(&payload.email).await?;
+
+    // Respond the same way whether or not the account exists to avoid leaking registered emails
+    if let Some(user) = user {
+        let ttl = TokenPurpose::PasswordReset.ttl(&config.tokens);
+        let token = users.issue_token(user.id, TokenPurpose::PasswordReset, ttl).await?;
+
+        let body = format!(
+            "Hi {},\n\nSomeone asked to reset the password for your BrainJar account. If it was you, open the link below:\n\n{}/reset-password?token={}\n\nThe link expires in {} minutes. If you did not ask for this you can ignore this email.",
+            user.username,
+            config.app_base_url,
+            token,
+            ttl.num_minutes()
+        );
+
+        if let Err(e) = mailer.send(Email::new(&user.email, "Reset your BrainJar password", body)).await {
+            tracing::error!("Failed to send password reset email to {}: {}", user.email, e);
+        }
+    } else {
+        tracing::debug!("Password reset requested for unknown email: {}", payload.email);
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "If an account exists for that email, a reset link has been sent"
+    })))
+}
+
+async fn reset_password(
+    users: web::Data<dyn UserRepository>,
+    sessions: web::Data<dyn SessionRepository>,
+    access_tokens: web::Data<dyn AccessTokenRepository>,
+    payload: web::Json<ResetPasswordRequest>,
+) -> Result<HttpResponse, AppError> {
+    check_password_length(&payload.new_password)?;
+
+    let user_id = users
+        .consume_token(&payload.token, TokenPurpose::PasswordReset)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Invalid or expired reset token".into()))?;
+
+    let password_hash = hash(payload.new_password.as_bytes(), DEFAULT_COST)
+        .map_err(|e| AppError::internal("Password hashing failed", e))?;
+
+    users.set_password(user_id, &password_hash).await?;
+    users.clear_failed_logins(user_id).await?;
+
+    // Whoever knew the old password should not stay logged in, nor keep the personal access
+    // tokens they could have created with it
+    sessions.revoke_all_for_user(user_id, "password_reset").await?;
+    access_tokens.revoke_all_for_user(user_id).await?;
+
+    tracing::info!("Password reset for user: {}", user_id);
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Password has been reset"
+    })))
+}
+
+async fn verify_email(
+    users: web::Data<dyn UserRepository>,
+    payload: web::Json<VerifyEmailRequest>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = users
+        .consume_token(&payload.token, TokenPurpose::EmailVerification)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Invalid or expired verification token".into()))?;
+
+    let verified_at = users.mark_verified(user_id).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Email verified",
+        "verified_at": verified_at
+    })))
+}
+
+async fn resend_verification(
+    users: web::Data<dyn UserRepository>,
+    config: web::Data<AppConfig>,
+    mailer: web::Data<dyn Mailer>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let user = users.find_by_id(user.id).await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+    if user.verified_at.is_some() {
+        return Err(AppError::Conflict("Email is already verified".into()));
+    }
+
+    send_verification_email(users.get_ref(), &config, mailer.get_ref(), &user).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Verification email sent"
+    })))
 }
 
 async fn get_user_suggestions(
-    pool: web::Data<PgPool>,
+    friends: web::Data<dyn FriendRepository>,
     user: AuthenticatedUser,
-) -> Result<HttpResponse, Error> {
-    let suggestions = sqlx::query_as::<_, User>(
-        "SELECT DISTINCT u.* 
-         FROM users u
-         WHERE u.id != $1  -- Exclude current user
-         AND u.id NOT IN (
-             -- Exclude users with existing friend requests (in either direction)
-             SELECT f.friend_id FROM friends f WHERE f.user_id = $1
-             UNION
-             SELECT f.user_id FROM friends f WHERE f.friend_id = $1
-         )
-         ORDER BY u.username
-         LIMIT 20"
-    )
-    .bind(user.id)
-    .fetch_all(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+) -> Result<HttpResponse, AppError> {
+    let suggestions = friends.suggestions(user.id).await?;
 
     // Remove password hashes from response
     let safe_suggestions: Vec<User> = suggestions.into_iter().map(|mut user| {
@@ -188,3 +442,167 @@ async fn get_user_suggestions(
         "count": safe_suggestions.len()
     })))
 }
+
+// Streak days are counted from midnight in this zone from the next solve on
+async fn set_timezone(
+    users: web::Data<dyn UserRepository>,
+    user: AuthenticatedUser,
+    payload: web::Json<UpdateTimezoneRequest>,
+) -> Result<HttpResponse, AppError> {
+    let timezone = parse_timezone(&payload.timezone).ok_or_else(|| {
+        AppError::validation(
+            "Invalid timezone",
+            serde_json::json!({ "timezone": format!("'{}' is not an IANA time zone name", payload.timezone) }),
+        )
+    })?;
+
+    let mut updated = users
+        .set_timezone(user.id, timezone.name())
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+    updated.password_hash = "***".to_string();
+
+    tracing::info!("User {} set their timezone to {}", user.id, updated.timezone);
+    Ok(HttpResponse::Ok().json(updated))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::{Method, StatusCode};
+    use serde_json::json;
+
+    use crate::test_app::{init, register, request, send, PASSWORD};
+
+    fn signup(username: &str, email: &str, password: &str) -> actix_web::test::TestRequest {
+        request(Method::POST, "/api/auth/register", None)
+            .set_json(json!({ "username": username, "email": email, "password": password }))
+    }
+
+    fn refresh(refresh_token: &str) -> actix_web::test::TestRequest {
+        request(Method::POST, "/api/auth/refresh", None).set_json(json!({ "refresh_token": refresh_token }))
+    }
+
+    #[actix_web::test]
+    async fn signup_checks_password_length_and_duplicates() {
+        let app = init().await;
+
+        let (status, body) = send(&app, signup("ada", "ada@example.com", "short")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Password must be at least 8 characters");
+
+        let (status, body) = send(&app, signup("ada", "ada@example.com", PASSWORD)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["username"], "ada");
+        assert!(body.get("password_hash").is_none());
+
+        let (status, _) = send(&app, signup("ada", "other@example.com", PASSWORD)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[actix_web::test]
+    async fn login_rejects_wrong_credentials() {
+        let app = init().await;
+        register(&app, "ada").await;
+
+        for (email, password) in [("ada@example.com", "wrong password"), ("nobody@example.com", PASSWORD)] {
+            let (status, _) = send(
+                &app,
+                request(Method::POST, "/api/auth/login", None).set_json(json!({ "email": email, "password": password })),
+            )
+            .await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", email);
+        }
+    }
+
+    #[actix_web::test]
+    async fn refresh_rotates_the_token_pair() {
+        let app = init().await;
+        let ada = register(&app, "ada").await;
+
+        let (status, body) = send(&app, refresh(&ada.refresh_token)).await;
+        assert_eq!(status, StatusCode::OK);
+        let rotated = body["refresh_token"].as_str().unwrap();
+        assert_ne!(rotated, ada.refresh_token);
+
+        let (status, _) = send(&app, request(Method::GET, "/api/auth/sessions", body["token"].as_str())).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = send(&app, refresh(rotated)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn refresh_token_reuse_revokes_the_session() {
+        let app = init().await;
+        let ada = register(&app, "ada").await;
+
+        let (_, body) = send(&app, refresh(&ada.refresh_token)).await;
+        let rotated = body["refresh_token"].as_str().unwrap().to_string();
+
+        let (status, body) = send(&app, refresh(&ada.refresh_token)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["message"], "Refresh token reuse detected, session revoked");
+
+        // The whole session is gone: the newer refresh token and the access token with it
+        let (status, _) = send(&app, refresh(&rotated)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = send(&app, request(Method::GET, "/api/auth/sessions", Some(&ada.token))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn logout_revokes_only_that_session() {
+        let app = init().await;
+        let ada = register(&app, "ada").await;
+        let (_, body) = send(
+            &app,
+            request(Method::POST, "/api/auth/login", None)
+                .set_json(json!({ "email": "ada@example.com", "password": PASSWORD })),
+        )
+        .await;
+        let other = body["token"].as_str().unwrap();
+
+        let (status, _) = send(&app, request(Method::POST, "/api/auth/logout", Some(&ada.token))).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (status, _) = send(&app, request(Method::GET, "/api/auth/sessions", Some(&ada.token))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = send(&app, refresh(&ada.refresh_token)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = send(&app, request(Method::GET, "/api/auth/sessions", Some(other))).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn password_reset_revokes_sessions_and_access_tokens() {
+        let app = init().await;
+        let ada = register(&app, "ada").await;
+        let (status, body) = send(
+            &app,
+            request(Method::POST, "/api/tokens", Some(&ada.token)).set_json(json!({ "name": "ci", "scopes": ["problems:read"] })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+        let access_token = body["token"].as_str().unwrap().to_string();
+
+        send(&app, request(Method::POST, "/api/auth/forgot-password", None).set_json(json!({ "email": "ada@example.com" }))).await;
+        let (_, outbox) = send(&app, request(Method::GET, "/api/dev/outbox", None)).await;
+        let email = outbox.as_array().unwrap().last().unwrap()["body"].as_str().unwrap().to_string();
+        let reset_token = email.split("token=").nth(1).unwrap().split_whitespace().next().unwrap();
+
+        let (status, _) = send(
+            &app,
+            request(Method::POST, "/api/auth/reset-password", None)
+                .set_json(json!({ "token": reset_token, "new_password": "a brand new password" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        for token in [ada.token.as_str(), access_token.as_str()] {
+            let (status, _) = send(&app, request(Method::GET, "/api/problems", Some(token))).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+        }
+        let (status, _) = send(&app, refresh(&ada.refresh_token)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
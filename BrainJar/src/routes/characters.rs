@@ -1,8 +1,9 @@
-use actix_web::{web, HttpResponse, Error};
-use sqlx::PgPool;
+use actix_web::{web, HttpResponse};
 
+use crate::error::AppError;
 use crate::models::character::{Character, CreateCharacter, UpdateCharacter};
 use crate::middleware::AuthenticatedUser;
+use crate::repository::CharacterRepository;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
@@ -16,64 +17,44 @@ pub fn config(cfg: &mut web::ServiceConfig) {
 }
 
 async fn get_my_character(
-    pool: web::Data<PgPool>,
+    characters: web::Data<dyn CharacterRepository>,
     user: AuthenticatedUser,
-) -> Result<HttpResponse, Error> {
-    match Character::get_by_user_id(&**pool, user.id).await {
-        Ok(Some(character)) => Ok(HttpResponse::Ok().json(character)),
-        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Character not found",
-            "message": "Create your character to get started"
-        }))),
-        Err(e) => Err(actix_web::error::ErrorInternalServerError(e.to_string())),
-    }
+) -> Result<HttpResponse, AppError> {
+    let character = characters.find_for_user(user.id).await?
+        .ok_or_else(|| AppError::NotFound("Character not found, create your character to get started".into()))?;
+
+    Ok(HttpResponse::Ok().json(character))
 }
 
 async fn create_or_update_character(
-    pool: web::Data<PgPool>,
+    characters: web::Data<dyn CharacterRepository>,
     user: AuthenticatedUser,
     payload: web::Json<CreateCharacter>,
-) -> Result<HttpResponse, Error> {
-    let character = Character::create_or_update_for_user(&**pool, user.id, payload.into_inner())
-        .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+) -> Result<HttpResponse, AppError> {
+    let character = characters.upsert(user.id, payload.into_inner()).await?;
 
     Ok(HttpResponse::Ok().json(character))
 }
 
 async fn update_character(
-    pool: web::Data<PgPool>,
+    characters: web::Data<dyn CharacterRepository>,
     user: AuthenticatedUser,
     payload: web::Json<UpdateCharacter>,
-) -> Result<HttpResponse, Error> {
-    let character = sqlx::query_as::<_, Character>(
-        "UPDATE characters 
-         SET name = COALESCE($2, name),
-             avatar_url = COALESCE($3, avatar_url),
-             bio = COALESCE($4, bio),
-             personality_traits = COALESCE($5, personality_traits)
-         WHERE user_id = $1
-         RETURNING *"
-    )
-    .bind(user.id)
-    .bind(&payload.name)
-    .bind(&payload.avatar_url)
-    .bind(&payload.bio)
-    .bind(&payload.personality_traits)
-    .fetch_optional(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?
-    .ok_or_else(|| actix_web::error::ErrorNotFound("Character not found"))?;
+) -> Result<HttpResponse, AppError> {
+    let character = characters
+        .update(user.id, &payload)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Character not found".into()))?;
 
     Ok(HttpResponse::Ok().json(character))
 }
 
-async fn get_default_avatars() -> Result<HttpResponse, Error> {
+async fn get_default_avatars() -> Result<HttpResponse, AppError> {
     let avatars = Character::get_default_avatars();
     Ok(HttpResponse::Ok().json(avatars))
 }
 
-async fn get_personality_suggestions() -> Result<HttpResponse, Error> {
+async fn get_personality_suggestions() -> Result<HttpResponse, AppError> {
     let suggestions = Character::get_personality_suggestions();
     Ok(HttpResponse::Ok().json(suggestions))
 }
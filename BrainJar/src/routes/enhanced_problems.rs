@@ -1,408 +1,105 @@
-use actix_web::{web, HttpResponse, Error};
-use sqlx::PgPool;
+use actix_web::{web, HttpResponse};
 use uuid::Uuid;
 
+use crate::error::AppError;
 use crate::middleware::AuthenticatedUser;
-use crate::models::resource::{
-    ProblemResource, ProblemFeedback, ProblemCategory, CreateFeedbackRequest,
-    CreateResourceRequest, ProblemWithDetails, CategoryResourcesResponse, YouTubeVideo
-};
+use crate::models::resource::{CategoryResourcesResponse, CreateResourceRequest, ProblemDetails, YouTubeVideo};
+use crate::repository::{ProblemRepository, ResourceRepository};
+use crate::routes::problems::visible_problem;
 
+// Resources, details and the category catalog. Listings and feedback are served by
+// problems.rs. These are plain resources rather than an /api/problems scope, so they
+// have to be configured before problems::config to be matched first.
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
-        web::scope("/api/problems")
-            .route("", web::get().to(get_all_problems_except_mine))
-            .route("/{id}/feedback", web::post().to(create_problem_feedback))
-            .route("/{id}/feedback", web::get().to(get_problem_feedback))
-            .route("/{id}/resources", web::get().to(get_problem_resources))
-            .route("/{id}/resources", web::post().to(add_problem_resource))
-            .route("/{id}/details", web::get().to(get_problem_details))
-    )
-    .service(
-        web::scope("/api/my-problems")
-            .route("", web::get().to(get_my_problems))
+        web::resource("/api/problems/{id}/resources")
+            .route(web::get().to(get_problem_resources))
+            .route(web::post().to(add_problem_resource)),
     )
+    .route("/api/problems/{id}/details", web::get().to(get_problem_details))
     .service(
         web::scope("/api/resources")
             .route("/categories", web::get().to(get_all_categories))
-            .route("/categories/{category_id}", web::get().to(get_category_resources))
+            .route("/categories/{category_id}", web::get().to(get_category_resources)),
     );
 }
 
-async fn get_all_problems_except_mine(
-    pool: web::Data<PgPool>,
-    user: AuthenticatedUser,
-    query: web::Query<ProblemQuery>,
-) -> Result<HttpResponse, Error> {
-    let limit = query.limit.unwrap_or(20).min(100);
-    let offset = query.offset.unwrap_or(0);
-
-    let problems = sqlx::query!(
-        "SELECT p.*, 
-                u.username as author_username, 
-                u.avatar_url as author_avatar_url,
-                COALESCE(AVG(pf.rating), 0) as average_rating,
-                COUNT(DISTINCT pf.id) as total_ratings,
-                COUNT(DISTINCT CASE WHEN pf.is_helpful = true THEN pf.id END) as helpful_count,
-                COUNT(DISTINCT CASE WHEN pf.feedback IS NOT NULL THEN pf.id END) as total_feedback
-         FROM problems p
-         JOIN users u ON p.user_id = u.id
-         LEFT JOIN problem_feedback pf ON p.id = pf.problem_id
-         WHERE p.user_id != $1
-         GROUP BY p.id, u.username, u.avatar_url
-         ORDER BY p.created_at DESC
-         LIMIT $2 OFFSET $3",
-        user.id,
-        limit,
-        offset
-    )
-    .fetch_all(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-
-    let problem_details: Vec<serde_json::Value> = problems
-        .iter()
-        .map(|p| serde_json::json!({
-            "id": p.id,
-            "title": p.title,
-            "description": p.description,
-            "difficulty": p.difficulty,
-            "status": p.status,
-            "user_id": p.user_id,
-            "created_at": p.created_at,
-            "updated_at": p.updated_at,
-            "author_username": p.author_username,
-            "author_avatar_url": p.author_avatar_url,
-            "average_rating": p.average_rating.unwrap_or(0.0),
-            "total_ratings": p.total_ratings.unwrap_or(0),
-            "helpful_count": p.helpful_count.unwrap_or(0),
-            "total_feedback": p.total_feedback.unwrap_or(0)
-        }))
-        .collect();
-
-    let total_count: i64 = sqlx::query_scalar!(
-        "SELECT COUNT(*) FROM problems WHERE user_id != $1",
-        user.id
-    )
-    .fetch_one(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?
-    .unwrap_or(0);
-
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "problems": problem_details,
-        "total_count": total_count,
-        "has_more": offset + (problems.len() as i64) < total_count
-    })))
-}
-
-async fn get_my_problems(
-    pool: web::Data<PgPool>,
-    user: AuthenticatedUser,
-    query: web::Query<ProblemQuery>,
-) -> Result<HttpResponse, Error> {
-    let limit = query.limit.unwrap_or(20).min(100);
-    let offset = query.offset.unwrap_or(0);
-
-    let problems = sqlx::query!(
-        "SELECT p.*, 
-                COALESCE(AVG(pf.rating), 0) as average_rating,
-                COUNT(DISTINCT pf.id) as total_ratings,
-                COUNT(DISTINCT CASE WHEN pf.is_helpful = true THEN pf.id END) as helpful_count,
-                COUNT(DISTINCT CASE WHEN pf.feedback IS NOT NULL THEN pf.id END) as total_feedback
-         FROM problems p
-         LEFT JOIN problem_feedback pf ON p.id = pf.problem_id
-         WHERE p.user_id = $1
-         GROUP BY p.id
-         ORDER BY p.created_at DESC
-         LIMIT $2 OFFSET $3",
-        user.id,
-        limit,
-        offset
-    )
-    .fetch_all(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-
-    let problem_details: Vec<serde_json::Value> = problems
-        .iter()
-        .map(|p| serde_json::json!({
-            "id": p.id,
-            "title": p.title,
-            "description": p.description,
-            "difficulty": p.difficulty,
-            "status": p.status,
-            "user_id": p.user_id,
-            "created_at": p.created_at,
-            "updated_at": p.updated_at,
-            "average_rating": p.average_rating.unwrap_or(0.0),
-            "total_ratings": p.total_ratings.unwrap_or(0),
-            "helpful_count": p.helpful_count.unwrap_or(0),
-            "total_feedback": p.total_feedback.unwrap_or(0)
-        }))
-        .collect();
-
-    let total_count: i64 = sqlx::query_scalar!(
-        "SELECT COUNT(*) FROM problems WHERE user_id = $1",
-        user.id
-    )
-    .fetch_one(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?
-    .unwrap_or(0);
-
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "problems": problem_details,
-        "total_count": total_count,
-        "has_more": offset + (problems.len() as i64) < total_count
-    })))
-}
+const CATEGORY_RESOURCE_LIMIT: i64 = 20;
 
-async fn create_problem_feedback(
-    pool: web::Data<PgPool>,
+async fn get_problem_resources(
+    problems: web::Data<dyn ProblemRepository>,
+    resources: web::Data<dyn ResourceRepository>,
     user: AuthenticatedUser,
     path: web::Path<Uuid>,
-    payload: web::Json<CreateFeedbackRequest>,
-) -> Result<HttpResponse, Error> {
-    let problem_id = path.into_inner();
-
-    // Check if problem exists
-    let problem_exists = sqlx::query!("SELECT id FROM problems WHERE id = $1", problem_id)
-        .fetch_optional(&**pool)
-        .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-
-    if problem_exists.is_none() {
-        return Ok(HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Problem not found"
-        })));
-    }
-
-    // Upsert feedback (insert or update if already exists)
-    let feedback = sqlx::query_as::<_, ProblemFeedback>(
-        "INSERT INTO problem_feedback (id, problem_id, user_id, rating, feedback, is_helpful)
-         VALUES ($1, $2, $3, $4, $5, $6)
-         ON CONFLICT (problem_id, user_id)
-         DO UPDATE SET 
-            rating = EXCLUDED.rating,
-            feedback = EXCLUDED.feedback,
-            is_helpful = EXCLUDED.is_helpful,
-            created_at = NOW()
-         RETURNING *"
-    )
-    .bind(Uuid::new_v4())
-    .bind(problem_id)
-    .bind(user.id)
-    .bind(payload.rating)
-    .bind(&payload.feedback)
-    .bind(payload.is_helpful)
-    .fetch_one(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "message": "Feedback submitted successfully",
-        "feedback": feedback
-    })))
-}
-
-async fn get_problem_feedback(
-    pool: web::Data<PgPool>,
-    path: web::Path<Uuid>,
-    query: web::Query<PaginationQuery>,
-) -> Result<HttpResponse, Error> {
+) -> Result<HttpResponse, AppError> {
     let problem_id = path.into_inner();
-    let limit = query.limit.unwrap_or(10).min(50);
-    let offset = query.offset.unwrap_or(0);
-
-    let feedback = sqlx::query!(
-        "SELECT pf.*, u.username, u.avatar_url
-         FROM problem_feedback pf
-         JOIN users u ON pf.user_id = u.id
-         WHERE pf.problem_id = $1
-         ORDER BY pf.created_at DESC
-         LIMIT $2 OFFSET $3",
-        problem_id,
-        limit,
-        offset
-    )
-    .fetch_all(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    visible_problem(problems.get_ref(), problem_id, user.id).await?;
 
-    let feedback_details: Vec<serde_json::Value> = feedback
-        .iter()
-        .map(|f| serde_json::json!({
-            "id": f.id,
-            "rating": f.rating,
-            "feedback": f.feedback,
-            "is_helpful": f.is_helpful,
-            "created_at": f.created_at,
-            "user": {
-                "username": f.username,
-                "avatar_url": f.avatar_url
-            }
-        }))
-        .collect();
+    let resources = resources.list_for_problem(problem_id).await?;
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
-        "feedback": feedback_details,
-        "total_count": feedback_details.len()
+        "resources": resources
     })))
 }
 
-async fn get_problem_resources(
-    pool: web::Data<PgPool>,
-    path: web::Path<Uuid>,
-) -> Result<HttpResponse, Error> {
-    let problem_id = path.into_inner();
-
-    let resources = sqlx::query_as::<_, ProblemResource>(
-        "SELECT * FROM problem_resources 
-         WHERE problem_id = $1
-         ORDER BY is_recommended DESC, created_at DESC"
-    )
-    .bind(problem_id)
-    .fetch_all(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+fn validate_resource(resource: &CreateResourceRequest) -> Result<(), AppError> {
+    let mut errors = serde_json::Map::new();
+    if resource.title.trim().is_empty() {
+        errors.insert("title".into(), "must not be empty".into());
+    }
+    if !(resource.url.starts_with("https://") || resource.url.starts_with("http://")) {
+        errors.insert("url".into(), "must be an http(s) URL".into());
+    }
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "resources": resources
-    })))
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::validation("Invalid resource", errors.into()))
+    }
 }
 
 async fn add_problem_resource(
-    pool: web::Data<PgPool>,
+    problems: web::Data<dyn ProblemRepository>,
+    resources: web::Data<dyn ResourceRepository>,
     user: AuthenticatedUser,
     path: web::Path<Uuid>,
     payload: web::Json<CreateResourceRequest>,
-) -> Result<HttpResponse, Error> {
+) -> Result<HttpResponse, AppError> {
     let problem_id = path.into_inner();
+    validate_resource(&payload)?;
+    visible_problem(problems.get_ref(), problem_id, user.id).await?;
 
-    // Check if problem exists
-    let problem_exists = sqlx::query!("SELECT id FROM problems WHERE id = $1", problem_id)
-        .fetch_optional(&**pool)
-        .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    let resource = resources.create(problem_id, &payload).await?;
 
-    if problem_exists.is_none() {
-        return Ok(HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Problem not found"
-        })));
-    }
-
-    let resource = sqlx::query_as::<_, ProblemResource>(
-        "INSERT INTO problem_resources 
-         (id, problem_id, resource_type, title, url, description, thumbnail_url, provider, duration, difficulty_level)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
-         RETURNING *"
-    )
-    .bind(Uuid::new_v4())
-    .bind(problem_id)
-    .bind(payload.resource_type)
-    .bind(&payload.title)
-    .bind(&payload.url)
-    .bind(&payload.description)
-    .bind(&payload.thumbnail_url)
-    .bind(&payload.provider)
-    .bind(&payload.duration)
-    .bind(payload.difficulty_level)
-    .fetch_one(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-
-    Ok(HttpResponse::Ok().json(serde_json::json!({
+    Ok(HttpResponse::Created().json(serde_json::json!({
         "message": "Resource added successfully",
         "resource": resource
     })))
 }
 
+// A problem with its author, feedback totals, categories and resources
 async fn get_problem_details(
-    pool: web::Data<PgPool>,
+    problems: web::Data<dyn ProblemRepository>,
+    resources: web::Data<dyn ResourceRepository>,
+    user: AuthenticatedUser,
     path: web::Path<Uuid>,
-) -> Result<HttpResponse, Error> {
+) -> Result<HttpResponse, AppError> {
     let problem_id = path.into_inner();
+    let problem = problems
+        .find_listed(problem_id, user.id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Problem not found".into()))?;
 
-    // Get problem with author details and statistics
-    let problem = sqlx::query!(
-        "SELECT p.*, 
-                u.username as author_username, 
-                u.avatar_url as author_avatar_url,
-                COALESCE(AVG(pf.rating), 0) as average_rating,
-                COUNT(DISTINCT pf.id) as total_ratings,
-                COUNT(DISTINCT CASE WHEN pf.is_helpful = true THEN pf.id END) as helpful_count,
-                COUNT(DISTINCT CASE WHEN pf.feedback IS NOT NULL THEN pf.id END) as total_feedback
-         FROM problems p
-         JOIN users u ON p.user_id = u.id
-         LEFT JOIN problem_feedback pf ON p.id = pf.problem_id
-         WHERE p.id = $1
-         GROUP BY p.id, u.username, u.avatar_url",
-        problem_id
-    )
-    .fetch_optional(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-
-    if problem.is_none() {
-        return Ok(HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Problem not found"
-        })));
-    }
-
-    let p = problem.unwrap();
+    let categories = resources.categories_for_problem(problem_id).await?;
+    let resources = resources.list_for_problem(problem_id).await?;
 
-    // Get categories
-    let categories = sqlx::query_as::<_, ProblemCategory>(
-        "SELECT pc.* FROM problem_categories pc
-         JOIN problem_category_mappings pcm ON pc.id = pcm.category_id
-         WHERE pcm.problem_id = $1"
-    )
-    .bind(problem_id)
-    .fetch_all(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-
-    // Get resources
-    let resources = sqlx::query_as::<_, ProblemResource>(
-        "SELECT * FROM problem_resources 
-         WHERE problem_id = $1
-         ORDER BY is_recommended DESC, created_at DESC"
-    )
-    .bind(problem_id)
-    .fetch_all(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "id": p.id,
-        "title": p.title,
-        "description": p.description,
-        "difficulty": p.difficulty,
-        "status": p.status,
-        "user_id": p.user_id,
-        "created_at": p.created_at,
-        "updated_at": p.updated_at,
-        "author_username": p.author_username,
-        "author_avatar_url": p.author_avatar_url,
-        "average_rating": p.average_rating.unwrap_or(0.0),
-        "total_ratings": p.total_ratings.unwrap_or(0),
-        "helpful_count": p.helpful_count.unwrap_or(0),
-        "total_feedback": p.total_feedback.unwrap_or(0),
-        "categories": categories,
-        "resources": resources
-    })))
+    Ok(HttpResponse::Ok().json(ProblemDetails { problem, categories, resources }))
 }
 
 async fn get_all_categories(
-    pool: web::Data<PgPool>,
-) -> Result<HttpResponse, Error> {
-    let categories = sqlx::query_as::<_, ProblemCategory>(
-        "SELECT * FROM problem_categories ORDER BY name"
-    )
-    .fetch_all(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    resources: web::Data<dyn ResourceRepository>,
+) -> Result<HttpResponse, AppError> {
+    let categories = resources.list_categories().await?;
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "categories": categories
@@ -410,47 +107,24 @@ async fn get_all_categories(
 }
 
 async fn get_category_resources(
-    pool: web::Data<PgPool>,
+    resources: web::Data<dyn ResourceRepository>,
+    user: AuthenticatedUser,
     path: web::Path<Uuid>,
-) -> Result<HttpResponse, Error> {
+) -> Result<HttpResponse, AppError> {
     let category_id = path.into_inner();
+    let category = resources
+        .find_category(category_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Category not found".into()))?;
 
-    // Get category details
-    let category = sqlx::query_as::<_, ProblemCategory>(
-        "SELECT * FROM problem_categories WHERE id = $1"
-    )
-    .bind(category_id)
-    .fetch_optional(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-
-    if category.is_none() {
-        return Ok(HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Category not found"
-        })));
-    }
-
-    let cat = category.unwrap();
-
-    // Get resources for this category
-    let resources = sqlx::query_as::<_, ProblemResource>(
-        "SELECT DISTINCT pr.* FROM problem_resources pr
-         JOIN problem_category_mappings pcm ON pr.problem_id = pcm.problem_id
-         WHERE pcm.category_id = $1
-         ORDER BY pr.is_recommended DESC, pr.created_at DESC
-         LIMIT 20"
-    )
-    .bind(category_id)
-    .fetch_all(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    let category_resources = resources.list_for_category(category_id, user.id, CATEGORY_RESOURCE_LIMIT).await?;
 
     // Generate mock YouTube videos (in a real app, you'd call YouTube API)
-    let youtube_videos = generate_mock_youtube_videos(&cat.name);
+    let youtube_videos = generate_mock_youtube_videos(&category.name);
 
     Ok(HttpResponse::Ok().json(CategoryResourcesResponse {
-        category: cat,
-        resources,
+        category,
+        resources: category_resources,
         youtube_videos,
     }))
 }
@@ -478,17 +152,3 @@ fn generate_mock_youtube_videos(category_name: &str) -> Vec<YouTubeVideo> {
         },
     ]
 }
-
-#[derive(serde::Deserialize)]
-struct ProblemQuery {
-    limit: Option<i64>,
-    offset: Option<i64>,
-    difficulty: Option<String>,
-    category: Option<String>,
-}
-
-#[derive(serde::Deserialize)]
-struct PaginationQuery {
-    limit: Option<i64>,
-    offset: Option<i64>,
-}
@@ -0,0 +1,39 @@
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+
+use crate::error::AppError;
+use crate::middleware::AuthenticatedUser;
+use crate::models::leaderboard::{
+    Leaderboard, LeaderboardMetric, LeaderboardParams, LeaderboardScope, LeaderboardWindow,
+};
+use crate::repository::leaderboards::LeaderboardFilter;
+use crate::repository::LeaderboardRepository;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 100;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/api/leaderboards").route("", web::get().to(get_leaderboard)));
+}
+
+// Weekly solves by default. Study groups don't exist yet, so scope is global or friends.
+async fn get_leaderboard(
+    leaderboards: web::Data<dyn LeaderboardRepository>,
+    user: AuthenticatedUser,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, AppError> {
+    let window = params.window.unwrap_or(LeaderboardWindow::Weekly);
+    let metric = params.metric.unwrap_or(LeaderboardMetric::Solved);
+    let scope = params.scope.unwrap_or(LeaderboardScope::Global);
+
+    let filter = LeaderboardFilter {
+        caller: user.id,
+        metric,
+        scope,
+        since: window.since(Utc::now()),
+        limit: params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+    };
+    let ranking = leaderboards.rank(&filter).await?;
+
+    Ok(HttpResponse::Ok().json(Leaderboard { window, metric, scope, entries: ranking.entries, me: ranking.caller }))
+}
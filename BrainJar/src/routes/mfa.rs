@@ -0,0 +1,202 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use bcrypt::verify;
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::jwt::JwtKeys;
+use crate::middleware::{verify_mfa_token, AuthenticatedUser};
+use crate::models::mfa::{DisableMfaRequest, MfaCodeRequest, MfaLoginRequest, UserMfa};
+use crate::models::user::User;
+use crate::repository::{MfaRepository, SessionRepository, UserRepository};
+use crate::routes::auth::{start_session, LoginResponse};
+use crate::totp;
+
+// Mounted under /api/auth/mfa by auth::config
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("", web::get().to(get_mfa_status))
+        .route("/enroll", web::post().to(enroll))
+        .route("/confirm", web::post().to(confirm_enrollment))
+        .route("/disable", web::post().to(disable))
+        .route("/recovery-codes", web::post().to(regenerate_recovery_codes))
+        .route("/verify", web::post().to(verify_login));
+}
+
+async fn find_user(users: &dyn UserRepository, user_id: Uuid) -> Result<User, AppError> {
+    users.find_by_id(user_id).await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))
+}
+
+// Check a TOTP code and burn its time step so it cannot be used twice
+async fn check_totp(repo: &dyn MfaRepository, mfa: &UserMfa, code: &str) -> Result<bool, AppError> {
+    match totp::verify(&mfa.totp_secret, code, Utc::now().timestamp(), mfa.last_used_step) {
+        Some(step) => repo.record_step(mfa.user_id, step).await,
+        None => Ok(false),
+    }
+}
+
+async fn get_enabled_mfa(repo: &dyn MfaRepository, user_id: Uuid) -> Result<UserMfa, AppError> {
+    repo.get(user_id).await?
+        .filter(|mfa| mfa.enabled_at.is_some())
+        .ok_or_else(|| AppError::BadRequest("Two-factor authentication is not enabled".into()))
+}
+
+async fn get_mfa_status(
+    mfa: web::Data<dyn MfaRepository>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let enabled_at = mfa.get(user.id).await?.and_then(|m| m.enabled_at);
+
+    let recovery_codes_remaining = if enabled_at.is_some() {
+        mfa.remaining_recovery_codes(user.id).await?
+    } else {
+        0
+    };
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "enabled": enabled_at.is_some(),
+        "enabled_at": enabled_at,
+        "recovery_codes_remaining": recovery_codes_remaining
+    })))
+}
+
+async fn enroll(
+    users: web::Data<dyn UserRepository>,
+    mfa: web::Data<dyn MfaRepository>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let account = find_user(users.get_ref(), user.id).await?;
+    let secret = totp::generate_secret();
+
+    mfa.start_enrollment(user.id, &secret).await?
+        .ok_or_else(|| AppError::Conflict("Two-factor authentication is already enabled".into()))?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "secret": secret,
+        "provisioning_uri": totp::provisioning_uri(&secret, &account.email)
+    })))
+}
+
+async fn confirm_enrollment(
+    repo: web::Data<dyn MfaRepository>,
+    user: AuthenticatedUser,
+    payload: web::Json<MfaCodeRequest>,
+) -> Result<HttpResponse, AppError> {
+    let mfa = repo.get(user.id).await?
+        .ok_or_else(|| AppError::BadRequest("Start enrollment before confirming".into()))?;
+
+    if mfa.enabled_at.is_some() {
+        return Err(AppError::Conflict("Two-factor authentication is already enabled".into()));
+    }
+
+    if !check_totp(repo.get_ref(), &mfa, &payload.code).await? {
+        return Err(AppError::BadRequest("Invalid authentication code".into()));
+    }
+
+    repo.enable(user.id).await?;
+    let recovery_codes = repo.regenerate_recovery_codes(user.id).await?;
+
+    tracing::info!("Two-factor authentication enabled for user: {}", user.id);
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "enabled": true,
+        "recovery_codes": recovery_codes
+    })))
+}
+
+async fn disable(
+    users: web::Data<dyn UserRepository>,
+    repo: web::Data<dyn MfaRepository>,
+    user: AuthenticatedUser,
+    payload: web::Json<DisableMfaRequest>,
+) -> Result<HttpResponse, AppError> {
+    let account = find_user(users.get_ref(), user.id).await?;
+    let mfa = get_enabled_mfa(repo.get_ref(), user.id).await?;
+
+    let password_ok = verify(&payload.password, &account.password_hash)
+        .map_err(|e| AppError::internal("Password verification error", e))?;
+
+    if !password_ok {
+        return Err(AppError::Unauthorized("Invalid credentials".into()));
+    }
+
+    let code_ok = check_totp(repo.get_ref(), &mfa, &payload.code).await?
+        || repo.consume_recovery_code(user.id, &payload.code).await?;
+
+    if !code_ok {
+        return Err(AppError::BadRequest("Invalid authentication code".into()));
+    }
+
+    repo.disable(user.id).await?;
+
+    tracing::info!("Two-factor authentication disabled for user: {}", user.id);
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "enabled": false
+    })))
+}
+
+async fn regenerate_recovery_codes(
+    repo: web::Data<dyn MfaRepository>,
+    user: AuthenticatedUser,
+    payload: web::Json<MfaCodeRequest>,
+) -> Result<HttpResponse, AppError> {
+    let mfa = get_enabled_mfa(repo.get_ref(), user.id).await?;
+
+    if !check_totp(repo.get_ref(), &mfa, &payload.code).await? {
+        return Err(AppError::BadRequest("Invalid authentication code".into()));
+    }
+
+    let recovery_codes = repo.regenerate_recovery_codes(user.id).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "recovery_codes": recovery_codes
+    })))
+}
+
+// Second login step: exchange the mfa_token from /login plus a code for a real session
+async fn verify_login(
+    users: web::Data<dyn UserRepository>,
+    sessions: web::Data<dyn SessionRepository>,
+    repo: web::Data<dyn MfaRepository>,
+    config: web::Data<AppConfig>,
+    keys: web::Data<JwtKeys>,
+    req: HttpRequest,
+    payload: web::Json<MfaLoginRequest>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = verify_mfa_token(&keys, &payload.mfa_token).map_err(|e| AppError::Unauthorized(e.into()))?;
+    let mut user = find_user(users.get_ref(), user_id).await?;
+
+    // Second-factor failures count towards the same lockout as wrong passwords
+    if let Some(retry_after) = user.lockout_remaining() {
+        return Err(AppError::RateLimited {
+            message: "Too many failed login attempts, try again later".to_string(),
+            retry_after: retry_after as u64,
+        });
+    }
+
+    let mfa = get_enabled_mfa(repo.get_ref(), user_id).await?;
+
+    let accepted = match (&payload.code, &payload.recovery_code) {
+        (Some(code), _) => check_totp(repo.get_ref(), &mfa, code).await?,
+        (None, Some(recovery_code)) => {
+            let used = repo.consume_recovery_code(user_id, recovery_code).await?;
+            if used {
+                tracing::info!("Recovery code used to log in user: {}", user_id);
+            }
+            used
+        }
+        (None, None) => return Err(AppError::BadRequest("Provide a code or recovery_code".into())),
+    };
+
+    if !accepted {
+        tracing::warn!("Invalid second factor for user: {}", user_id);
+        users.record_failed_login(user_id).await?;
+        return Err(AppError::Unauthorized("Invalid authentication code".into()));
+    }
+
+    let tokens = start_session(sessions.get_ref(), users.get_ref(), &config, &keys, &req, &user).await?;
+    user.password_hash = String::from("***");
+
+    tracing::info!("Successful two-factor login for user: {}", user.username);
+    Ok(HttpResponse::Ok().json(LoginResponse { tokens, user }))
+}
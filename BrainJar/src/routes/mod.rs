@@ -1,24 +1,66 @@
 use actix_web::{web, HttpResponse};
 
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::jwt::JwtKeys;
+use crate::mailer::Mailer;
+
+pub mod admin;
 pub mod auth;
+pub mod mfa;
+pub mod tokens;
+pub mod enhanced_problems;
 pub mod problems;
+pub mod search;
 pub mod streaks;
+pub mod leaderboards;
+pub mod tags;
+pub mod transfer;
 pub mod characters;
 pub mod friends_simple;
 pub mod chat;
 pub mod messages_simple;
-// pub mod enhanced_problems; // Disabled until database is updated
 
 async fn health_check() -> HttpResponse {
     HttpResponse::Ok().json(serde_json::json!({ "status": "ok" }))
 }
 
+// Public keys other services use to verify BrainJar tokens. Empty while signing with a
+// shared secret.
+async fn jwks(keys: web::Data<JwtKeys>) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header(("Cache-Control", "public, max-age=300"))
+        .json(keys.jwks())
+}
+
+// Only the in-memory mail transport keeps an outbox, so this is a 404 for SMTP/file delivery.
+// Always 404 in production since it exposes reset and verification links.
+async fn dev_outbox(config: web::Data<AppConfig>, mailer: web::Data<dyn Mailer>) -> Result<HttpResponse, AppError> {
+    if config.is_production() {
+        return Err(AppError::NotFound("Route not found".into()));
+    }
+
+    let emails = mailer
+        .outbox()
+        .ok_or_else(|| AppError::NotFound("Outbox not available".into()))?;
+
+    Ok(HttpResponse::Ok().json(emails))
+}
+
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.route("/health", web::get().to(health_check))
+        .route("/.well-known/jwks.json", web::get().to(jwks))
+        .route("/api/dev/outbox", web::get().to(dev_outbox))
         .configure(auth::config)
+        .configure(admin::config)
+        .configure(tokens::config)
+        .configure(enhanced_problems::config)
+        .configure(transfer::config)
         .configure(problems::config)
-        // .configure(enhanced_problems::config) // Disabled until database is updated
+        .configure(search::config)
+        .configure(tags::config)
         .configure(streaks::config)
+        .configure(leaderboards::config)
         .configure(characters::config)
         .configure(friends_simple::configure_friends_routes)
         .configure(chat::config)
@@ -1,10 +1,20 @@
-use actix_web::{web, HttpResponse, Error};
-use crate::models::problem::{Problem, ProblemResponse, CreateProblem, UpdateProblemStatus};
-use crate::models::streak::Streak;
+use actix_web::{web, HttpResponse};
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::models::problem::{
+    CreateProblem, FeedbackWithAuthor, Problem, ProblemFeedbackInput, ProblemListParams, ProblemResponse,
+    ProblemRevision, RevisionDiff, RevisionDiffParams, UpdateProblemStatus, UpdateProblemVisibility,
+};
+use crate::models::solution::{CreateSolution, ProblemSolution, SolutionListQuery, SolutionSort, SolutionVoteInput};
+use crate::models::tag::{SetCategoriesRequest, SetTagsRequest};
 use crate::middleware::AuthenticatedUser;
-use sqlx::PgPool;
+use crate::repository::listing::{ListingScope, ProblemListing};
+use crate::models::activity::ActivityKind;
+use crate::repository::{
+    ActivityRepository, ProblemRepository, ResourceRepository, SolutionRepository, StreakRepository, TagRepository,
+};
+use crate::routes::tags::parse_tags;
 use uuid::Uuid;
-use chrono::Utc;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
@@ -12,43 +22,103 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("", web::post().to(create_problem))
             .route("", web::get().to(get_problems))
             .route("/community", web::get().to(get_community_problems))
+            .route("/solved", web::get().to(get_solved_problems))
+            .route("/categories", web::get().to(get_problem_categories))
+            .route("/stats", web::get().to(get_problem_stats))
             .route("/{id}", web::get().to(get_problem))
             .route("/{id}", web::put().to(update_problem))
             .route("/{id}", web::delete().to(delete_problem))
             .route("/{id}/solve", web::patch().to(mark_problem_solved))
+            .route("/{id}/visibility", web::patch().to(set_problem_visibility))
+            .route("/{id}/tags", web::put().to(set_problem_tags))
+            .route("/{id}/categories", web::put().to(set_problem_categories))
             .route("/{id}/feedback", web::post().to(submit_problem_feedback))
             .route("/{id}/feedback", web::get().to(get_problem_feedback))
             .route("/{id}/responses", web::get().to(get_problem_responses))
-            .route("/solved", web::get().to(get_solved_problems))
-            .route("/categories", web::get().to(get_problem_categories))
-            .route("/stats", web::get().to(get_problem_stats))
+            .route("/{id}/revisions", web::get().to(get_problem_revisions))
+            .route("/{id}/revisions/diff", web::get().to(diff_problem_revisions))
+            .route("/{id}/revisions/{revision}", web::get().to(get_problem_revision))
+            .route("/{id}/revisions/{revision}/restore", web::post().to(restore_problem_revision))
+            .route("/{id}/solutions", web::post().to(submit_solution))
+            .route("/{id}/solutions", web::get().to(get_problem_solutions))
+            .route("/{id}/solutions/{solution_id}", web::put().to(update_solution))
+            .route("/{id}/solutions/{solution_id}", web::delete().to(delete_solution))
+            .route("/{id}/solutions/{solution_id}/vote", web::put().to(vote_solution))
+            .route("/{id}/solutions/{solution_id}/accept", web::post().to(accept_solution))
+            .route("/{id}/solutions/{solution_id}/accept", web::delete().to(unaccept_solution))
+    )
+    .service(
+        web::scope("/api/me")
+            .route("/solved", web::get().to(get_my_solutions))
     );
 }
 
+// Tags and extra categories sent along with a create or update, checked before the
+// problem is written so a bad one doesn't leave the edit half applied
+struct Taxonomy {
+    tags: Option<Vec<String>>,
+    category_ids: Option<Vec<Uuid>>,
+}
+
+impl Taxonomy {
+    async fn parse(resources: &dyn ResourceRepository, problem: &CreateProblem) -> Result<Self, AppError> {
+        let tags = problem.tags.as_deref().map(parse_tags).transpose()?;
+        let category_ids = match problem.category_ids.as_deref() {
+            None => None,
+            Some(ids) => Some(parse_category_ids(resources, ids).await?),
+        };
+
+        Ok(Taxonomy { tags, category_ids })
+    }
+
+    // Returns the problem's tags after the change
+    async fn apply(
+        &self,
+        tags: &dyn TagRepository,
+        resources: &dyn ResourceRepository,
+        problem: &Problem,
+    ) -> Result<Option<Vec<String>>, AppError> {
+        if let Some(category_ids) = &self.category_ids {
+            resources.set_problem_categories(problem.id, category_ids).await?;
+        }
+        match &self.tags {
+            Some(names) => Ok(Some(tags.set_problem_tags(problem.id, names).await?)),
+            None => Ok(problem.tags.clone()),
+        }
+    }
+}
+
+// Deduplicated, and every one has to be in the catalog
+async fn parse_category_ids(resources: &dyn ResourceRepository, ids: &[Uuid]) -> Result<Vec<Uuid>, AppError> {
+    let mut unique: Vec<Uuid> = Vec::with_capacity(ids.len());
+    for id in ids {
+        if unique.contains(id) {
+            continue;
+        }
+        if resources.find_category(*id).await?.is_none() {
+            return Err(AppError::validation(
+                "Invalid categories",
+                serde_json::json!({ "category_ids": format!("unknown category {}", id) }),
+            ));
+        }
+        unique.push(*id);
+    }
+    Ok(unique)
+}
+
 async fn create_problem(
-    pool: web::Data<PgPool>,
+    problems: web::Data<dyn ProblemRepository>,
+    tags: web::Data<dyn TagRepository>,
+    resources: web::Data<dyn ResourceRepository>,
+    activity: web::Data<dyn ActivityRepository>,
+    config: web::Data<AppConfig>,
     user: AuthenticatedUser,
     problem: web::Json<CreateProblem>,
-) -> Result<HttpResponse, Error> {
-    let new_problem = sqlx::query_as::<_, Problem>(
-        "INSERT INTO problems (id, title, description, category, user_id, created_at, documentation_links, video_references, difficulty_level, tags, solved)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
-         RETURNING *"
-    )
-    .bind(Uuid::new_v4())
-    .bind(&problem.title)
-    .bind(&problem.description)
-    .bind(&problem.category)
-    .bind(user.id)
-    .bind(Utc::now())
-    .bind(None::<Vec<String>>) // documentation_links as empty
-    .bind(None::<Vec<String>>) // video_references as empty
-    .bind(None::<String>) // difficulty_level as null
-    .bind(None::<Vec<String>>) // tags as empty
-    .bind(false) // solved defaults to false
-    .fetch_one(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+) -> Result<HttpResponse, AppError> {
+    let taxonomy = Taxonomy::parse(resources.get_ref(), &problem).await?;
+    let new_problem = problems.create(user.id, &problem).await?;
+    let problem_tags = taxonomy.apply(tags.get_ref(), resources.get_ref(), &new_problem).await?;
+    activity.record(user.id, ActivityKind::ProblemCreated, 1, &config.streaks).await?;
 
     // Convert to simplified response format
     let response = ProblemResponse {
@@ -57,206 +127,187 @@ async fn create_problem(
         description: new_problem.description,
         category: new_problem.category,
         user_id: new_problem.user_id,
-        solved: new_problem.solved.unwrap_or(false),
+        solved: new_problem.solved,
+        visibility: new_problem.visibility,
+        difficulty_level: new_problem.difficulty_level,
+        tags: problem_tags.unwrap_or_default(),
         created_at: new_problem.created_at,
     };
 
     Ok(HttpResponse::Created().json(response))
 }
 
+// The caller's own problems, filtered and paginated (see ProblemListParams)
 async fn get_problems(
-    pool: web::Data<PgPool>,
+    problems: web::Data<dyn ProblemRepository>,
     user: AuthenticatedUser,
-) -> Result<HttpResponse, Error> {
-    let problems = sqlx::query_as::<_, Problem>(
-        "SELECT * FROM problems WHERE user_id = $1 ORDER BY created_at DESC"
-    )
-    .bind(user.id)
-    .fetch_all(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    params: web::Query<ProblemListParams>,
+) -> Result<HttpResponse, AppError> {
+    let listing = ProblemListing::from_params(ListingScope::Owner(user.id), &params)?;
+    let page = problems.list(&listing).await?;
 
-    // Convert to simplified response format
-    let problem_responses: Vec<ProblemResponse> = problems
-        .into_iter()
-        .map(|p| ProblemResponse {
-            id: p.id,
-            title: p.title,
-            description: p.description,
-            category: p.category,
-            user_id: p.user_id,
-            solved: p.solved.unwrap_or(false),
-            created_at: p.created_at,
-        })
-        .collect();
-
-    Ok(HttpResponse::Ok().json(problem_responses))
+    Ok(HttpResponse::Ok().json(page))
 }
 
 async fn get_problem(
-    pool: web::Data<PgPool>,
+    problems: web::Data<dyn ProblemRepository>,
     path: web::Path<Uuid>,
     user: AuthenticatedUser,
-) -> Result<HttpResponse, Error> {
-    let problem = sqlx::query_as::<_, Problem>(
-        "SELECT * FROM problems WHERE id = $1 AND user_id = $2"
-    )
-    .bind(path.into_inner())
-    .bind(user.id)
-    .fetch_optional(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?
-    .ok_or_else(|| actix_web::error::ErrorNotFound("Problem not found"))?;
+) -> Result<HttpResponse, AppError> {
+    let problem = problems
+        .find_for_owner(path.into_inner(), user.id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Problem not found".into()))?;
 
     Ok(HttpResponse::Ok().json(problem))
 }
 
 async fn update_problem(
-    pool: web::Data<PgPool>,
+    problems: web::Data<dyn ProblemRepository>,
+    tags: web::Data<dyn TagRepository>,
+    resources: web::Data<dyn ResourceRepository>,
     path: web::Path<Uuid>,
     user: AuthenticatedUser,
     problem: web::Json<CreateProblem>,
-) -> Result<HttpResponse, Error> {
-    let updated_problem = sqlx::query_as::<_, Problem>(
-        "UPDATE problems 
-         SET title = $1, description = $2, category = $3
-         WHERE id = $4 AND user_id = $5 
-         RETURNING *"
-    )
-    .bind(&problem.title)
-    .bind(&problem.description)
-    .bind(&problem.category)
-    .bind(path.into_inner())
-    .bind(user.id)
-    .fetch_optional(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?
-    .ok_or_else(|| actix_web::error::ErrorNotFound("Problem not found"))?;
+) -> Result<HttpResponse, AppError> {
+    let taxonomy = Taxonomy::parse(resources.get_ref(), &problem).await?;
+    let mut updated_problem = problems
+        .update(path.into_inner(), user.id, &problem, None)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Problem not found".into()))?;
+    updated_problem.tags = taxonomy.apply(tags.get_ref(), resources.get_ref(), &updated_problem).await?;
 
     Ok(HttpResponse::Ok().json(updated_problem))
 }
 
-async fn delete_problem(
-    pool: web::Data<PgPool>,
+async fn set_problem_tags(
+    problems: web::Data<dyn ProblemRepository>,
+    tags: web::Data<dyn TagRepository>,
     path: web::Path<Uuid>,
     user: AuthenticatedUser,
-) -> Result<HttpResponse, Error> {
-    let result = sqlx::query!(
-        "DELETE FROM problems WHERE id = $1 AND user_id = $2",
-        path.into_inner(),
-        user.id
-    )
-    .execute(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    payload: web::Json<SetTagsRequest>,
+) -> Result<HttpResponse, AppError> {
+    let names = parse_tags(&payload.tags)?;
+    let problem = problems
+        .find_for_owner(path.into_inner(), user.id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Problem not found".into()))?;
 
-    if result.rows_affected() == 0 {
-        return Err(actix_web::error::ErrorNotFound("Problem not found"));
+    let tags = tags.set_problem_tags(problem.id, &names).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "problem_id": problem.id, "tags": tags })))
+}
+
+// The catalog categories a problem is listed under, besides its own category
+async fn set_problem_categories(
+    problems: web::Data<dyn ProblemRepository>,
+    resources: web::Data<dyn ResourceRepository>,
+    path: web::Path<Uuid>,
+    user: AuthenticatedUser,
+    payload: web::Json<SetCategoriesRequest>,
+) -> Result<HttpResponse, AppError> {
+    let category_ids = parse_category_ids(resources.get_ref(), &payload.category_ids).await?;
+    let problem = problems
+        .find_for_owner(path.into_inner(), user.id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Problem not found".into()))?;
+
+    let categories = resources.set_problem_categories(problem.id, &category_ids).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "problem_id": problem.id, "categories": categories })))
+}
+
+async fn delete_problem(
+    problems: web::Data<dyn ProblemRepository>,
+    path: web::Path<Uuid>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    if !problems.delete(path.into_inner(), user.id).await? {
+        return Err(AppError::NotFound("Problem not found".into()));
     }
 
     Ok(HttpResponse::NoContent().finish())
 }
 
+// Marking your own problem solved is bookkeeping, it doesn't count towards a streak
 async fn mark_problem_solved(
-    pool: web::Data<PgPool>,
+    problems: web::Data<dyn ProblemRepository>,
     path: web::Path<Uuid>,
     user: AuthenticatedUser,
     payload: web::Json<UpdateProblemStatus>,
-) -> Result<HttpResponse, Error> {
-    let problem = sqlx::query_as::<_, Problem>(
-        "UPDATE problems 
-         SET solved = $1
-         WHERE id = $2 AND user_id = $3 
-         RETURNING *"
-    )
-    .bind(payload.solved)
-    .bind(path.into_inner())
-    .bind(user.id)
-    .fetch_optional(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?
-    .ok_or_else(|| actix_web::error::ErrorNotFound("Problem not found"))?;
-
-    // Update streak if problem was solved
-    if payload.solved {
-        let _streak = Streak::update_for_problem_solve(&**pool, user.id)
-            .await
-            .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-    }
+) -> Result<HttpResponse, AppError> {
+    let problem = problems
+        .set_solved(path.into_inner(), user.id, payload.solved)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Problem not found".into()))?;
 
     Ok(HttpResponse::Ok().json(problem))
 }
 
+async fn set_problem_visibility(
+    problems: web::Data<dyn ProblemRepository>,
+    path: web::Path<Uuid>,
+    user: AuthenticatedUser,
+    payload: web::Json<UpdateProblemVisibility>,
+) -> Result<HttpResponse, AppError> {
+    let problem = problems
+        .set_visibility(path.into_inner(), user.id, payload.visibility)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Problem not found".into()))?;
+
+    Ok(HttpResponse::Ok().json(problem))
+}
+
+// The problem if `viewer` may see it. Hidden problems are reported as missing so their
+// existence isn't revealed.
+pub(crate) async fn visible_problem(
+    problems: &dyn ProblemRepository,
+    problem_id: Uuid,
+    viewer: Uuid,
+) -> Result<Problem, AppError> {
+    problems
+        .find_visible(problem_id, viewer)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Problem not found".into()))
+}
+
 async fn get_problem_categories(
-    pool: web::Data<PgPool>,
-) -> Result<HttpResponse, Error> {
-    let categories = sqlx::query!(
-        "SELECT DISTINCT category, COUNT(*) as problem_count
-         FROM problems 
-         GROUP BY category 
-         ORDER BY problem_count DESC, category ASC"
-    )
-    .fetch_all(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-
-    let categories: Vec<_> = categories
-        .into_iter()
-        .map(|row| serde_json::json!({
-            "name": row.category,
-            "count": row.problem_count.unwrap_or(0)
-        }))
-        .collect();
+    problems: web::Data<dyn ProblemRepository>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let categories = problems.categories(user.id).await?;
 
     Ok(HttpResponse::Ok().json(categories))
 }
 
+// The caller's own problems marked solved, same parameters as get_problems
 async fn get_solved_problems(
-    pool: web::Data<PgPool>,
+    problems: web::Data<dyn ProblemRepository>,
     user: AuthenticatedUser,
-) -> Result<HttpResponse, Error> {
-    let solved_problems = sqlx::query_as::<_, Problem>(
-        "SELECT * FROM problems 
-         WHERE user_id = $1 AND solved = true 
-         ORDER BY created_at DESC"
-    )
-    .bind(user.id)
-    .fetch_all(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    params: web::Query<ProblemListParams>,
+) -> Result<HttpResponse, AppError> {
+    let mut listing = ProblemListing::from_params(ListingScope::Owner(user.id), &params)?;
+    listing.solved = Some(true);
+    let page = problems.list(&listing).await?;
 
-    Ok(HttpResponse::Ok().json(solved_problems))
+    Ok(HttpResponse::Ok().json(page))
 }
 
 async fn get_problem_stats(
-    pool: web::Data<PgPool>,
+    problems: web::Data<dyn ProblemRepository>,
     user: AuthenticatedUser,
-) -> Result<HttpResponse, Error> {
-    let stats = sqlx::query!(
-        "SELECT 
-            COUNT(*) as total_problems,
-            COUNT(CASE WHEN solved = true THEN 1 END) as solved_problems,
-            COUNT(CASE WHEN difficulty_level = 'Easy' THEN 1 END) as easy_problems,
-            COUNT(CASE WHEN difficulty_level = 'Medium' THEN 1 END) as medium_problems,
-            COUNT(CASE WHEN difficulty_level = 'Hard' THEN 1 END) as hard_problems,
-            COUNT(DISTINCT category) as categories_explored
-         FROM problems 
-         WHERE user_id = $1",
-        user.id
-    )
-    .fetch_one(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+) -> Result<HttpResponse, AppError> {
+    let stats = problems.stats(user.id).await?;
 
     let response = serde_json::json!({
-        "total_problems": stats.total_problems.unwrap_or(0),
-        "solved_problems": stats.solved_problems.unwrap_or(0),
-        "easy_problems": stats.easy_problems.unwrap_or(0),
-        "medium_problems": stats.medium_problems.unwrap_or(0),
-        "hard_problems": stats.hard_problems.unwrap_or(0),
-        "categories_explored": stats.categories_explored.unwrap_or(0),
-        "success_rate": if stats.total_problems.unwrap_or(0) > 0 {
-            (stats.solved_problems.unwrap_or(0) as f64 / stats.total_problems.unwrap_or(1) as f64) * 100.0
+        "total_problems": stats.total_problems,
+        "solved_problems": stats.solved_problems,
+        "easy_problems": stats.easy_problems,
+        "medium_problems": stats.medium_problems,
+        "hard_problems": stats.hard_problems,
+        "categories_explored": stats.categories_explored,
+        "success_rate": if stats.total_problems > 0 {
+            (stats.solved_problems as f64 / stats.total_problems as f64) * 100.0
         } else {
             0.0
         }
@@ -267,209 +318,629 @@ async fn get_problem_stats(
 
 // Get community problems (problems created by other users)
 async fn get_community_problems(
-    pool: web::Data<PgPool>,
+    problems: web::Data<dyn ProblemRepository>,
     user: AuthenticatedUser,
-) -> Result<HttpResponse, Error> {
-    let problems = sqlx::query!(
-        "SELECT p.id, p.title, p.description, p.category, p.user_id, p.created_at, p.solved, u.username as created_by 
-         FROM problems p
-         JOIN users u ON p.user_id = u.id
-         WHERE p.user_id != $1 
-         ORDER BY p.created_at DESC",
-        user.id
-    )
-    .fetch_all(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-
-    let problems_json: Vec<serde_json::Value> = problems
-        .into_iter()
-        .map(|row| serde_json::json!({
-            "id": row.id,
-            "title": row.title,
-            "description": row.description,
-            "category": row.category,
-            "user_id": row.user_id,
-            "solved": row.solved.unwrap_or(false),
-            "created_at": row.created_at,
-            "created_by": row.created_by
-        }))
-        .collect();
-
-    Ok(HttpResponse::Ok().json(problems_json))
+    params: web::Query<ProblemListParams>,
+) -> Result<HttpResponse, AppError> {
+    let listing = ProblemListing::from_params(ListingScope::Community(user.id), &params)?;
+    let page = problems.list(&listing).await?;
+
+    Ok(HttpResponse::Ok().json(page))
 }
 
 // Submit feedback for a problem
 async fn submit_problem_feedback(
-    pool: web::Data<PgPool>,
+    problems: web::Data<dyn ProblemRepository>,
+    activity: web::Data<dyn ActivityRepository>,
+    config: web::Data<AppConfig>,
     path: web::Path<Uuid>,
     user: AuthenticatedUser,
     feedback: web::Json<ProblemFeedbackInput>,
-) -> Result<HttpResponse, Error> {
+) -> Result<HttpResponse, AppError> {
     let problem_id = path.into_inner();
 
-    // Check if user already submitted feedback for this problem
-    let existing_feedback = sqlx::query!(
-        "SELECT id FROM problem_feedback WHERE problem_id = $1 AND user_id = $2",
-        problem_id,
-        user.id
-    )
-    .fetch_optional(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-
-    if existing_feedback.is_some() {
-        // Update existing feedback
-        let updated_feedback = sqlx::query!(
-            "UPDATE problem_feedback 
-             SET rating = $1, feedback = $2, is_helpful = $3, created_at = NOW()
-             WHERE problem_id = $4 AND user_id = $5
-             RETURNING id, created_at",
-            feedback.rating,
-            feedback.feedback.as_deref(),
-            feedback.is_helpful,
-            problem_id,
-            user.id
-        )
-        .fetch_one(&**pool)
-        .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-
-        return Ok(HttpResponse::Ok().json(serde_json::json!({
-            "id": updated_feedback.id,
-            "problem_id": problem_id,
-            "user_id": user.id,
-            "rating": feedback.rating,
-            "feedback": feedback.feedback,
-            "is_helpful": feedback.is_helpful,
-            "created_at": updated_feedback.created_at
-        })));
+    if feedback.rating.is_some_and(|rating| !(1..=5).contains(&rating)) {
+        return Err(AppError::validation(
+            "Invalid feedback",
+            serde_json::json!({ "rating": "must be between 1 and 5" }),
+        ));
     }
 
-    // Create new feedback
-    let new_feedback = sqlx::query!(
-        "INSERT INTO problem_feedback (problem_id, user_id, rating, feedback, is_helpful)
-         VALUES ($1, $2, $3, $4, $5)
-         RETURNING id, created_at",
-        problem_id,
-        user.id,
-        feedback.rating,
-        feedback.feedback.as_deref(),
-        feedback.is_helpful
-    )
-    .fetch_one(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    visible_problem(problems.get_ref(), problem_id, user.id).await?;
 
-    Ok(HttpResponse::Created().json(serde_json::json!({
-        "id": new_feedback.id,
-        "problem_id": problem_id,
-        "user_id": user.id,
-        "rating": feedback.rating,
-        "feedback": feedback.feedback,
-        "is_helpful": feedback.is_helpful,
-        "created_at": new_feedback.created_at
-    })))
+    // Replaces any feedback the user already left on this problem
+    let (feedback, created) = problems.upsert_feedback(problem_id, user.id, &feedback).await?;
+
+    if created {
+        activity.record(user.id, ActivityKind::FeedbackGiven, 1, &config.streaks).await?;
+        Ok(HttpResponse::Created().json(feedback))
+    } else {
+        Ok(HttpResponse::Ok().json(feedback))
+    }
+}
+
+fn feedback_json(row: FeedbackWithAuthor) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.id,
+        "user_id": row.user_id,
+        "user_name": row.user_name,
+        "user_avatar": row.user_avatar,
+        "rating": row.rating,
+        "feedback": row.feedback,
+        "is_helpful": row.is_helpful,
+        "created_at": row.created_at
+    })
 }
 
 // Get feedback for a problem
 async fn get_problem_feedback(
-    pool: web::Data<PgPool>,
+    problems: web::Data<dyn ProblemRepository>,
     path: web::Path<Uuid>,
-) -> Result<HttpResponse, Error> {
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
     let problem_id = path.into_inner();
+    visible_problem(problems.get_ref(), problem_id, user.id).await?;
 
-    let feedback = sqlx::query!(
-        "SELECT pf.*, u.username as user_name
-         FROM problem_feedback pf
-         JOIN users u ON pf.user_id = u.id
-         WHERE pf.problem_id = $1
-         ORDER BY pf.created_at DESC",
-        problem_id
-    )
-    .fetch_all(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-
-    let feedback_json: Vec<serde_json::Value> = feedback
-        .into_iter()
-        .map(|row| serde_json::json!({
-            "id": row.id,
-            "user_id": row.user_id,
-            "user_name": row.user_name,
-            "rating": row.rating,
-            "feedback": row.feedback,
-            "is_helpful": row.is_helpful,
-            "created_at": row.created_at
-        }))
-        .collect();
+    let feedback = problems.list_feedback(problem_id).await?;
+
+    let feedback_json: Vec<serde_json::Value> = feedback.into_iter().map(feedback_json).collect();
 
     Ok(HttpResponse::Ok().json(feedback_json))
 }
 
 // Get all responses (feedback + solutions) for a problem (for problem creators)
 async fn get_problem_responses(
-    pool: web::Data<PgPool>,
+    problems: web::Data<dyn ProblemRepository>,
+    solutions: web::Data<dyn SolutionRepository>,
     path: web::Path<Uuid>,
     user: AuthenticatedUser,
-) -> Result<HttpResponse, Error> {
+) -> Result<HttpResponse, AppError> {
     let problem_id = path.into_inner();
 
-    // Check if the user is the owner of the problem
-    let problem_owner = sqlx::query!(
-        "SELECT user_id FROM problems WHERE id = $1",
-        problem_id
+    require_problem_owner(
+        problems.get_ref(),
+        problem_id,
+        user.id,
+        "Not authorized to view responses for this problem",
     )
-    .fetch_optional(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?
-    .ok_or_else(|| actix_web::error::ErrorNotFound("Problem not found"))?;
-
-    if problem_owner.user_id != user.id {
-        return Err(actix_web::error::ErrorForbidden("Not authorized to view responses for this problem"));
-    }
+    .await?;
 
     // Get feedback responses
-    let feedback = sqlx::query!(
-        "SELECT pf.*, u.username as user_name, u.avatar_url
-         FROM problem_feedback pf
-         JOIN users u ON pf.user_id = u.id
-         WHERE pf.problem_id = $1
-         ORDER BY pf.created_at DESC",
-        problem_id
-    )
-    .fetch_all(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    let feedback = problems.list_feedback(problem_id).await?;
+    let solutions = solutions.list_for_problem(problem_id, SolutionSort::Top).await?;
 
-    // For now, we'll just return feedback. Solutions can be added later if needed
-    let feedback_len = feedback.len();
+    let total_responses = feedback.len() + solutions.len();
     let responses = serde_json::json!({
         "problem_id": problem_id,
         "feedback": feedback
             .into_iter()
-            .map(|row| serde_json::json!({
-                "id": row.id,
-                "user_id": row.user_id,
-                "user_name": row.user_name,
-                "user_avatar": row.avatar_url,
-                "rating": row.rating,
-                "feedback": row.feedback,
-                "is_helpful": row.is_helpful,
-                "created_at": row.created_at,
-                "type": "feedback"
-            }))
+            .map(|row| {
+                let mut json = feedback_json(row);
+                json["type"] = "feedback".into();
+                json
+            })
             .collect::<Vec<_>>(),
-        "solutions": [], // Placeholder for future solutions functionality
-        "total_responses": feedback_len
+        "solutions": solutions,
+        "total_responses": total_responses
     });
 
     Ok(HttpResponse::Ok().json(responses))
 }
 
-#[derive(serde::Deserialize)]
-pub struct ProblemFeedbackInput {
-    pub rating: Option<i32>,
-    pub feedback: Option<String>,
-    pub is_helpful: Option<bool>,
-}
\ No newline at end of file
+fn validate_solution(solution: &CreateSolution) -> Result<(), AppError> {
+    if solution.solution_text.trim().is_empty() {
+        return Err(AppError::validation(
+            "Invalid solution",
+            serde_json::json!({ "solution_text": "must not be empty" }),
+        ));
+    }
+    Ok(())
+}
+
+// Submit a solution. It only counts as a solve for the solver's streak once the problem's
+// author accepts it.
+async fn submit_solution(
+    problems: web::Data<dyn ProblemRepository>,
+    solutions: web::Data<dyn SolutionRepository>,
+    activity: web::Data<dyn ActivityRepository>,
+    config: web::Data<AppConfig>,
+    path: web::Path<Uuid>,
+    user: AuthenticatedUser,
+    solution: web::Json<CreateSolution>,
+) -> Result<HttpResponse, AppError> {
+    let problem_id = path.into_inner();
+    validate_solution(&solution)?;
+
+    visible_problem(problems.get_ref(), problem_id, user.id).await?;
+
+    let solution = solutions.create(problem_id, user.id, &solution).await?;
+    activity.record(user.id, ActivityKind::SolutionPosted, 1, &config.streaks).await?;
+
+    Ok(HttpResponse::Created().json(solution))
+}
+
+// Accepted solution first, then by score (?sort=newest or ?sort=oldest for plain recency)
+async fn get_problem_solutions(
+    problems: web::Data<dyn ProblemRepository>,
+    solutions: web::Data<dyn SolutionRepository>,
+    path: web::Path<Uuid>,
+    query: web::Query<SolutionListQuery>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let problem_id = path.into_inner();
+    visible_problem(problems.get_ref(), problem_id, user.id).await?;
+
+    let solutions = solutions.list_for_problem(problem_id, query.sort.unwrap_or_default()).await?;
+
+    Ok(HttpResponse::Ok().json(solutions))
+}
+
+// Look up a solution under its problem and make sure the caller wrote it
+async fn own_solution(
+    solutions: &dyn SolutionRepository,
+    problem_id: Uuid,
+    solution_id: Uuid,
+    user_id: Uuid,
+) -> Result<ProblemSolution, AppError> {
+    let solution = find_solution(solutions, problem_id, solution_id).await?;
+
+    if solution.user_id != user_id {
+        return Err(AppError::Forbidden("You can only change your own solutions".into()));
+    }
+
+    Ok(solution)
+}
+
+async fn update_solution(
+    solutions: web::Data<dyn SolutionRepository>,
+    path: web::Path<(Uuid, Uuid)>,
+    user: AuthenticatedUser,
+    solution: web::Json<CreateSolution>,
+) -> Result<HttpResponse, AppError> {
+    let (problem_id, solution_id) = path.into_inner();
+    validate_solution(&solution)?;
+    own_solution(solutions.get_ref(), problem_id, solution_id, user.id).await?;
+
+    let updated = solutions
+        .update(solution_id, user.id, &solution)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Solution not found".into()))?;
+
+    Ok(HttpResponse::Ok().json(updated))
+}
+
+async fn delete_solution(
+    solutions: web::Data<dyn SolutionRepository>,
+    path: web::Path<(Uuid, Uuid)>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let (problem_id, solution_id) = path.into_inner();
+    own_solution(solutions.get_ref(), problem_id, solution_id, user.id).await?;
+
+    if !solutions.delete(solution_id, user.id).await? {
+        return Err(AppError::NotFound("Solution not found".into()));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Look up a solution under its problem, whoever wrote it
+async fn find_solution(
+    solutions: &dyn SolutionRepository,
+    problem_id: Uuid,
+    solution_id: Uuid,
+) -> Result<ProblemSolution, AppError> {
+    solutions
+        .find_by_id(solution_id)
+        .await?
+        .filter(|s| s.problem_id == problem_id)
+        .ok_or_else(|| AppError::NotFound("Solution not found".into()))
+}
+
+async fn vote_solution(
+    problems: web::Data<dyn ProblemRepository>,
+    solutions: web::Data<dyn SolutionRepository>,
+    path: web::Path<(Uuid, Uuid)>,
+    user: AuthenticatedUser,
+    vote: web::Json<SolutionVoteInput>,
+) -> Result<HttpResponse, AppError> {
+    let (problem_id, solution_id) = path.into_inner();
+
+    if !(-1..=1).contains(&vote.value) {
+        return Err(AppError::validation(
+            "Invalid vote",
+            serde_json::json!({ "value": "must be 1, -1 or 0" }),
+        ));
+    }
+
+    visible_problem(problems.get_ref(), problem_id, user.id).await?;
+    let solution = find_solution(solutions.get_ref(), problem_id, solution_id).await?;
+    if solution.user_id == user.id {
+        return Err(AppError::BadRequest("You cannot vote on your own solution".into()));
+    }
+
+    let totals = solutions.vote(solution_id, user.id, vote.value).await?;
+
+    Ok(HttpResponse::Ok().json(totals))
+}
+
+// Only the problem's author decides which solution answered it
+const ACCEPT_DENIED: &str = "Only the problem's author can accept a solution";
+
+// The problem if `user_id` wrote it. Like visible_problem, problems the user can't see are
+// missing; only visible ones they don't own are forbidden with `denied`.
+async fn require_problem_owner(
+    problems: &dyn ProblemRepository,
+    problem_id: Uuid,
+    user_id: Uuid,
+    denied: &str,
+) -> Result<Problem, AppError> {
+    let problem = visible_problem(problems, problem_id, user_id).await?;
+
+    if problem.user_id != user_id {
+        return Err(AppError::Forbidden(denied.into()));
+    }
+
+    Ok(problem)
+}
+
+// Accepting replaces any previously accepted solution and marks the problem solved. It's
+// the verified solve behind streaks: the solution's author gets credit for the problem,
+// unless it's the problem's own author.
+async fn accept_solution(
+    problems: web::Data<dyn ProblemRepository>,
+    solutions: web::Data<dyn SolutionRepository>,
+    streaks: web::Data<dyn StreakRepository>,
+    config: web::Data<AppConfig>,
+    path: web::Path<(Uuid, Uuid)>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let (problem_id, solution_id) = path.into_inner();
+    require_problem_owner(problems.get_ref(), problem_id, user.id, ACCEPT_DENIED).await?;
+
+    let solution = solutions
+        .accept(problem_id, solution_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Solution not found".into()))?;
+
+    if solution.user_id != user.id {
+        streaks.record_solve(solution.user_id, problem_id, &config.streaks).await?;
+    }
+
+    Ok(HttpResponse::Ok().json(solution))
+}
+
+// Leaves the problem's solved flag alone, the owner can still change it with /solve
+async fn unaccept_solution(
+    problems: web::Data<dyn ProblemRepository>,
+    solutions: web::Data<dyn SolutionRepository>,
+    path: web::Path<(Uuid, Uuid)>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let (problem_id, solution_id) = path.into_inner();
+    require_problem_owner(problems.get_ref(), problem_id, user.id, ACCEPT_DENIED).await?;
+
+    let solution = find_solution(solutions.get_ref(), problem_id, solution_id).await?;
+    if !solution.is_accepted {
+        return Err(AppError::BadRequest("Solution is not the accepted one".into()));
+    }
+
+    solutions.unaccept(problem_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Problems the caller has submitted solutions to, most recent first
+async fn get_my_solutions(
+    solutions: web::Data<dyn SolutionRepository>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let solved = solutions.solved_by_user(user.id).await?;
+
+    Ok(HttpResponse::Ok().json(solved))
+}
+
+async fn find_revision(
+    problems: &dyn ProblemRepository,
+    problem_id: Uuid,
+    revision: i32,
+) -> Result<ProblemRevision, AppError> {
+    problems
+        .find_revision(problem_id, revision)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Revision {} not found", revision)))
+}
+
+// Every edit of a problem, newest first
+async fn get_problem_revisions(
+    problems: web::Data<dyn ProblemRepository>,
+    path: web::Path<Uuid>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let problem_id = path.into_inner();
+    visible_problem(problems.get_ref(), problem_id, user.id).await?;
+
+    let revisions = problems.revisions(problem_id).await?;
+
+    Ok(HttpResponse::Ok().json(revisions))
+}
+
+async fn get_problem_revision(
+    problems: web::Data<dyn ProblemRepository>,
+    path: web::Path<(Uuid, i32)>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let (problem_id, revision) = path.into_inner();
+    visible_problem(problems.get_ref(), problem_id, user.id).await?;
+    let revision = find_revision(problems.get_ref(), problem_id, revision).await?;
+
+    Ok(HttpResponse::Ok().json(revision))
+}
+
+// ?from=1&to=3. Either order works, `from` is shown as the before side.
+async fn diff_problem_revisions(
+    problems: web::Data<dyn ProblemRepository>,
+    path: web::Path<Uuid>,
+    params: web::Query<RevisionDiffParams>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let problem_id = path.into_inner();
+    visible_problem(problems.get_ref(), problem_id, user.id).await?;
+    let from = find_revision(problems.get_ref(), problem_id, params.from).await?;
+    let to = find_revision(problems.get_ref(), problem_id, params.to).await?;
+
+    Ok(HttpResponse::Ok().json(RevisionDiff::between(&from, &to)))
+}
+
+// Put an earlier revision's content back. This is recorded as a new revision, so the
+// history is never rewritten.
+async fn restore_problem_revision(
+    problems: web::Data<dyn ProblemRepository>,
+    path: web::Path<(Uuid, i32)>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let (problem_id, revision) = path.into_inner();
+    require_problem_owner(
+        problems.get_ref(),
+        problem_id,
+        user.id,
+        "Only the problem's author can restore a revision",
+    )
+    .await?;
+
+    let revision = find_revision(problems.get_ref(), problem_id, revision).await?;
+    let content = CreateProblem {
+        title: revision.title,
+        description: revision.description,
+        category: revision.category,
+        visibility: None,
+        difficulty_level: None,
+        documentation_links: None,
+        tags: None,
+        category_ids: None,
+    };
+
+    let restored = problems
+        .update(problem_id, user.id, &content, Some(revision.revision))
+        .await?
+        .ok_or_else(|| AppError::NotFound("Problem not found".into()))?;
+
+    Ok(HttpResponse::Ok().json(restored))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::{Method, StatusCode};
+    use serde_json::{json, Value};
+
+    use crate::test_app::{create_problem, init, register, request, send};
+
+    fn ids(page: &Value) -> Vec<String> {
+        page["problems"].as_array().unwrap().iter().map(|p| p["id"].as_str().unwrap().to_string()).collect()
+    }
+
+    #[actix_web::test]
+    async fn hidden_problems_are_not_found_rather_than_forbidden() {
+        let app = init().await;
+        let ada = register(&app, "ada").await;
+        let bob = register(&app, "bob").await;
+        let private = create_problem(&app, &ada, json!({ "visibility": "private" })).await;
+
+        for (method, path) in [
+            (Method::GET, format!("/api/problems/{}/responses", private)),
+            (Method::GET, format!("/api/problems/{}/solutions", private)),
+            (Method::GET, format!("/api/problems/{}/feedback", private)),
+            (Method::GET, format!("/api/problems/{}/revisions", private)),
+            (Method::POST, format!("/api/problems/{}/revisions/1/restore", private)),
+        ] {
+            let (status, _) = send(&app, request(method.clone(), &path, Some(&bob.token))).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{} {}", method, path);
+        }
+
+        let (status, _) = send(
+            &app,
+            request(Method::POST, &format!("/api/problems/{}/solutions", private), Some(&bob.token))
+                .set_json(json!({ "solution_text": "peek" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = send(&app, request(Method::GET, &format!("/api/problems/{}/responses", private), Some(&ada.token))).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn owner_only_actions_on_visible_problems_are_forbidden() {
+        let app = init().await;
+        let ada = register(&app, "ada").await;
+        let bob = register(&app, "bob").await;
+        let public = create_problem(&app, &ada, json!({})).await;
+
+        let (status, _) = send(&app, request(Method::GET, &format!("/api/problems/{}/responses", public), Some(&bob.token))).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, _) = send(
+            &app,
+            request(Method::POST, &format!("/api/problems/{}/revisions/1/restore", public), Some(&bob.token)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[actix_web::test]
+    async fn community_listing_skips_private_problems() {
+        let app = init().await;
+        let ada = register(&app, "ada").await;
+        let bob = register(&app, "bob").await;
+        let public = create_problem(&app, &ada, json!({ "title": "Public" })).await;
+        create_problem(&app, &ada, json!({ "title": "Private", "visibility": "private" })).await;
+        create_problem(&app, &bob, json!({ "title": "Bob's own" })).await;
+
+        let (status, page) = send(&app, request(Method::GET, "/api/problems/community", Some(&bob.token))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&page), [public.to_string()]);
+        assert_eq!(page["problems"][0]["user_id"], ada.id.to_string());
+    }
+
+    #[actix_web::test]
+    async fn listing_cursors_page_through_everything_once() {
+        let app = init().await;
+        let ada = register(&app, "ada").await;
+        for i in 0..5 {
+            create_problem(&app, &ada, json!({ "title": format!("Problem {}", i) })).await;
+        }
+
+        let (_, all) = send(&app, request(Method::GET, "/api/problems?limit=50", Some(&ada.token))).await;
+        assert_eq!(all["next_cursor"], Value::Null);
+
+        let mut seen = Vec::new();
+        let mut path = "/api/problems?limit=2".to_string();
+        loop {
+            let (status, page) = send(&app, request(Method::GET, &path, Some(&ada.token))).await;
+            assert_eq!(status, StatusCode::OK);
+            assert!(ids(&page).len() <= 2);
+            seen.extend(ids(&page));
+            match page["next_cursor"].as_str() {
+                Some(cursor) => path = format!("/api/problems?limit=2&cursor={}", cursor),
+                None => break,
+            }
+        }
+
+        assert_eq!(seen, ids(&all));
+        assert_eq!(seen.len(), 5);
+    }
+
+    #[actix_web::test]
+    async fn listing_rejects_bad_cursors() {
+        let app = init().await;
+        let ada = register(&app, "ada").await;
+        for _ in 0..2 {
+            create_problem(&app, &ada, json!({})).await;
+        }
+
+        let (status, _) = send(&app, request(Method::GET, "/api/problems?cursor=nonsense", Some(&ada.token))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (_, page) = send(&app, request(Method::GET, "/api/problems?limit=1", Some(&ada.token))).await;
+        let cursor = page["next_cursor"].as_str().unwrap();
+        let (status, body) = send(
+            &app,
+            request(Method::GET, &format!("/api/problems?sort=rating&cursor={}", cursor), Some(&ada.token)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Cursor belongs to a different sort order");
+    }
+
+    #[actix_web::test]
+    async fn listing_filters_ignore_case_and_untagged_problems_list_no_tags() {
+        let app = init().await;
+        let ada = register(&app, "ada").await;
+        let hard = create_problem(&app, &ada, json!({ "difficulty_level": "hard", "tags": ["Graphs"] })).await;
+        create_problem(&app, &ada, json!({})).await;
+
+        let (status, page) = send(&app, request(Method::GET, "/api/problems?difficulty=HARD&sort=Rating", Some(&ada.token))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&page), [hard.to_string()]);
+
+        let (_, page) = send(&app, request(Method::GET, "/api/problems?tags=graphs", Some(&ada.token))).await;
+        assert_eq!(ids(&page), [hard.to_string()]);
+
+        let (_, page) = send(&app, request(Method::GET, "/api/problems", Some(&ada.token))).await;
+        assert!(page["problems"].as_array().unwrap().iter().all(|p| p["tags"].is_array()));
+    }
+
+    #[actix_web::test]
+    async fn only_accepted_solutions_to_others_problems_count_as_solves() {
+        let app = init().await;
+        let ada = register(&app, "ada").await;
+        let bob = register(&app, "bob").await;
+        let problem = create_problem(&app, &ada, json!({})).await;
+
+        let (status, solution) = send(
+            &app,
+            request(Method::POST, &format!("/api/problems/{}/solutions", problem), Some(&bob.token))
+                .set_json(json!({ "solution_text": "use a hash map" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let solution_id = solution["id"].as_str().unwrap();
+
+        let (_, streak) = send(&app, request(Method::GET, "/api/streaks", Some(&bob.token))).await;
+        assert_eq!(streak["count"], 0);
+
+        let accept = format!("/api/problems/{}/solutions/{}/accept", problem, solution_id);
+        let (status, _) = send(&app, request(Method::POST, &accept, Some(&bob.token))).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = send(&app, request(Method::POST, &accept, Some(&ada.token))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (_, streak) = send(&app, request(Method::GET, "/api/streaks", Some(&bob.token))).await;
+        assert_eq!(streak["count"], 1);
+        assert_eq!(streak["problems_solved_today"], 1);
+
+        // Marking your own problem solved isn't a solve
+        let (status, _) = send(
+            &app,
+            request(Method::PATCH, &format!("/api/problems/{}/solve", problem), Some(&ada.token))
+                .set_json(json!({ "solved": true })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, streak) = send(&app, request(Method::GET, "/api/streaks", Some(&ada.token))).await;
+        assert_eq!(streak["count"], 0);
+    }
+
+    #[actix_web::test]
+    async fn unknown_solution_fields_are_rejected() {
+        let app = init().await;
+        let ada = register(&app, "ada").await;
+        let bob = register(&app, "bob").await;
+        let problem = create_problem(&app, &ada, json!({})).await;
+
+        let (status, _) = send(
+            &app,
+            request(Method::POST, &format!("/api/problems/{}/solutions", problem), Some(&bob.token))
+                .set_json(json!({ "solution_text": "x", "is_accepted": true })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn categories_only_count_visible_problems() {
+        let app = init().await;
+        let ada = register(&app, "ada").await;
+        let bob = register(&app, "bob").await;
+        create_problem(&app, &ada, json!({ "category": "graphs" })).await;
+        create_problem(&app, &ada, json!({ "category": "graphs", "visibility": "private" })).await;
+        create_problem(&app, &ada, json!({ "category": "secret plans", "visibility": "private" })).await;
+
+        let (status, categories) = send(&app, request(Method::GET, "/api/problems/categories", Some(&bob.token))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(categories, json!([{ "name": "graphs", "count": 1 }]));
+
+        let (_, categories) = send(&app, request(Method::GET, "/api/problems/categories", Some(&ada.token))).await;
+        assert_eq!(categories, json!([{ "name": "graphs", "count": 2 }, { "name": "secret plans", "count": 1 }]));
+    }
+}
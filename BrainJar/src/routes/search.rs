@@ -0,0 +1,74 @@
+use actix_web::{web, HttpResponse};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::middleware::AuthenticatedUser;
+use crate::models::search::{SearchParams, SearchQuery, SearchResponse, SearchResultType};
+use crate::repository::SearchRepository;
+
+const MAX_QUERY_LENGTH: usize = 200;
+const DEFAULT_LIMIT: i64 = 20;
+const MAX_LIMIT: i64 = 100;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/search", web::get().to(search));
+}
+
+fn parse_params(params: &SearchParams, viewer: Uuid) -> Result<SearchQuery, AppError> {
+    let text = params.q.as_deref().unwrap_or_default().trim();
+    if text.is_empty() {
+        return Err(AppError::validation("Invalid search", serde_json::json!({ "q": "must not be empty" })));
+    }
+    if text.chars().count() > MAX_QUERY_LENGTH {
+        return Err(AppError::validation(
+            "Invalid search",
+            serde_json::json!({ "q": format!("must be at most {} characters", MAX_QUERY_LENGTH) }),
+        ));
+    }
+
+    let types = match params.types.as_deref() {
+        None | Some("") => SearchResultType::ALL.to_vec(),
+        Some(types) => {
+            let mut parsed = Vec::new();
+            for name in types.split(',').map(str::trim) {
+                let kind = SearchResultType::parse(name).ok_or_else(|| {
+                    AppError::validation(
+                        "Invalid search",
+                        serde_json::json!({ "type": format!("unknown type {}, expected problem, solution or resource", name) }),
+                    )
+                })?;
+                if !parsed.contains(&kind) {
+                    parsed.push(kind);
+                }
+            }
+            parsed
+        }
+    };
+
+    Ok(SearchQuery {
+        text: text.to_string(),
+        viewer,
+        types,
+        limit: params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+        offset: params.offset.unwrap_or(0).max(0),
+    })
+}
+
+// GET /api/search?q=binary+search&type=problem,solution&limit=20&offset=0
+// `q` accepts web search syntax: "quoted phrases", -excluded words and `or`.
+async fn search(
+    search: web::Data<dyn SearchRepository>,
+    user: AuthenticatedUser,
+    params: web::Query<SearchParams>,
+) -> Result<HttpResponse, AppError> {
+    let query = parse_params(&params, user.id)?;
+    let (results, total) = search.search(&query).await?;
+
+    Ok(HttpResponse::Ok().json(SearchResponse {
+        query: query.text,
+        results,
+        total,
+        limit: query.limit,
+        offset: query.offset,
+    }))
+}
@@ -1,129 +1,176 @@
-use actix_web::{web, HttpResponse, Error};
-use sqlx::PgPool;
+use actix_web::{web, HttpResponse};
+use chrono::{Datelike, Duration, NaiveDate, Utc};
 
-use crate::models::streak::{Streak, UpdateStreak};
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::models::activity::{Heatmap, HeatmapParams};
+use crate::models::streak::streak_day;
 use crate::middleware::AuthenticatedUser;
+use crate::repository::{ActivityRepository, StreakRepository, UserRepository};
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api/streaks")
             .route("", web::get().to(get_streak))
-            .route("", web::patch().to(update_streak))
             .route("/stats", web::get().to(get_streak_stats))
-            .route("/update-for-problem", web::post().to(update_streak_for_problem))
+            .route("/heatmap", web::get().to(get_activity_heatmap))
+            .route("/recompute", web::post().to(recompute_streak))
             .route("/leaderboard", web::get().to(get_streak_leaderboard))
     );
 }
 
 async fn get_streak(
-    pool: web::Data<PgPool>,
+    streaks: web::Data<dyn StreakRepository>,
     user: AuthenticatedUser,
-) -> Result<HttpResponse, Error> {
-    let streak = Streak::get_by_user_id(&**pool, user.id)
-        .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+) -> Result<HttpResponse, AppError> {
+    let streak = streaks.find_for_user(user.id).await?;
 
     match streak {
         Some(s) => Ok(HttpResponse::Ok().json(s)),
         None => {
             // Create a new streak if none exists
-            let new_streak = Streak::create_new(&**pool, user.id)
-                .await
-                .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+            let new_streak = streaks.create(user.id).await?;
             Ok(HttpResponse::Ok().json(new_streak))
         }
     }
 }
 
-async fn update_streak(
-    pool: web::Data<PgPool>,
-    user: AuthenticatedUser,
-    payload: web::Json<UpdateStreak>,
-) -> Result<HttpResponse, Error> {
-    let streak = sqlx::query_as::<_, Streak>(
-        "UPDATE streaks 
-         SET count = $1 
-         WHERE user_id = $2 
-         RETURNING *"
-    )
-    .bind(payload.count)
-    .bind(user.id)
-    .fetch_optional(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?
-    .ok_or_else(|| actix_web::error::ErrorNotFound("Streak not found"))?;
-
-    Ok(HttpResponse::Ok().json(streak))
-}
+// Used freezes listed in the stats
+const FREEZE_HISTORY_LIMIT: i64 = 30;
 
 async fn get_streak_stats(
-    pool: web::Data<PgPool>,
+    streaks: web::Data<dyn StreakRepository>,
+    activity: web::Data<dyn ActivityRepository>,
+    users: web::Data<dyn UserRepository>,
+    config: web::Data<AppConfig>,
     user: AuthenticatedUser,
-) -> Result<HttpResponse, Error> {
-    let streak = Streak::get_by_user_id(&**pool, user.id)
-        .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-
-    let streak = match streak {
+) -> Result<HttpResponse, AppError> {
+    let tz = users
+        .find_by_id(user.id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?
+        .tz();
+
+    let streak = match streaks.find_for_user(user.id).await? {
         Some(s) => s,
-        None => {
-            Streak::create_new(&**pool, user.id)
-                .await
-                .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?
-        }
+        None => streaks.create(user.id).await?,
     };
 
-    let stats = streak
-        .get_stats(&**pool)
-        .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    let since = streak_day(Utc::now(), tz, &config.streaks) - Duration::days(30);
+    let active_days: Vec<NaiveDate> =
+        activity.solve_days(user.id, Some(since)).await?.into_iter().map(|a| a.day).collect();
+    let freezes_used = streaks.freezes_used(user.id, FREEZE_HISTORY_LIMIT).await?;
+
+    Ok(HttpResponse::Ok().json(streak.stats(&active_days, freezes_used, tz, &config.streaks)))
+}
 
-    Ok(HttpResponse::Ok().json(stats))
+// Every day of ?year= (the current year by default) with what the caller did on it
+async fn get_activity_heatmap(
+    activity: web::Data<dyn ActivityRepository>,
+    users: web::Data<dyn UserRepository>,
+    config: web::Data<AppConfig>,
+    user: AuthenticatedUser,
+    params: web::Query<HeatmapParams>,
+) -> Result<HttpResponse, AppError> {
+    let tz = users
+        .find_by_id(user.id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?
+        .tz();
+
+    let year = params.year.unwrap_or_else(|| streak_day(Utc::now(), tz, &config.streaks).year());
+    let (from, to) = NaiveDate::from_ymd_opt(year, 1, 1)
+        .zip(NaiveDate::from_ymd_opt(year, 12, 31))
+        .filter(|_| (2000..=9999).contains(&year))
+        .ok_or_else(|| AppError::validation("Invalid year", serde_json::json!({ "year": "must be between 2000 and 9999" })))?;
+
+    let days = activity.range(user.id, from, to).await?;
+
+    Ok(HttpResponse::Ok().json(Heatmap::new(year, days)))
 }
 
-async fn update_streak_for_problem(
-    pool: web::Data<PgPool>,
+// Rebuild the caller's streak from their activity ledger
+async fn recompute_streak(
+    streaks: web::Data<dyn StreakRepository>,
     user: AuthenticatedUser,
-) -> Result<HttpResponse, Error> {
-    let streak = Streak::update_for_problem_solve(&**pool, user.id)
-        .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+) -> Result<HttpResponse, AppError> {
+    let derived = streaks.derive(user.id).await?;
+    let streak = streaks
+        .apply_derived(user.id, &derived)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Streak not found".into()))?;
 
     Ok(HttpResponse::Ok().json(streak))
 }
 
 async fn get_streak_leaderboard(
-    pool: web::Data<PgPool>,
+    streaks: web::Data<dyn StreakRepository>,
+    config: web::Data<AppConfig>,
     _user: AuthenticatedUser,
-) -> Result<HttpResponse, Error> {
-    #[derive(serde::Serialize)]
-    struct LeaderboardEntry {
-        username: String,
-        current_streak: i32,
-        longest_streak: i32,
-        problems_solved_today: i32,
+) -> Result<HttpResponse, AppError> {
+    let leaderboard = streaks.leaderboard(50, &config.streaks).await?;
+
+    Ok(HttpResponse::Ok().json(leaderboard))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::{Method, StatusCode};
+    use serde_json::{json, Value};
+
+    use crate::test_app::{create_problem, init, register, request, send};
+
+    #[actix_web::test]
+    async fn solves_build_the_streak_and_recompute_agrees() {
+        let app = init().await;
+        let ada = register(&app, "ada").await;
+        let bob = register(&app, "bob").await;
+
+        let mut accepts = Vec::new();
+        for _ in 0..2 {
+            let problem = create_problem(&app, &ada, json!({})).await;
+            let (_, solution) = send(
+                &app,
+                request(Method::POST, &format!("/api/problems/{}/solutions", problem), Some(&bob.token))
+                    .set_json(json!({ "solution_text": "answer" })),
+            )
+            .await;
+            let accept = format!("/api/problems/{}/solutions/{}/accept", problem, solution["id"].as_str().unwrap());
+            let (status, _) = send(&app, request(Method::POST, &accept, Some(&ada.token))).await;
+            assert_eq!(status, StatusCode::OK);
+            accepts.push(accept);
+        }
+
+        // Accepting the same problem's solution again isn't a new solve
+        send(&app, request(Method::DELETE, &accepts[0], Some(&ada.token))).await;
+        send(&app, request(Method::POST, &accepts[0], Some(&ada.token))).await;
+
+        let (_, streak) = send(&app, request(Method::GET, "/api/streaks", Some(&bob.token))).await;
+        assert_eq!((streak["count"].clone(), streak["problems_solved_today"].clone()), (json!(1), json!(2)));
+
+        let (status, recomputed) = send(&app, request(Method::POST, "/api/streaks/recompute", Some(&bob.token))).await;
+        assert_eq!(status, StatusCode::OK);
+        for field in ["count", "longest_streak", "problems_solved_today"] {
+            assert_eq!(recomputed[field], streak[field], "{}", field);
+        }
+
+        let (status, stats) = send(&app, request(Method::GET, "/api/streaks/stats", Some(&bob.token))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(stats["current_streak"], 1);
+        assert_eq!(stats["weekly_activity"].as_array().unwrap().last(), Some(&Value::Bool(true)));
     }
 
-    let leaderboard = sqlx::query!(
-        "SELECT u.username, s.count as current_streak, s.longest_streak, s.problems_solved_today
-         FROM streaks s
-         JOIN users u ON s.user_id = u.id
-         ORDER BY s.count DESC, s.longest_streak DESC
-         LIMIT 50"
-    )
-    .fetch_all(&**pool)
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-
-    let leaderboard: Vec<LeaderboardEntry> = leaderboard
-        .into_iter()
-        .map(|row| LeaderboardEntry {
-            username: row.username,
-            current_streak: row.current_streak,
-            longest_streak: row.longest_streak.unwrap_or(1),
-            problems_solved_today: row.problems_solved_today.unwrap_or(0),
-        })
-        .collect();
+    #[actix_web::test]
+    async fn streak_without_solves_is_empty() {
+        let app = init().await;
+        let ada = register(&app, "ada").await;
 
-    Ok(HttpResponse::Ok().json(leaderboard))
-}
\ No newline at end of file
+        let (status, stats) = send(&app, request(Method::GET, "/api/streaks/stats", Some(&ada.token))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(stats["current_streak"], 0);
+
+        let (status, recomputed) = send(&app, request(Method::POST, "/api/streaks/recompute", Some(&ada.token))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(recomputed["count"], 0);
+    }
+}
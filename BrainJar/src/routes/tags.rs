@@ -0,0 +1,48 @@
+use actix_web::{web, HttpResponse};
+
+use crate::error::AppError;
+use crate::middleware::AuthenticatedUser;
+use crate::models::tag::{normalize_tag, TagQuery, MAX_TAGS_PER_PROBLEM};
+use crate::repository::TagRepository;
+
+const DEFAULT_LIMIT: i64 = 10;
+const MAX_LIMIT: i64 = 50;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/tags", web::get().to(autocomplete));
+}
+
+// Normalized and deduplicated, or what's wrong with them
+pub(crate) fn normalize_tags(raw: &[String]) -> Result<Vec<String>, String> {
+    let mut tags = Vec::new();
+    for tag in raw {
+        let name = normalize_tag(tag).ok_or_else(|| format!("'{}' has no letters or digits", tag))?;
+        if !tags.contains(&name) {
+            tags.push(name);
+        }
+    }
+
+    if tags.len() > MAX_TAGS_PER_PROBLEM {
+        return Err(format!("at most {} tags per problem", MAX_TAGS_PER_PROBLEM));
+    }
+    Ok(tags)
+}
+
+// Tags from a create, update or PUT .../tags body
+pub(crate) fn parse_tags(raw: &[String]) -> Result<Vec<String>, AppError> {
+    normalize_tags(raw).map_err(|message| AppError::validation("Invalid tags", serde_json::json!({ "tags": message })))
+}
+
+// Tags starting with ?q= (or one of their aliases), most used first
+async fn autocomplete(
+    tags: web::Data<dyn TagRepository>,
+    user: AuthenticatedUser,
+    query: web::Query<TagQuery>,
+) -> Result<HttpResponse, AppError> {
+    let prefix = query.q.as_deref().and_then(normalize_tag);
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let tags = tags.search(prefix.as_deref(), user.id, limit).await?;
+
+    Ok(HttpResponse::Ok().json(tags))
+}
@@ -0,0 +1,91 @@
+use actix_web::{web, HttpResponse};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::middleware::AuthenticatedUser;
+use crate::models::access_token::{
+    is_valid_scope, CreateAccessToken, CreatedAccessToken, MAX_TOKENS_PER_USER, SCOPES,
+};
+use crate::repository::AccessTokenRepository;
+
+const MAX_EXPIRY_DAYS: i64 = 365;
+
+// Personal access tokens can't reach these routes, so a leaked token cannot mint more tokens
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/tokens")
+            .route("", web::get().to(list_tokens))
+            .route("", web::post().to(create_token))
+            .route("/scopes", web::get().to(list_scopes))
+            .route("/{id}", web::delete().to(revoke_token))
+    );
+}
+
+async fn list_scopes() -> HttpResponse {
+    HttpResponse::Ok().json(SCOPES)
+}
+
+async fn list_tokens(
+    tokens: web::Data<dyn AccessTokenRepository>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let tokens = tokens.list_for_user(user.id).await?;
+
+    Ok(HttpResponse::Ok().json(tokens))
+}
+
+async fn create_token(
+    tokens: web::Data<dyn AccessTokenRepository>,
+    user: AuthenticatedUser,
+    payload: web::Json<CreateAccessToken>,
+) -> Result<HttpResponse, AppError> {
+    // Report every invalid field at once, keyed by field name
+    let mut errors = serde_json::Map::new();
+    let name = payload.name.trim();
+    if name.is_empty() || name.len() > 100 {
+        errors.insert("name".into(), "must be between 1 and 100 characters".into());
+    }
+
+    if payload.scopes.is_empty() {
+        errors.insert("scopes".into(), "at least one scope is required".into());
+    } else if let Some(scope) = payload.scopes.iter().find(|s| !is_valid_scope(s)) {
+        errors.insert("scopes".into(), format!("unknown scope: {}", scope).into());
+    }
+
+    if payload.expires_in_days.is_some_and(|days| !(1..=MAX_EXPIRY_DAYS).contains(&days)) {
+        errors.insert("expires_in_days".into(), format!("must be between 1 and {}", MAX_EXPIRY_DAYS).into());
+    }
+
+    if !errors.is_empty() {
+        return Err(AppError::validation("Invalid access token request", errors.into()));
+    }
+
+    let active = tokens.count_active_for_user(user.id).await?;
+    if active >= MAX_TOKENS_PER_USER {
+        return Err(AppError::BadRequest("Too many active access tokens, revoke one first".into()));
+    }
+
+    let mut scopes = payload.scopes.clone();
+    scopes.sort();
+    scopes.dedup();
+
+    let (access_token, token) =
+        tokens.create(user.id, name, &scopes, payload.expires_in_days).await?;
+
+    tracing::info!("Personal access token {} created for user {}", access_token.id, user.id);
+    Ok(HttpResponse::Created().json(CreatedAccessToken { access_token, token }))
+}
+
+async fn revoke_token(
+    tokens: web::Data<dyn AccessTokenRepository>,
+    user: AuthenticatedUser,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let revoked = tokens.revoke(path.into_inner(), user.id).await?;
+
+    if !revoked {
+        return Err(AppError::NotFound("Access token not found".into()));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,214 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::middleware::AuthenticatedUser;
+use crate::models::activity::ActivityKind;
+use crate::models::problem::{CreateProblem, Difficulty};
+use crate::models::solution::SolutionSort;
+use crate::models::transfer::{ExportParams, ExportedProblem, ImportParams, ImportReport, ImportRow, ImportRowReport, TransferFormat};
+use crate::repository::{ActivityRepository, ProblemRepository, SolutionRepository};
+use crate::routes::tags::normalize_tags;
+use crate::transfer;
+
+const MAX_IMPORT_ROWS: usize = 500;
+const MAX_TITLE_LENGTH: usize = 255;
+const MAX_CATEGORY_LENGTH: usize = 50;
+
+// Registered ahead of the /api/problems scope, which would otherwise take these paths
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/api/problems/import").route(web::post().to(import_problems)))
+        .service(web::resource("/api/problems/export").route(web::get().to(export_problems)));
+}
+
+// A problem ready to create, or the errors by field
+fn validate_row(row: ImportRow) -> Result<CreateProblem, serde_json::Map<String, serde_json::Value>> {
+    let mut errors = serde_json::Map::new();
+
+    let title = row.title.unwrap_or_default();
+    if title.is_empty() {
+        errors.insert("title".into(), "must not be empty".into());
+    } else if title.chars().count() > MAX_TITLE_LENGTH {
+        errors.insert("title".into(), format!("must be at most {} characters", MAX_TITLE_LENGTH).into());
+    }
+
+    let description = row.description.unwrap_or_default();
+    if description.is_empty() {
+        errors.insert("description".into(), "must not be empty".into());
+    }
+
+    let category = row.category.unwrap_or_default();
+    if category.is_empty() {
+        errors.insert("category".into(), "must not be empty".into());
+    } else if category.chars().count() > MAX_CATEGORY_LENGTH {
+        errors.insert("category".into(), format!("must be at most {} characters", MAX_CATEGORY_LENGTH).into());
+    }
+
+    let difficulty = row.difficulty.as_deref().map(|name| {
+        Difficulty::parse(name).ok_or_else(|| {
+            errors.insert("difficulty".into(), format!("'{}' is not easy, medium or hard", name).into());
+        })
+    });
+
+    let tags = normalize_tags(&row.tags).map_err(|message| {
+        errors.insert("tags".into(), message.into());
+    });
+
+    if let Some(link) = row.links.iter().find(|l| !(l.starts_with("https://") || l.starts_with("http://"))) {
+        errors.insert("links".into(), format!("'{}' is not an http(s) URL", link).into());
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(CreateProblem {
+        title,
+        description,
+        category,
+        visibility: None,
+        difficulty_level: difficulty.transpose().ok().flatten(),
+        documentation_links: Some(row.links).filter(|links| !links.is_empty()),
+        tags: tags.ok(),
+        category_ids: None,
+    })
+}
+
+// Validates every row and, unless ?dry_run=true, creates them all in one go. A file with
+// any invalid row imports nothing and gets the report back with 422.
+async fn import_problems(
+    problems: web::Data<dyn ProblemRepository>,
+    activity: web::Data<dyn ActivityRepository>,
+    config: web::Data<AppConfig>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+    params: web::Query<ImportParams>,
+    body: web::Bytes,
+) -> Result<HttpResponse, AppError> {
+    let content_type = req.headers().get("Content-Type").and_then(|v| v.to_str().ok());
+    let format = params
+        .format
+        .or_else(|| content_type.and_then(TransferFormat::from_content_type))
+        .ok_or_else(|| AppError::BadRequest("Pass ?format=json, csv or markdown".into()))?;
+
+    let text = std::str::from_utf8(&body).map_err(|_| AppError::BadRequest("The import must be UTF-8 text".into()))?;
+    let rows = transfer::parse(format, text)
+        .map_err(|message| AppError::validation("Invalid import file", serde_json::json!({ "file": message })))?;
+
+    if rows.is_empty() {
+        return Err(AppError::validation("Invalid import file", serde_json::json!({ "file": "contains no problems" })));
+    }
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Err(AppError::validation(
+            "Invalid import file",
+            serde_json::json!({ "file": format!("at most {} problems per import", MAX_IMPORT_ROWS) }),
+        ));
+    }
+
+    let mut report = ImportReport { dry_run: params.dry_run, total: rows.len(), valid: 0, imported: 0, rows: Vec::new() };
+    let mut valid = Vec::new();
+    for (i, row) in rows.into_iter().enumerate() {
+        let title = row.title.clone();
+        let errors = match validate_row(row) {
+            Ok(problem) => {
+                valid.push(problem);
+                serde_json::Map::new()
+            }
+            Err(errors) => errors,
+        };
+        report.rows.push(ImportRowReport { row: i + 1, title, errors, problem_id: None });
+    }
+    report.valid = valid.len();
+
+    if params.dry_run {
+        return Ok(HttpResponse::Ok().json(report));
+    }
+    if report.valid < report.total {
+        return Ok(HttpResponse::UnprocessableEntity().json(report));
+    }
+
+    let created = problems.import(user.id, &valid).await?;
+    for (row, problem) in report.rows.iter_mut().zip(&created) {
+        row.problem_id = Some(problem.id);
+    }
+    report.imported = created.len();
+    activity.record(user.id, ActivityKind::ProblemCreated, report.imported as i32, &config.streaks).await?;
+
+    tracing::info!("User {} imported {} problems", user.id, report.imported);
+    Ok(HttpResponse::Created().json(report))
+}
+
+// The caller's problems with the solutions posted on them, then the problems of others the
+// caller answered with just the caller's solutions, as a download
+async fn export_problems(
+    problems: web::Data<dyn ProblemRepository>,
+    solutions: web::Data<dyn SolutionRepository>,
+    user: AuthenticatedUser,
+    params: web::Query<ExportParams>,
+) -> Result<HttpResponse, AppError> {
+    let format = params.format.unwrap_or_default();
+
+    let owned = problems.owned_by(user.id).await?;
+    let answered = problems.answered_by(user.id).await?;
+    let ids: Vec<Uuid> = owned.iter().chain(&answered).map(|p| p.id).collect();
+    let mut by_problem = solutions.list_for_problems(&ids, SolutionSort::Top).await?;
+
+    let mut exported = Vec::with_capacity(ids.len());
+    for problem in owned {
+        let problem_solutions = by_problem.remove(&problem.id).unwrap_or_default();
+        exported.push(ExportedProblem::new(problem, true, problem_solutions));
+    }
+    for problem in answered {
+        let mut problem_solutions = by_problem.remove(&problem.id).unwrap_or_default();
+        problem_solutions.retain(|s| s.user_id == user.id);
+        exported.push(ExportedProblem::new(problem, false, problem_solutions));
+    }
+
+    let body = transfer::render(format, &exported).map_err(AppError::Internal)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"brainjar-problems.{}\"", format.extension()),
+        ))
+        .body(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::{Method, StatusCode};
+    use serde_json::{json, Value};
+
+    use crate::test_app::{create_problem, create_solution, init, register, request, send};
+
+    fn solution_texts(problem: &Value) -> Vec<&str> {
+        problem["solutions"].as_array().unwrap().iter().map(|s| s["solution_text"].as_str().unwrap()).collect()
+    }
+
+    #[actix_web::test]
+    async fn export_includes_the_callers_answers_to_other_problems() {
+        let app = init().await;
+        let ada = register(&app, "ada").await;
+        let bob = register(&app, "bob").await;
+        let own = create_problem(&app, &ada, json!({ "title": "Ada's" })).await;
+        let bobs = create_problem(&app, &bob, json!({ "title": "Bob's" })).await;
+        let unanswered = create_problem(&app, &bob, json!({ "title": "Unanswered" })).await;
+        create_solution(&app, &bob, own, "bob on ada's").await;
+        create_solution(&app, &ada, bobs, "ada on bob's").await;
+        create_solution(&app, &bob, bobs, "bob on bob's").await;
+        create_solution(&app, &bob, unanswered, "bob alone").await;
+
+        let (status, body) = send(&app, request(Method::GET, "/api/problems/export", Some(&ada.token))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let problems = body.as_array().unwrap();
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0]["title"], "Ada's");
+        assert_eq!(problems[0]["owned"], true);
+        assert_eq!(solution_texts(&problems[0]), ["bob on ada's"]);
+        assert_eq!(problems[1]["title"], "Bob's");
+        assert_eq!(problems[1]["owned"], false);
+        assert_eq!(solution_texts(&problems[1]), ["ada on bob's"]);
+    }
+}
@@ -0,0 +1,32 @@
+// Rebuilds every stored streak from its history (the activity ledger and the freezes used)
+// and reports the ones that disagree, e.g. counts set through the old client endpoints.
+// Runs from POST /api/admin/streaks/recompute or `backend recompute-streaks [--dry-run]`.
+
+use crate::models::streak::{StreakAudit, StreakDiscrepancy};
+use crate::repository::{RepoResult, StreakRepository};
+
+// Unless `dry_run`, streaks that disagree are overwritten with their derived counts
+pub async fn recompute_streaks(streaks: &dyn StreakRepository, dry_run: bool) -> RepoResult<StreakAudit> {
+    let stored = streaks.all().await?;
+    let mut audit = StreakAudit { dry_run, checked: stored.len(), repaired: 0, discrepancies: Vec::new() };
+
+    for streak in stored {
+        let derived = streaks.derive(streak.user_id).await?;
+        if derived.count == streak.count && derived.longest_streak == streak.longest_streak {
+            continue;
+        }
+
+        audit.discrepancies.push(StreakDiscrepancy {
+            user_id: streak.user_id,
+            stored_count: streak.count,
+            derived_count: derived.count,
+            stored_longest_streak: streak.longest_streak,
+            derived_longest_streak: derived.longest_streak,
+        });
+        if !dry_run && streaks.apply_derived(streak.user_id, &derived).await?.is_some() {
+            audit.repaired += 1;
+        }
+    }
+
+    Ok(audit)
+}
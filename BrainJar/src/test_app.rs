@@ -0,0 +1,133 @@
+// Handler test setup: the whole API over a fresh in-memory store, wired like main() minus
+// CORS, logging and rate limiting
+
+use std::sync::Arc;
+
+use actix_http::Request;
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::http::{Method, StatusCode};
+use actix_web::middleware::from_fn;
+use actix_web::{test, web, App, Error};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::config::{AppConfig, JwtConfig};
+use crate::mailer::{InMemoryMailer, Mailer};
+use crate::{error, jwt, repository, routes};
+
+pub const PASSWORD: &str = "correct horse battery";
+
+// A registered user with a live login session
+pub struct TestUser {
+    pub id: Uuid,
+    pub token: String,
+    pub refresh_token: String,
+}
+
+pub fn config() -> AppConfig {
+    AppConfig {
+        jwt: JwtConfig { secret: "test-secret".to_string(), ..Default::default() },
+        ..Default::default()
+    }
+}
+
+pub async fn init() -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error> {
+    let config = config();
+    let keys = jwt::JwtKeys::load(&config.jwt).unwrap();
+    let mailer: Arc<dyn Mailer> = Arc::new(InMemoryMailer::default());
+    let repositories = repository::Repositories::in_memory();
+
+    test::init_service(
+        App::new()
+            .wrap(from_fn(error::request_id))
+            .app_data(web::Data::from(mailer))
+            .app_data(web::Data::new(config))
+            .app_data(web::Data::new(keys))
+            .app_data(web::JsonConfig::default().error_handler(|e, _| error::extractor_error(e)))
+            .app_data(web::QueryConfig::default().error_handler(|e, _| error::extractor_error(e)))
+            .app_data(web::PathConfig::default().error_handler(|e, _| error::extractor_error(e)))
+            .configure(|cfg| {
+                repositories.configure(cfg);
+                routes::configure(cfg);
+            })
+            .default_service(web::to(error::not_found)),
+    )
+    .await
+}
+
+// A request with the token as its bearer, when there is one
+pub fn request(method: Method, path: &str, token: Option<&str>) -> test::TestRequest {
+    let req = test::TestRequest::default().method(method).uri(path);
+    match token {
+        Some(token) => req.insert_header(("Authorization", format!("Bearer {}", token))),
+        None => req,
+    }
+}
+
+// The status and JSON body, Null when the body is empty
+pub async fn send<S, B>(app: &S, req: test::TestRequest) -> (StatusCode, Value)
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    let res = test::call_service(app, req.to_request()).await;
+    let status = res.status();
+    let body = test::read_body(res).await;
+    let value = if body.is_empty() { Value::Null } else { serde_json::from_slice(&body).unwrap() };
+    (status, value)
+}
+
+pub async fn register<S, B>(app: &S, username: &str) -> TestUser
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    let email = format!("{}@example.com", username);
+    let (status, _) = send(
+        app,
+        request(Method::POST, "/api/auth/register", None)
+            .set_json(json!({ "username": username, "email": email, "password": PASSWORD })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "registering {}", username);
+
+    let (status, body) = send(
+        app,
+        request(Method::POST, "/api/auth/login", None).set_json(json!({ "email": email, "password": PASSWORD })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "logging in {}", username);
+
+    TestUser {
+        id: body["user"]["id"].as_str().unwrap().parse().unwrap(),
+        token: body["token"].as_str().unwrap().to_string(),
+        refresh_token: body["refresh_token"].as_str().unwrap().to_string(),
+    }
+}
+
+// Create a problem owned by `user` and return its id
+pub async fn create_problem<S, B>(app: &S, user: &TestUser, problem: Value) -> Uuid
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    let mut body = json!({ "title": "Two sum", "description": "Find two numbers", "category": "arrays" });
+    body.as_object_mut().unwrap().extend(problem.as_object().cloned().unwrap_or_default());
+
+    let (status, created) = send(app, request(Method::POST, "/api/problems", Some(&user.token)).set_json(body)).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", created);
+    created["id"].as_str().unwrap().parse().unwrap()
+}
+
+// Post `user`'s solution to a problem
+pub async fn create_solution<S, B>(app: &S, user: &TestUser, problem: Uuid, text: &str)
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    let path = format!("/api/problems/{}/solutions", problem);
+    let body = json!({ "solution_text": text });
+    let (status, created) = send(app, request(Method::POST, &path, Some(&user.token)).set_json(body)).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", created);
+}
@@ -0,0 +1,130 @@
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+
+// RFC 6238 defaults understood by every authenticator app
+const PERIOD_SECONDS: i64 = 30;
+const DIGITS: u32 = 6;
+// Accept codes from one step either side to tolerate clock drift
+const ALLOWED_DRIFT_STEPS: i64 = 1;
+
+pub const ISSUER: &str = "BrainJar";
+
+// 160-bit secret, base32 encoded as expected by authenticator apps
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    BASE32_NOPAD.encode(&bytes)
+}
+
+// otpauth:// URI that the frontend renders as a QR code
+pub fn provisioning_uri(secret: &str, account_name: &str) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={digits}&period={period}",
+        issuer = percent_encode(ISSUER),
+        account = percent_encode(account_name),
+        secret = secret,
+        digits = DIGITS,
+        period = PERIOD_SECONDS,
+    )
+}
+
+pub fn current_step(unix_time: i64) -> i64 {
+    unix_time / PERIOD_SECONDS
+}
+
+fn code_at_step(key: &[u8], step: i64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(&(step as u64).to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    // Dynamic truncation (RFC 4226 section 5.3)
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = ((digest[offset] as u32 & 0x7f) << 24)
+        | ((digest[offset + 1] as u32) << 16)
+        | ((digest[offset + 2] as u32) << 8)
+        | (digest[offset + 3] as u32);
+
+    binary % 10u32.pow(DIGITS)
+}
+
+// Check a code against the secret and return the time step it matched.
+// Steps at or before `last_used_step` are rejected so a code cannot be replayed.
+pub fn verify(secret: &str, code: &str, unix_time: i64, last_used_step: Option<i64>) -> Option<i64> {
+    let code = code.trim().replace(' ', "");
+    if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+
+    let now_step = current_step(unix_time);
+    (now_step - ALLOWED_DRIFT_STEPS..=now_step + ALLOWED_DRIFT_STEPS)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| code_at_step(&key, *step) == code)
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The RFC 6238 SHA1 test key, "12345678901234567890"
+    const SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn matches_the_rfc_test_vectors() {
+        // The RFC lists 8 digit codes; these are their last 6 digits
+        for (time, code) in [(59, "287082"), (1111111109, "081804"), (1234567890, "005924"), (2000000000, "279037")] {
+            assert_eq!(verify(SECRET, code, time, None), Some(current_step(time)), "at {}", time);
+        }
+    }
+
+    #[test]
+    fn accepts_one_step_of_drift() {
+        assert_eq!(verify(SECRET, "287082", 59 + PERIOD_SECONDS, None), Some(1));
+        assert_eq!(verify(SECRET, "287082", 59 + 2 * PERIOD_SECONDS, None), None);
+    }
+
+    #[test]
+    fn rejects_replayed_steps() {
+        assert_eq!(verify(SECRET, "287082", 59, Some(0)), Some(1));
+        assert_eq!(verify(SECRET, "287082", 59, Some(1)), None);
+    }
+
+    #[test]
+    fn ignores_spaces_and_rejects_malformed_codes() {
+        assert_eq!(verify(SECRET, " 287 082 ", 59, None), Some(1));
+        assert_eq!(verify(SECRET, "28708", 59, None), None);
+        assert_eq!(verify(SECRET, "28708a", 59, None), None);
+        assert_eq!(verify("not base32!", "287082", 59, None), None);
+    }
+
+    #[test]
+    fn generated_secrets_verify_their_own_codes() {
+        let secret = generate_secret();
+        let key = BASE32_NOPAD.decode(secret.as_bytes()).unwrap();
+        let code = format!("{:06}", code_at_step(&key, current_step(1_700_000_000)));
+
+        assert_eq!(key.len(), 20);
+        assert_eq!(verify(&secret, &code, 1_700_000_000, None), Some(current_step(1_700_000_000)));
+    }
+
+    #[test]
+    fn provisioning_uri_encodes_the_account() {
+        let uri = provisioning_uri(SECRET, "ada lovelace@example.com");
+
+        assert!(uri.starts_with("otpauth://totp/BrainJar:ada%20lovelace%40example.com?secret=GEZDGNBV"));
+        assert!(uri.ends_with("&issuer=BrainJar&algorithm=SHA1&digits=6&period=30"));
+    }
+}
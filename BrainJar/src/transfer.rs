@@ -0,0 +1,473 @@
+// Reading and writing problem sets as JSON, CSV or Markdown. Parsing only deals with the
+// file format; the fields come out as found and are validated row by row by the import
+// handler, so one bad row doesn't hide the problems with the others.
+
+use crate::models::transfer::{ExportedProblem, ImportRow, TransferFormat};
+
+// Markdown exports append the solutions under this heading; imports drop everything from
+// it to the end of the document
+const SOLUTIONS_HEADING: &str = "## Solutions";
+
+pub fn parse(format: TransferFormat, text: &str) -> Result<Vec<ImportRow>, String> {
+    match format {
+        TransferFormat::Json => parse_json(text),
+        TransferFormat::Csv => parse_csv(text),
+        TransferFormat::Markdown => parse_markdown(text),
+    }
+}
+
+pub fn render(format: TransferFormat, problems: &[ExportedProblem]) -> Result<String, String> {
+    match format {
+        TransferFormat::Json => serde_json::to_string_pretty(problems).map_err(|e| e.to_string()),
+        TransferFormat::Csv => render_csv(problems),
+        TransferFormat::Markdown => Ok(render_markdown(problems)),
+    }
+}
+
+// "a, b; c" -> [a, b, c]
+fn split_list(value: &str, separators: &[char]) -> Vec<String> {
+    value
+        .split(separators)
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    Some(value.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+// An array of objects with title, description, category, difficulty, tags and links.
+// Lists may also be given as comma separated strings.
+fn parse_json(text: &str) -> Result<Vec<ImportRow>, String> {
+    let value: serde_json::Value = serde_json::from_str(text).map_err(|e| format!("invalid JSON: {}", e))?;
+    let items = value.as_array().ok_or("expected a JSON array of problems")?;
+
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let object = item.as_object().ok_or_else(|| format!("row {}: expected an object", i + 1))?;
+            let text = |field: &str| match object.get(field) {
+                None | Some(serde_json::Value::Null) => Ok(None),
+                Some(serde_json::Value::String(s)) => Ok(non_empty(s)),
+                Some(_) => Err(format!("row {}: {} must be a string", i + 1, field)),
+            };
+            let list = |field: &str| match object.get(field) {
+                None | Some(serde_json::Value::Null) => Ok(Vec::new()),
+                Some(serde_json::Value::String(s)) => Ok(split_list(s, &[','])),
+                Some(serde_json::Value::Array(items)) => items
+                    .iter()
+                    .map(|item| {
+                        item.as_str()
+                            .map(str::to_string)
+                            .ok_or_else(|| format!("row {}: {} must only contain strings", i + 1, field))
+                    })
+                    .collect(),
+                Some(_) => Err(format!("row {}: {} must be a list", i + 1, field)),
+            };
+
+            Ok(ImportRow {
+                title: text("title")?,
+                description: text("description")?,
+                category: text("category")?,
+                difficulty: text("difficulty")?,
+                tags: list("tags")?,
+                links: list("links")?,
+            })
+        })
+        .collect()
+}
+
+// A header row naming the columns, in any order and case. title, description and category
+// are required; tags are separated by commas or semicolons, links by semicolons or spaces.
+fn parse_csv(text: &str) -> Result<Vec<ImportRow>, String> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(text.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| format!("invalid CSV: {}", e))?
+        .iter()
+        .map(str::to_lowercase)
+        .collect();
+
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    for required in ["title", "description", "category"] {
+        if column(required).is_none() {
+            return Err(format!("the CSV header has no {} column", required));
+        }
+    }
+    let (title, description, category) = (column("title"), column("description"), column("category"));
+    let (difficulty, tags, links) = (column("difficulty"), column("tags"), column("links"));
+
+    reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(|e| format!("invalid CSV: {}", e))?;
+            let cell = |index: Option<usize>| index.and_then(|i| record.get(i)).unwrap_or_default();
+
+            Ok(ImportRow {
+                title: non_empty(cell(title)),
+                description: non_empty(cell(description)),
+                category: non_empty(cell(category)),
+                difficulty: non_empty(cell(difficulty)),
+                tags: split_list(cell(tags), &[',', ';']),
+                links: split_list(cell(links), &[';', ' ']),
+            })
+        })
+        .collect()
+}
+
+// Double quoted values may escape `"` and `\` with a backslash, as quoted() writes them
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    if let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        let mut unescaped = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            match (c, chars.clone().next()) {
+                ('\\', Some(next @ ('"' | '\\'))) => {
+                    unescaped.push(next);
+                    chars.next();
+                }
+                _ => unescaped.push(c),
+            }
+        }
+        return unescaped;
+    }
+    value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')).unwrap_or(value).to_string()
+}
+
+// A body line that would otherwise end the document (`---`) or the description
+// (SOLUTIONS_HEADING), with any backslashes already in front of it. Exports add one more
+// backslash to these, which Markdown renders as the plain text; imports take it off again.
+fn is_reserved(line: &str) -> bool {
+    let line = line.trim_end().trim_start_matches('\\');
+    line == "---" || line == SOLUTIONS_HEADING
+}
+
+fn escape_body(text: &str) -> String {
+    text.lines()
+        .map(|line| if is_reserved(line) { format!("\\{}", line) } else { line.to_string() })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn unescape_body(line: &str) -> &str {
+    match line.strip_prefix('\\') {
+        Some(rest) if is_reserved(line) => rest,
+        _ => line,
+    }
+}
+
+// [a, "b"] or a, b
+fn inline_list(value: &str) -> Vec<String> {
+    let value = value.trim();
+    let inner = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')).unwrap_or(value);
+    inner
+        .split(',')
+        .map(unquote)
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[derive(Clone, Copy)]
+enum ListField {
+    Tags,
+    Links,
+}
+
+// Documents of front matter between `---` lines followed by the description:
+//
+//   ---
+//   title: Two Sum
+//   category: Arrays
+//   tags: [hash-map, arrays]
+//   links:
+//     - https://example.com/two-sum
+//   ---
+//   Given an array...
+//
+// The next `---` line starts the next document. Descriptions escape their own `---` lines
+// as `\---`, see is_reserved().
+fn parse_markdown(text: &str) -> Result<Vec<ImportRow>, String> {
+    let is_fence = |line: &str| line.trim_end() == "---";
+    let mut lines = text.lines().enumerate().peekable();
+    let mut rows = Vec::new();
+
+    loop {
+        while lines.next_if(|(_, line)| line.trim().is_empty()).is_some() {}
+        let Some((opened_at, line)) = lines.next() else {
+            break;
+        };
+        if !is_fence(line) {
+            return Err(format!("line {}: expected --- to open a problem's front matter", opened_at + 1));
+        }
+
+        let mut row = ImportRow::default();
+        let mut open_list: Option<ListField> = None;
+        let mut closed = false;
+        for (number, line) in lines.by_ref() {
+            if is_fence(line) {
+                closed = true;
+                break;
+            }
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            if let (Some(field), Some(item)) = (open_list, trimmed.strip_prefix("- ")) {
+                let item = unquote(item);
+                match field {
+                    ListField::Tags => row.tags.push(item),
+                    ListField::Links => row.links.push(item),
+                }
+                continue;
+            }
+
+            let (key, value) = trimmed
+                .split_once(':')
+                .ok_or_else(|| format!("line {}: expected key: value", number + 1))?;
+            let value = &unquote(value);
+            open_list = None;
+            match key.trim().to_lowercase().as_str() {
+                "title" => row.title = non_empty(value),
+                "description" => row.description = non_empty(value),
+                "category" => row.category = non_empty(value),
+                "difficulty" => row.difficulty = non_empty(value),
+                "tags" if value.is_empty() => open_list = Some(ListField::Tags),
+                "tags" => row.tags = inline_list(value),
+                "links" if value.is_empty() => open_list = Some(ListField::Links),
+                "links" => row.links = inline_list(value),
+                // Written by exports, not imported
+                _ => {}
+            }
+        }
+        if !closed {
+            return Err(format!("line {}: front matter is never closed with ---", opened_at + 1));
+        }
+
+        let mut body = Vec::new();
+        while let Some((_, line)) = lines.next_if(|(_, line)| !is_fence(line)) {
+            body.push(line);
+        }
+        if let Some(end) = body.iter().position(|line| line.trim_end() == SOLUTIONS_HEADING) {
+            body.truncate(end);
+        }
+        let body: Vec<&str> = body.into_iter().map(unescape_body).collect();
+        if let Some(description) = non_empty(&body.join("\n")) {
+            row.description = Some(description);
+        }
+
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+// One row per problem with the import columns plus solved, visibility, owned, created_at and
+// the number of solutions. The solutions themselves are only in JSON and Markdown exports.
+fn render_csv(problems: &[ExportedProblem]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record([
+            "title", "description", "category", "difficulty", "tags", "links", "solved", "visibility", "owned",
+            "created_at", "solutions",
+        ])
+        .map_err(|e| e.to_string())?;
+
+    for p in problems {
+        writer
+            .write_record([
+                p.title.as_str(),
+                &p.description,
+                &p.category,
+                p.difficulty.map(|d| d.as_str()).unwrap_or_default(),
+                &p.tags.join("; "),
+                &p.links.join("; "),
+                if p.solved { "true" } else { "false" },
+                p.visibility.as_str(),
+                if p.owned { "true" } else { "false" },
+                &p.created_at.to_rfc3339(),
+                &p.solutions.len().to_string(),
+            ])
+            .map_err(|e| e.to_string())?;
+    }
+
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+// Front matter values on one line, quoted so a ':' or '#' in them survives
+fn quoted(value: &str) -> String {
+    let value = value.replace(['\r', '\n'], " ").replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{}\"", value)
+}
+
+fn render_markdown(problems: &[ExportedProblem]) -> String {
+    let mut out = String::new();
+    for p in problems {
+        out.push_str("---\n");
+        out.push_str(&format!("title: {}\n", quoted(&p.title)));
+        out.push_str(&format!("category: {}\n", quoted(&p.category)));
+        if let Some(difficulty) = p.difficulty {
+            out.push_str(&format!("difficulty: {}\n", difficulty.as_str()));
+        }
+        out.push_str(&format!("tags: [{}]\n", p.tags.join(", ")));
+        if !p.links.is_empty() {
+            out.push_str("links:\n");
+            for link in &p.links {
+                out.push_str(&format!("  - {}\n", link));
+            }
+        }
+        out.push_str(&format!("solved: {}\n", p.solved));
+        out.push_str(&format!("visibility: {}\n", p.visibility.as_str()));
+        out.push_str(&format!("owned: {}\n", p.owned));
+        out.push_str(&format!("created_at: {}\n", p.created_at.to_rfc3339()));
+        out.push_str("---\n\n");
+        out.push_str(&escape_body(p.description.trim()));
+        out.push_str("\n\n");
+
+        if !p.solutions.is_empty() {
+            out.push_str(SOLUTIONS_HEADING);
+            out.push_str("\n\n");
+            for s in &p.solutions {
+                let accepted = if s.is_accepted { ", accepted" } else { "" };
+                out.push_str(&format!("### {} (score {}{})\n\n", s.author, s.score, accepted));
+                out.push_str(&escape_body(s.solution_text.trim()));
+                out.push_str("\n\n");
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::models::problem::{Difficulty, Visibility};
+    use crate::models::transfer::ExportedSolution;
+
+    fn exported(title: &str, description: &str, solutions: &[&str]) -> ExportedProblem {
+        ExportedProblem {
+            id: Uuid::new_v4(),
+            title: title.to_string(),
+            description: description.to_string(),
+            category: "Arrays: basics".to_string(),
+            difficulty: Some(Difficulty::Medium),
+            tags: vec!["hash-map".to_string(), "arrays".to_string()],
+            links: vec!["https://example.com/a?b=c#d".to_string()],
+            solved: false,
+            visibility: Visibility::Public,
+            owned: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            solutions: solutions
+                .iter()
+                .map(|text| ExportedSolution {
+                    id: Uuid::new_v4(),
+                    author: "ada".to_string(),
+                    solution_text: text.to_string(),
+                    is_accepted: false,
+                    score: 0,
+                    created_at: Utc::now(),
+                })
+                .collect(),
+        }
+    }
+
+    fn assert_round_trips(format: TransferFormat) {
+        let problems = vec![
+            exported("Two \"sums\", fast", "Line one\nLine two, with a comma", &["use a map"]),
+            exported("Second", "Plain", &[]),
+        ];
+
+        let text = render(format, &problems).unwrap();
+        let rows = parse(format, &text).unwrap();
+
+        assert_eq!(rows.len(), problems.len());
+        for (row, problem) in rows.iter().zip(&problems) {
+            assert_eq!(row.title.as_deref(), Some(problem.title.as_str()));
+            assert_eq!(row.description.as_deref(), Some(problem.description.as_str()));
+            assert_eq!(row.category.as_deref(), Some(problem.category.as_str()));
+            assert_eq!(row.difficulty.as_deref(), Some("medium"));
+            assert_eq!(row.tags, problem.tags);
+            assert_eq!(row.links, problem.links);
+        }
+    }
+
+    #[test]
+    fn json_round_trips() {
+        assert_round_trips(TransferFormat::Json);
+    }
+
+    #[test]
+    fn csv_round_trips() {
+        assert_round_trips(TransferFormat::Csv);
+    }
+
+    #[test]
+    fn json_lists_may_be_comma_separated() {
+        let rows = parse(TransferFormat::Json, r#"[{"title": "t", "tags": "a, b,,c", "links": null}]"#).unwrap();
+
+        assert_eq!(rows[0].tags, ["a", "b", "c"]);
+        assert!(rows[0].links.is_empty());
+        assert_eq!(rows[0].description, None);
+    }
+
+    #[test]
+    fn json_rejects_wrong_types() {
+        let err = parse(TransferFormat::Json, r#"[{"title": "t"}, {"title": 3}]"#).unwrap_err();
+        assert_eq!(err, "row 2: title must be a string");
+
+        assert!(parse(TransferFormat::Json, r#"{"title": "t"}"#).is_err());
+    }
+
+    #[test]
+    fn csv_header_is_case_insensitive_and_requires_columns() {
+        let rows = parse(TransferFormat::Csv, "Category,TITLE,Description,Tags\nc,t,d,a;b\n").unwrap();
+
+        assert_eq!(rows[0].title.as_deref(), Some("t"));
+        assert_eq!(rows[0].tags, ["a", "b"]);
+
+        let err = parse(TransferFormat::Csv, "title,description\nt,d\n").unwrap_err();
+        assert_eq!(err, "the CSV header has no category column");
+    }
+
+    #[test]
+    fn markdown_round_trips_reserved_lines_and_quotes() {
+        let problems = vec![
+            exported(
+                r#"Say "hi" \ # twice"#,
+                "Intro\n---\nmiddle\n## Solutions\n\\---\nend",
+                &["first\n---\nsecond", "## Solutions"],
+            ),
+            exported("Second", "Plain", &[]),
+        ];
+
+        let text = render(TransferFormat::Markdown, &problems).unwrap();
+        let rows = parse(TransferFormat::Markdown, &text).unwrap();
+
+        assert_eq!(rows.len(), 2);
+        for (row, problem) in rows.iter().zip(&problems) {
+            assert_eq!(row.title.as_deref(), Some(problem.title.as_str()));
+            assert_eq!(row.description.as_deref(), Some(problem.description.as_str()));
+            assert_eq!(row.category.as_deref(), Some(problem.category.as_str()));
+            assert_eq!(row.difficulty.as_deref(), Some("medium"));
+            assert_eq!(row.tags, problem.tags);
+            assert_eq!(row.links, problem.links);
+        }
+    }
+
+    #[test]
+    fn markdown_keeps_hand_written_backslashes() {
+        let rows = parse(TransferFormat::Markdown, "---\ntitle: \"C:\\path\"\ncategory: 'x'\n---\nbody\n").unwrap();
+
+        assert_eq!(rows[0].title.as_deref(), Some("C:\\path"));
+        assert_eq!(rows[0].category.as_deref(), Some("x"));
+    }
+}